@@ -0,0 +1,196 @@
+//! Designating investigators and procurators for several reports at once, and
+//! the history of those designations.
+
+use rusqlite::{named_params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::{auth::User, dates, db, error::CommandError, model::get_information, validation};
+
+/// Which side of a report an assignment designates.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AssignmentKind {
+    Investigator,
+    Procurator,
+}
+
+impl AssignmentKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AssignmentKind::Investigator => "investigator",
+            AssignmentKind::Procurator => "procurator",
+        }
+    }
+
+    fn parse(value: &str) -> AssignmentKind {
+        match value {
+            "procurator" => AssignmentKind::Procurator,
+            _ => AssignmentKind::Investigator,
+        }
+    }
+}
+
+/// The designation given to every report of `InformationRepository::assign`.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Assignment {
+    pub kind: AssignmentKind,
+    /// Full name of the investigator or procurator.
+    pub assignee: String,
+    pub designation_no: String,
+    pub designated_at: i64,
+}
+
+/// A designation made through `assign`.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AssignmentRecord {
+    pub id: i64,
+    pub information_id: i64,
+    pub kind: AssignmentKind,
+    pub assignee: String,
+    pub designation_no: String,
+    pub designated_at: i64,
+    pub assigned_by: Option<i64>,
+    pub assigned_at: i64,
+}
+
+/// Designates the assignee of `assignment` for every report of `ids`, in a
+/// single transaction, changing no other field. Each report is validated as
+/// it would be by an update; the first invalid one cancels the whole
+/// assignment and is named in the error.
+pub fn assign(
+    conn: &Connection,
+    user: &User,
+    ids: &[i64],
+    assignment: &Assignment,
+) -> Result<(), CommandError> {
+    if ids.is_empty() {
+        return Err("No report to assign".into());
+    }
+    let tz = dates::DateSettings::load(conn)
+        .map_err(|_| "Fail to read date settings")?
+        .tz();
+    let assignee = assignment.assignee.trim().to_string();
+    let designation_no = assignment.designation_no.trim().to_string();
+    let designated_at = dates::start_of_day(assignment.designated_at, &tz);
+    let now = db::now();
+    let (column, prefix) = match assignment.kind {
+        AssignmentKind::Investigator => ("inv_investigator", "inv"),
+        AssignmentKind::Procurator => ("pro_procurator", "pro"),
+    };
+
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|_| "Fail to assign information")?;
+    for &id in ids {
+        let existing = get_information(&tx, id)
+            .map_err(|_| "Fail to assign information")?
+            .ok_or("Information not found")?;
+        let mut information = existing.clone();
+        match assignment.kind {
+            AssignmentKind::Investigator => {
+                information.inv_investigator = Some(assignee.clone());
+                information.inv_designation_no = Some(designation_no.clone());
+                information.inv_designated_at = Some(designated_at);
+            }
+            AssignmentKind::Procurator => {
+                information.pro_procurator = Some(assignee.clone());
+                information.pro_designation_no = Some(designation_no.clone());
+                information.pro_designated_at = Some(designated_at);
+            }
+        }
+        if let Err(errors) = validation::validate_information(&information, Some(&existing), &tz) {
+            return Err(
+                format!("{}: {}", existing.acceptance_no, CommandError::from(errors)).into(),
+            );
+        }
+
+        tx.execute(
+            &format!(
+                "
+                UPDATE information
+                SET
+                    {column} = :assignee,
+                    {prefix}_designation_no = :designation_no,
+                    {prefix}_designated_at = :designated_at,
+                    updated_at = :updated_at,
+                    local_updated_at = :updated_at
+                WHERE id = :id
+                ",
+                column = column,
+                prefix = prefix
+            ),
+            named_params! {
+                ":id": id,
+                ":assignee": assignee,
+                ":designation_no": designation_no,
+                ":designated_at": designated_at,
+                ":updated_at": now,
+            },
+        )
+        .and_then(|_| {
+            tx.execute(
+                "
+                INSERT INTO assignment_history (
+                    information_id, kind, assignee, designation_no, designated_at,
+                    assigned_by, assigned_at
+                )
+                VALUES (
+                    :information_id, :kind, :assignee, :designation_no, :designated_at,
+                    :assigned_by, :assigned_at
+                )
+                ",
+                named_params! {
+                    ":information_id": id,
+                    ":kind": assignment.kind.as_str(),
+                    ":assignee": assignee,
+                    ":designation_no": designation_no,
+                    ":designated_at": designated_at,
+                    ":assigned_by": user.id,
+                    ":assigned_at": now,
+                },
+            )
+        })
+        .map_err(|_| "Fail to assign information")?;
+    }
+    tx.commit().map_err(|_| "Fail to assign information")?;
+    Ok(())
+}
+
+/// The assignments of a report, oldest first.
+pub fn history(conn: &Connection, information_id: i64) -> Result<Vec<AssignmentRecord>, String> {
+    let mut stmt = conn
+        .prepare(
+            "
+            SELECT * FROM assignment_history
+            WHERE information_id = :information_id
+            ORDER BY assigned_at, id
+            ",
+        )
+        .map_err(|_| "Fail to read assignment history")?;
+    let mut rows = stmt
+        .query(named_params! { ":information_id": information_id })
+        .map_err(|_| "Fail to read assignment history")?;
+    let mut records = Vec::new();
+    while let Ok(Some(row)) = rows.next() {
+        let kind: String = row.get_unwrap("kind");
+        records.push(AssignmentRecord {
+            id: row.get_unwrap("id"),
+            information_id: row.get_unwrap("information_id"),
+            kind: AssignmentKind::parse(&kind),
+            assignee: row.get_unwrap("assignee"),
+            designation_no: row.get_unwrap("designation_no"),
+            designated_at: row.get_unwrap("designated_at"),
+            assigned_by: row.get_unwrap("assigned_by"),
+            assigned_at: row.get_unwrap("assigned_at"),
+        });
+    }
+    Ok(records)
+}
+
+pub fn delete_for_information(conn: &Connection, ids: &[i64]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("DELETE FROM assignment_history WHERE information_id = :id")?;
+    for id in ids {
+        stmt.execute(named_params! { ":id": id })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,516 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rusqlite::{named_params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth::{self, User},
+    dates::{self, DateSettings},
+    db,
+    model::get_information,
+    note,
+};
+
+/// Suffix of the store next to the database, `docman.db-attachments` for
+/// `docman.db`.
+const STORE_SUFFIX: &str = "-attachments";
+/// Name of the store when it was shared by every database, and of the folder
+/// of the temporary copies.
+const STORE_DIR: &str = "docman-attachments";
+pub(crate) const KEY_SETTING: &str = "attachment.key";
+/// Encrypted files start with this header followed by their nonce; files
+/// stored before encryption have neither.
+const BLOB_HEADER: &[u8; 8] = b"DMBLOB01";
+const NONCE_LENGTH: usize = 12;
+/// Extensions of the files that can be attached, the ones the app is allowed
+/// to open (see the shell scope in `tauri.conf.json`).
+pub const EXTENSIONS: &[&str] = &[
+    "xlsx", "pdf", "doc", "docx", "jpg", "jpeg", "png", "tif", "tiff",
+];
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentType {
+    /// The paper report (tin báo) as received.
+    Report,
+    DesignationDecision,
+    ProcuracyRequest,
+    Other,
+}
+
+impl DocumentType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DocumentType::Report => "report",
+            DocumentType::DesignationDecision => "designation_decision",
+            DocumentType::ProcuracyRequest => "procuracy_request",
+            DocumentType::Other => "other",
+        }
+    }
+
+    fn parse(value: &str) -> DocumentType {
+        match value {
+            "report" => DocumentType::Report,
+            "designation_decision" => DocumentType::DesignationDecision,
+            "procuracy_request" => DocumentType::ProcuracyRequest,
+            _ => DocumentType::Other,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Attachment {
+    id: i64,
+    information_id: i64,
+    /// SHA-256 of the content, also its name in the store.
+    hash: String,
+    file_name: String,
+    size: i64,
+    doc_type: DocumentType,
+    doc_date: Option<i64>,
+    description: Option<String>,
+    created_by: Option<i64>,
+    created_at: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AttachmentMetadata {
+    doc_type: DocumentType,
+    doc_date: Option<i64>,
+    description: Option<String>,
+}
+
+/// Key the stored files are encrypted with, along with the store of its
+/// database. It is generated once and kept in the encrypted database, so it
+/// is only readable with the vault key, stays the same when the password is
+/// changed and is part of every backup.
+pub struct StoreKey {
+    cipher: ChaCha20Poly1305,
+    dir: PathBuf,
+}
+
+/// Generates the store key on first use, encrypting the files stored before
+/// there was one.
+pub fn ensure_key(conn: &Connection) -> Result<(), String> {
+    if db::get_setting(conn, KEY_SETTING)
+        .map_err(|_| "Fail to read attachment key")?
+        .is_some()
+    {
+        return Ok(());
+    }
+    let key: String = conn
+        .query_row("SELECT lower(hex(randomblob(32)))", [], |row| row.get(0))
+        .map_err(|_| "Fail to create attachment key")?;
+    db::set_setting(conn, KEY_SETTING, &key).map_err(|_| "Fail to save attachment key")?;
+    // A database in memory has no files to encrypt.
+    if conn.path().map_or(true, str::is_empty) {
+        return Ok(());
+    }
+    encrypt_stored_files(&store_key(conn)?)
+}
+
+pub fn store_key(conn: &Connection) -> Result<StoreKey, String> {
+    let hex = db::get_setting(conn, KEY_SETTING)
+        .map_err(|_| "Fail to read attachment key")?
+        .ok_or("Attachment key is missing")?;
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .filter(|bytes| bytes.len() == 32)
+        .ok_or("Attachment key is invalid")?;
+    Ok(StoreKey {
+        cipher: ChaCha20Poly1305::new(Key::from_slice(&bytes)),
+        dir: store_dir(conn)?,
+    })
+}
+
+fn encrypt(key: &StoreKey, content: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let encrypted = key
+        .cipher
+        .encrypt(&nonce, content)
+        .map_err(|_| "Fail to encrypt file")?;
+    let mut blob = Vec::with_capacity(BLOB_HEADER.len() + nonce.len() + encrypted.len());
+    blob.extend_from_slice(BLOB_HEADER);
+    blob.extend_from_slice(&nonce);
+    blob.extend_from_slice(&encrypted);
+    Ok(blob)
+}
+
+/// The content of a stored file, which may predate encryption.
+fn decrypt(key: &StoreKey, blob: Vec<u8>) -> Result<Vec<u8>, String> {
+    if !blob.starts_with(BLOB_HEADER) {
+        return Ok(blob);
+    }
+    let rest = &blob[BLOB_HEADER.len()..];
+    if rest.len() < NONCE_LENGTH {
+        return Err("Attachment file is corrupted".into());
+    }
+    let (nonce, encrypted) = rest.split_at(NONCE_LENGTH);
+    key.cipher
+        .decrypt(Nonce::from_slice(nonce), encrypted)
+        .map_err(|_| "Attachment file is corrupted".into())
+}
+
+/// The store of the database at `database`, a folder next to it.
+pub fn store_path(database: &Path) -> PathBuf {
+    let mut name = database.file_name().unwrap_or_default().to_os_string();
+    name.push(STORE_SUFFIX);
+    database.with_file_name(name)
+}
+
+/// The store of the database `conn` is open on. Databases in memory have
+/// none.
+fn store_dir(conn: &Connection) -> Result<PathBuf, String> {
+    let database = conn
+        .path()
+        .filter(|path| !path.is_empty())
+        .ok_or("The database has no attachment folder")?;
+    let dir = store_path(Path::new(database));
+    fs::create_dir_all(&dir).map_err(|_| "Cannot create attachment folder")?;
+    Ok(dir)
+}
+
+/// Moves the store shared by every database in the data folder, from before
+/// each database had its own, to the one of the database at `database`.
+pub fn adopt_shared_store(database: &Path) -> Result<(), String> {
+    let shared = match dirs_next::data_dir() {
+        Some(dir) => dir.join(STORE_DIR),
+        None => return Ok(()),
+    };
+    let own = store_path(database);
+    if !shared.is_dir() || own.exists() {
+        return Ok(());
+    }
+    fs::rename(&shared, &own).map_err(|_| "Cannot move attachment folder".into())
+}
+
+/// Files are stored under their hash, fanned out by its first two characters
+/// to keep directories small.
+fn blob_path_in(dir: &Path, hash: &str) -> PathBuf {
+    dir.join(&hash[..2]).join(hash)
+}
+
+fn hash_content(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Writes `content` under a temporary name first so an interrupted write
+/// never looks like a stored file.
+fn write_blob(target: &Path, content: &[u8]) -> Result<(), String> {
+    let dir = target.parent().unwrap();
+    fs::create_dir_all(dir).map_err(|_| "Cannot create attachment folder")?;
+    let partial = target.with_extension("partial");
+    fs::write(&partial, content).map_err(|_| "Fail to copy file")?;
+    fs::rename(&partial, target).map_err(|_| "Fail to copy file".into())
+}
+
+/// Encrypts the files stored before the store had a key.
+fn encrypt_stored_files(key: &StoreKey) -> Result<(), String> {
+    for path in stored_files(&key.dir)? {
+        let content = fs::read(&path).map_err(|_| "Cannot read attachment file")?;
+        if !content.starts_with(BLOB_HEADER) {
+            write_blob(&path, &encrypt(key, &content)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Every file in the store at `dir`.
+fn stored_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for fan_out in fs::read_dir(dir).map_err(|_| "Cannot read attachment folder")? {
+        let fan_out = fan_out.map_err(|_| "Cannot read attachment folder")?.path();
+        if !fan_out.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(&fan_out).map_err(|_| "Cannot read attachment folder")? {
+            let path = entry.map_err(|_| "Cannot read attachment folder")?.path();
+            if path.is_file() && path.extension().is_none() {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Encrypts `source` into the store unless identical content is already
+/// there, returning the hash and size of its content.
+pub fn store_file(key: &StoreKey, source: &Path) -> Result<(String, u64), String> {
+    let content = fs::read(source).map_err(|_| "Cannot read file")?;
+    let hash = hash_content(&content);
+    let target = blob_path_in(&key.dir, &hash);
+    if !target.exists() {
+        write_blob(&target, &encrypt(key, &content)?)?;
+    }
+    Ok((hash, content.len() as u64))
+}
+
+/// Copies the files of the store of `conn`'s database missing from the store
+/// at `target`, still encrypted. Backups keep every file ever stored, so that
+/// any of them can be restored.
+pub fn back_up_files(conn: &Connection, target: &Path) -> Result<(), String> {
+    let source = store_dir(conn)?;
+    for path in stored_files(&source)? {
+        let copy = target.join(path.strip_prefix(&source).unwrap());
+        if !copy.exists() {
+            let content = fs::read(&path).map_err(|_| "Cannot read attachment file")?;
+            write_blob(&copy, &content)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies the files the attachments refer to back from the backup store at
+/// `source` when they are missing here.
+pub fn restore_files(conn: &Connection, source: &Path) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT hash FROM attachment")
+        .map_err(|_| "Fail to read attachments")?;
+    let hashes = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<String>>>())
+        .map_err(|_| "Fail to read attachments")?;
+    let dir = store_dir(conn)?;
+    for hash in hashes {
+        let target = blob_path_in(&dir, &hash);
+        let backup = blob_path_in(source, &hash);
+        if !target.exists() && backup.exists() {
+            let content = fs::read(&backup).map_err(|_| "Cannot read attachment file")?;
+            write_blob(&target, &content)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_attachment(row: &Row) -> Attachment {
+    let doc_type: String = row.get_unwrap("doc_type");
+    Attachment {
+        id: row.get_unwrap("id"),
+        information_id: row.get_unwrap("information_id"),
+        hash: row.get_unwrap("hash"),
+        file_name: row.get_unwrap("file_name"),
+        size: row.get_unwrap("size"),
+        doc_type: DocumentType::parse(&doc_type),
+        doc_date: row.get_unwrap("doc_date"),
+        description: row.get_unwrap("description"),
+        created_by: row.get_unwrap("created_by"),
+        created_at: row.get_unwrap("created_at"),
+    }
+}
+
+pub fn get_attachment(conn: &Connection, id: i64) -> Result<Attachment, String> {
+    conn.query_row(
+        "SELECT * FROM attachment WHERE id = :id",
+        named_params! { ":id": id },
+        |row| Ok(read_attachment(row)),
+    )
+    .optional()
+    .map_err(|_| "Fail to read attachment")?
+    .ok_or_else(|| "Attachment not found".into())
+}
+
+/// Deletes the files of `hashes` that no attachment refers to anymore.
+pub fn remove_unused_files(conn: &Connection, hashes: &[String]) {
+    for hash in hashes {
+        let in_use = conn
+            .query_row(
+                "SELECT count(*) FROM attachment WHERE hash = :hash",
+                named_params! { ":hash": hash },
+                |row| row.get::<_, i64>(0),
+            )
+            .map_or(true, |count| count > 0);
+        if !in_use {
+            if let Ok(dir) = store_dir(conn) {
+                let _ = fs::remove_file(blob_path_in(&dir, hash));
+            }
+        }
+    }
+}
+
+/// Deletes the attachment rows of the given reports, returning the hashes they
+/// used so the files can be cleaned up once the deletion is committed.
+pub fn delete_for_information(conn: &Connection, ids: &[i64]) -> rusqlite::Result<Vec<String>> {
+    let mut select = conn
+        .prepare("SELECT DISTINCT hash FROM attachment WHERE information_id = :information_id")?;
+    let mut delete =
+        conn.prepare("DELETE FROM attachment WHERE information_id = :information_id")?;
+    let mut hashes = Vec::new();
+    for id in ids {
+        let mut rows = select.query(named_params! { ":information_id": id })?;
+        while let Some(row) = rows.next()? {
+            hashes.push(row.get(0)?);
+        }
+        delete.execute(named_params! { ":information_id": id })?;
+    }
+    hashes.sort();
+    hashes.dedup();
+    Ok(hashes)
+}
+
+pub fn list(conn: &Connection, information_id: i64) -> Result<Vec<Attachment>, String> {
+    let mut stmt = conn
+        .prepare(
+            "
+            SELECT * FROM attachment
+            WHERE information_id = :information_id
+            ORDER BY doc_date, created_at
+            ",
+        )
+        .map_err(|_| "Fail to read attachments")?;
+    let mut rows = stmt
+        .query(named_params! { ":information_id": information_id })
+        .map_err(|_| "Fail to read attachments")?;
+    let mut attachments = Vec::new();
+    while let Ok(Some(row)) = rows.next() {
+        attachments.push(read_attachment(row));
+    }
+    Ok(attachments)
+}
+
+/// Fails unless the report exists and `user` may attach files to it: those
+/// registering reports, or whom it is assigned to. Checked before copying so
+/// that no file is copied for nothing.
+pub fn check_information(
+    conn: &Connection,
+    user: &User,
+    information_id: i64,
+) -> Result<(), String> {
+    let information = get_information(conn, information_id)
+        .map_err(|_| "Fail to read information")?
+        .ok_or("Information not found")?;
+    // Clerks attach the scans of the reports they register.
+    if auth::REGISTRARS.contains(&user.role) {
+        return Ok(());
+    }
+    auth::ensure_assigned(user, &information)
+}
+
+/// Fails for the files the app could not open, see `EXTENSIONS`.
+pub fn check_file_name(file_name: &str) -> Result<(), String> {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+    match extension {
+        Some(extension) if EXTENSIONS.contains(&extension.as_str()) => Ok(()),
+        _ => Err(format!(
+            "Only {} files can be attached",
+            EXTENSIONS.join(", ")
+        )),
+    }
+}
+
+/// Records a file copied with `store_file` as an attachment of the report, by
+/// someone allowed to by `check_information`. The file is removed again if
+/// the attachment can't be saved.
+pub fn add(
+    conn: &Connection,
+    user: &User,
+    information_id: i64,
+    file_name: &str,
+    (hash, size): (String, u64),
+    metadata: &AttachmentMetadata,
+) -> Result<Attachment, String> {
+    if let Err(err) =
+        check_file_name(file_name).and_then(|_| check_information(conn, user, information_id))
+    {
+        remove_unused_files(conn, &[hash]);
+        return Err(err);
+    }
+    let tz = DateSettings::load(conn)
+        .map_err(|_| "Fail to read date settings")?
+        .tz();
+    let inserted = conn.execute(
+        "
+        INSERT INTO attachment (
+            information_id, hash, file_name, size, doc_type, doc_date, description,
+            created_by, created_at
+        )
+        VALUES (
+            :information_id, :hash, :file_name, :size, :doc_type, :doc_date, :description,
+            :created_by, :created_at
+        )
+        ",
+        named_params! {
+            ":information_id": information_id,
+            ":hash": hash,
+            ":file_name": file_name,
+            ":size": size as i64,
+            ":doc_type": metadata.doc_type.as_str(),
+            ":doc_date": metadata.doc_date.map(|millis| dates::start_of_day(millis, &tz)),
+            ":description": metadata.description,
+            ":created_by": user.id,
+            ":created_at": db::now(),
+        },
+    );
+    if inserted.is_err() {
+        remove_unused_files(conn, &[hash]);
+        return Err("Fail to save attachment".into());
+    }
+    get_attachment(conn, conn.last_insert_rowid())
+}
+
+/// Removes an attachment; only a supervisor or its uploader, while still
+/// allowed on the report, may do so. The file is deleted once no other
+/// attachment shares its content.
+pub fn remove(conn: &Connection, user: &User, id: i64) -> Result<(), String> {
+    let attachment = get_attachment(conn, id)?;
+    if !auth::MANAGERS.contains(&user.role) {
+        if attachment.created_by != Some(user.id) {
+            return Err("You don't have permission to do this".into());
+        }
+        check_information(conn, user, attachment.information_id)?;
+    }
+    conn.unchecked_transaction()
+        .and_then(|tx| {
+            note::detach_attachment(&tx, id)?;
+            tx.execute(
+                "DELETE FROM attachment WHERE id = :id",
+                named_params! { ":id": id },
+            )?;
+            tx.commit()
+        })
+        .map_err(|_| "Fail to remove attachment")?;
+    remove_unused_files(conn, &[attachment.hash]);
+    Ok(())
+}
+
+fn temporary_dir() -> PathBuf {
+    std::env::temp_dir().join(STORE_DIR)
+}
+
+/// Decrypts the stored file and verifies it against its hash, then writes it
+/// to a temporary file carrying the original file name, for opening. The
+/// copies are removed with `remove_temporary_copies`.
+pub fn temporary_copy(key: &StoreKey, attachment: &Attachment) -> Result<PathBuf, String> {
+    let blob = fs::read(blob_path_in(&key.dir, &attachment.hash))
+        .map_err(|_| "Attachment file is missing")?;
+    let content = decrypt(key, blob)?;
+    if hash_content(&content) != attachment.hash {
+        return Err("Attachment file is corrupted".into());
+    }
+
+    let dir = temporary_dir().join(&attachment.hash);
+    fs::create_dir_all(&dir).map_err(|_| "Cannot create temporary folder")?;
+    let copy = dir.join(&attachment.file_name);
+    fs::write(&copy, content).map_err(|_| "Fail to copy file")?;
+    Ok(copy)
+}
+
+/// Deletes the decrypted copies made for opening attachments.
+pub fn remove_temporary_copies() {
+    let _ = fs::remove_dir_all(temporary_dir());
+}
@@ -0,0 +1,436 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use rusqlite::{named_params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::{db, Information};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Clerk,
+    Investigator,
+    Procurator,
+    Supervisor,
+    Admin,
+}
+
+/// Roles allowed to delete, export and manage backups.
+pub const MANAGERS: &[Role] = &[Role::Supervisor, Role::Admin];
+/// Roles allowed to register new reports.
+pub const REGISTRARS: &[Role] = &[Role::Clerk, Role::Supervisor, Role::Admin];
+
+impl Role {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Role::Clerk => "clerk",
+            Role::Investigator => "investigator",
+            Role::Procurator => "procurator",
+            Role::Supervisor => "supervisor",
+            Role::Admin => "admin",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Role> {
+        match value {
+            "clerk" => Some(Role::Clerk),
+            "investigator" => Some(Role::Investigator),
+            "procurator" => Some(Role::Procurator),
+            "supervisor" => Some(Role::Supervisor),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    /// Matched against `inv_investigator`/`pro_procurator` to decide which
+    /// reports belong to the user.
+    pub full_name: String,
+    pub role: Role,
+    pub created_at: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewUser {
+    pub username: String,
+    pub full_name: String,
+    pub password: String,
+    pub role: Role,
+}
+
+fn hash_password(password: &str) -> Result<String, String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!(
+            "Password must have at least {} characters",
+            MIN_PASSWORD_LENGTH
+        ));
+    }
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|_| "Fail to hash password".into())
+}
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+fn normalize_username(username: &str) -> String {
+    username.trim().to_lowercase()
+}
+
+fn read_user(row: &Row) -> User {
+    let role: String = row.get_unwrap("role");
+    User {
+        id: row.get_unwrap("id"),
+        username: row.get_unwrap("username"),
+        full_name: row.get_unwrap("full_name"),
+        role: Role::parse(&role).unwrap_or(Role::Clerk),
+        created_at: row.get_unwrap("created_at"),
+    }
+}
+
+/// Whether an account other than `id` has `full_name`. Reports are assigned
+/// by full name, so two accounts can't share one.
+fn full_name_taken(conn: &Connection, full_name: &str, id: i64) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM user WHERE full_name = :full_name AND id != :id)",
+        named_params! { ":full_name": full_name, ":id": id },
+        |row| row.get(0),
+    )
+    .map_err(|_| "Fail to read users".into())
+}
+
+/// Adds an account, failing when the username or the full name is taken.
+pub fn create_user(conn: &Connection, user: &NewUser) -> Result<User, String> {
+    let username = normalize_username(&user.username);
+    if username.is_empty() || user.full_name.trim().is_empty() {
+        return Err("Username and full name are required".into());
+    }
+    let password_hash = hash_password(&user.password)?;
+    let created_at = db::now();
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|_| "Fail to create user")?;
+    if full_name_taken(&tx, user.full_name.trim(), 0)? {
+        return Err("Another account already has this full name".into());
+    }
+    tx.execute(
+        "
+        INSERT INTO user (username, full_name, password_hash, role, created_at)
+        VALUES (:username, :full_name, :password_hash, :role, :created_at)
+        ",
+        named_params! {
+            ":username": username,
+            ":full_name": user.full_name.trim(),
+            ":password_hash": password_hash,
+            ":role": user.role.as_str(),
+            ":created_at": created_at,
+        },
+    )
+    .map_err(|_| "Username already exists")?;
+    let id = tx.last_insert_rowid();
+    tx.commit().map_err(|_| "Fail to create user")?;
+    Ok(User {
+        id,
+        username,
+        full_name: user.full_name.trim().to_string(),
+        role: user.role,
+        created_at,
+    })
+}
+
+pub fn user_count(conn: &Connection) -> Result<i64, String> {
+    conn.query_row("SELECT count(*) FROM user", [], |row| row.get(0))
+        .map_err(|_| "Fail to read users".into())
+}
+
+/// Stores assignees the way `is_assignee` compares them: trimmed, and blank
+/// as none.
+pub fn normalize_assignees(information: &mut Information) {
+    for assignee in [
+        &mut information.inv_investigator,
+        &mut information.pro_procurator,
+    ] {
+        *assignee = assignee
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string);
+    }
+}
+
+/// Whether `assignee`, an investigator or procurator of a report, is `user`.
+/// Reports are assigned by full name, so an account renamed through
+/// `update_user` keeps its reports, but two accounts with the same full name
+/// share them.
+fn is_assignee(user: &User, assignee: Option<&str>) -> bool {
+    assignee.map(str::trim) == Some(user.full_name.as_str())
+}
+
+/// Applies an update coming from a user who may only change part of a report:
+/// fields outside their remit are taken from `existing`.
+pub fn restrict_update(
+    user: &User,
+    existing: Information,
+    incoming: Information,
+) -> Result<Information, String> {
+    match user.role {
+        Role::Supervisor | Role::Admin => Ok(incoming),
+        Role::Clerk => Ok(Information {
+            acceptance_no: incoming.acceptance_no,
+            accepted_at: incoming.accepted_at,
+            plaintiff: incoming.plaintiff,
+            defendant: incoming.defendant,
+            description: incoming.description,
+            law: incoming.law,
+            ..existing
+        }),
+        Role::Investigator => {
+            if !is_assignee(user, existing.inv_investigator.as_deref()) {
+                return Err("You can only edit reports assigned to you".into());
+            }
+            Ok(Information {
+                inv_designation_no: incoming.inv_designation_no,
+                inv_designated_at: incoming.inv_designated_at,
+                inv_status: incoming.inv_status,
+                inv_handling_no: incoming.inv_handling_no,
+                inv_handled_at: incoming.inv_handled_at,
+                inv_transferred_at: incoming.inv_transferred_at,
+                inv_extended_at: incoming.inv_extended_at,
+                inv_recovered_at: incoming.inv_recovered_at,
+                inv_canceled_at: incoming.inv_canceled_at,
+                ..existing
+            })
+        }
+        Role::Procurator => {
+            if !is_assignee(user, existing.pro_procurator.as_deref()) {
+                return Err("You can only edit reports assigned to you".into());
+            }
+            Ok(Information {
+                pro_designation_no: incoming.pro_designation_no,
+                pro_designated_at: incoming.pro_designated_at,
+                pro_additional_evidence_requirement: incoming.pro_additional_evidence_requirement,
+                pro_non_prosecution_decision: incoming.pro_non_prosecution_decision,
+                pro_cessation_decision: incoming.pro_cessation_decision,
+                ..existing
+            })
+        }
+    }
+}
+
+/// Checks that `user` may add to the record of a report: supervisors and
+/// admins on any report, investigators and procurators on the reports
+/// assigned to them.
+pub fn ensure_assigned(user: &User, information: &Information) -> Result<(), String> {
+    let assignee = match user.role {
+        Role::Supervisor | Role::Admin => return Ok(()),
+        Role::Investigator => information.inv_investigator.as_deref(),
+        Role::Procurator => information.pro_procurator.as_deref(),
+        Role::Clerk => return Err("You don't have permission to do this".into()),
+    };
+    if !is_assignee(user, assignee) {
+        return Err("You can only edit reports assigned to you".into());
+    }
+    Ok(())
+}
+
+/// Creates the first account, always an admin. Only allowed while there are no
+/// users at all.
+pub fn create_initial_admin(
+    conn: &Connection,
+    username: String,
+    full_name: String,
+    password: String,
+) -> Result<User, String> {
+    if user_count(conn)? > 0 {
+        return Err("An administrator already exists".into());
+    }
+    create_user(
+        conn,
+        &NewUser {
+            username,
+            full_name,
+            password,
+            role: Role::Admin,
+        },
+    )
+}
+
+/// The user with these credentials.
+pub fn authenticate(conn: &Connection, username: &str, password: &str) -> Result<User, String> {
+    let found = conn
+        .query_row(
+            "SELECT * FROM user WHERE username = :username",
+            named_params! { ":username": normalize_username(username) },
+            |row| {
+                Ok((
+                    read_user(row),
+                    row.get_unwrap::<&str, String>("password_hash"),
+                ))
+            },
+        )
+        .optional()
+        .map_err(|_| "Fail to read users")?;
+    match found {
+        Some((user, hash)) if verify_password(password, &hash) => Ok(user),
+        _ => Err(String::from("Wrong username or password")),
+    }
+}
+
+/// Changes the password of a user who knows the current one.
+pub fn change_password(
+    conn: &Connection,
+    id: i64,
+    old_password: &str,
+    new_password: &str,
+) -> Result<(), String> {
+    let hash: String = conn
+        .query_row(
+            "SELECT password_hash FROM user WHERE id = :id",
+            named_params! { ":id": id },
+            |row| row.get(0),
+        )
+        .map_err(|_| "Fail to read users")?;
+    if !verify_password(old_password, &hash) {
+        return Err("Wrong password".into());
+    }
+    conn.execute(
+        "UPDATE user SET password_hash = :password_hash, updated_at = :updated_at WHERE id = :id",
+        named_params! {
+            ":password_hash": hash_password(new_password)?,
+            ":updated_at": db::now(),
+            ":id": id,
+        },
+    )
+    .map_err(|_| "Fail to change password")?;
+    Ok(())
+}
+
+/// The account with `id`, `None` once it is deleted.
+pub fn get_user(conn: &Connection, id: i64) -> Result<Option<User>, String> {
+    conn.query_row(
+        "SELECT * FROM user WHERE id = :id",
+        named_params! { ":id": id },
+        |row| Ok(read_user(row)),
+    )
+    .optional()
+    .map_err(|_| "Fail to read users".into())
+}
+
+pub fn list_users(conn: &Connection) -> Result<Vec<User>, String> {
+    let mut stmt = conn
+        .prepare("SELECT * FROM user ORDER BY username")
+        .map_err(|_| "Fail to read users")?;
+    let mut rows = stmt.query([]).map_err(|_| "Fail to read users")?;
+    let mut users = Vec::new();
+    while let Ok(Some(row)) = rows.next() {
+        users.push(read_user(row));
+    }
+    Ok(users)
+}
+
+/// Updates an account. Reports assigned under its former full name are
+/// assigned under the new one, so the name can't be blank or another
+/// account's.
+pub fn update_user(conn: &Connection, id: i64, full_name: &str, role: Role) -> Result<(), String> {
+    let full_name = full_name.trim();
+    if full_name.is_empty() {
+        return Err("Full name is required".into());
+    }
+    // Checked in the transaction, so that two accounts renamed at once can't
+    // both take the name.
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|_| "Fail to update user")?;
+    if full_name_taken(&tx, full_name, id)? {
+        return Err("Another account already has this full name".into());
+    }
+    let rename = || -> rusqlite::Result<()> {
+        let former: String = tx.query_row(
+            "SELECT full_name FROM user WHERE id = :id",
+            named_params! { ":id": id },
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "
+            UPDATE user
+            SET full_name = :full_name, role = :role, updated_at = :updated_at
+            WHERE id = :id
+            ",
+            named_params! {
+                ":full_name": full_name,
+                ":role": role.as_str(),
+                ":updated_at": db::now(),
+                ":id": id,
+            },
+        )?;
+        if former != full_name {
+            for column in ["inv_investigator", "pro_procurator"] {
+                tx.execute(
+                    &format!(
+                        "
+                        UPDATE information
+                        SET {column} = :full_name, updated_at = :updated_at,
+                            local_updated_at = :updated_at
+                        WHERE {column} = :former
+                        ",
+                        column = column
+                    ),
+                    named_params! {
+                        ":full_name": full_name,
+                        ":former": former,
+                        ":updated_at": db::now(),
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    };
+    rename()
+        .and_then(|_| tx.commit())
+        .map_err(|_| "Fail to update user")?;
+    Ok(())
+}
+
+/// Sets a new password without asking for the current one.
+pub fn reset_password(conn: &Connection, id: i64, password: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE user SET password_hash = :password_hash, updated_at = :updated_at WHERE id = :id",
+        named_params! {
+            ":password_hash": hash_password(password)?,
+            ":updated_at": db::now(),
+            ":id": id,
+        },
+    )
+    .map_err(|_| "Fail to reset password")?;
+    Ok(())
+}
+
+/// Deletes an account. Notes, events, attachments and links it recorded keep
+/// its id and show no author from then on; ids are never reused, so they are
+/// not credited to a later account. Reports assigned to its full name stay
+/// assigned until reassigned.
+pub fn delete_user(conn: &Connection, id: i64) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM user WHERE id = :id",
+        named_params! { ":id": id },
+    )
+    .map_err(|_| "Fail to delete user")?;
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use std::{
+    cmp::Reverse,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use chrono::Local;
+use rusqlite::{backup::Backup, params, Connection, DatabaseName, OpenFlags};
+use serde::Serialize;
+
+use crate::{attachment, db, pool::Pool, vault};
+
+const BACKUP_DIR: &str = "docman-backups";
+/// Backed up attachment files, shared by every backup in the folder.
+const ATTACHMENT_DIR: &str = "attachments";
+/// Automatic backups are named `docman-YYYY-MM-DD.db`.
+const DAILY_PREFIX: &str = "docman-";
+const PRE_RESTORE_PREFIX: &str = "pre-restore-";
+/// Number of daily backups, and of `pre-restore-*` ones, kept before the
+/// oldest are removed.
+const RETENTION: usize = 7;
+
+#[derive(Serialize, Debug)]
+pub struct BackupInfo {
+    name: String,
+    path: String,
+    size: u64,
+    modified_at: Option<i64>,
+}
+
+/// Backups of the database at `database` go in a folder beside it, which for
+/// the app's database is the one in the data folder.
+pub fn backup_path(database: &Path) -> PathBuf {
+    database.with_file_name(BACKUP_DIR)
+}
+
+fn backup_dir(database: &Path) -> Result<PathBuf, String> {
+    let dir = backup_path(database);
+    fs::create_dir_all(&dir).map_err(|_| "Cannot create backup folder")?;
+    Ok(dir)
+}
+
+/// The backup folder of the database `conn` is open on. Databases in memory
+/// have none.
+fn backup_dir_of(conn: &Connection) -> Result<PathBuf, String> {
+    let database = conn
+        .path()
+        .filter(|path| !path.is_empty())
+        .ok_or("The database has no backup folder")?;
+    backup_dir(Path::new(database))
+}
+
+/// Removes all but the newest `RETENTION` backups in `dir` named with
+/// `prefix`.
+fn rotate(dir: &Path, prefix: &str) -> Result<(), String> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|_| "Cannot read backup folder")?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| {
+                    name.starts_with(prefix) && name.ends_with(".db")
+                })
+        })
+        .collect();
+    // Names embed the date, so sorting by name sorts by age.
+    backups.sort();
+    backups.reverse();
+    for old in backups.iter().skip(RETENTION) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(())
+}
+
+/// Copies the live database with SQLite's online backup API, so it is safe to
+/// run while other commands are using the connection. The copy is encrypted
+/// with the same key as the live database.
+pub fn backup_to(conn: &Connection, path: &Path, key: &str) -> rusqlite::Result<()> {
+    let mut dst = Connection::open(path)?;
+    dst.pragma_update(None, "key", key)?;
+    Backup::new(conn, &mut dst)?.run_to_completion(100, Duration::from_millis(50), None)?;
+    // The copy inherits WAL mode from the live database; a rollback journal
+    // keeps it a single file that can be moved around.
+    dst.pragma_update_and_check(None, "journal_mode", "DELETE", |_| Ok(()))
+}
+
+/// Checks that `path` is an intact docman database this version can open.
+fn validate_backup(path: &Path, key: &str) -> Result<(), String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|_| "Cannot open backup file")?;
+    conn.pragma_update(None, "key", key)
+        .map_err(|_| "Cannot open backup file")?;
+
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|_| "Wrong password or backup file is not a valid database")?;
+    if integrity != "ok" {
+        return Err("Backup file is corrupted".into());
+    }
+
+    let version = db::schema_version(&conn).map_err(|_| "Cannot read backup schema version")?;
+    if version > db::SCHEMA_VERSION {
+        return Err("Backup was made by a newer version of the app".into());
+    }
+
+    let has_information: i64 = conn
+        .query_row(
+            "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'information'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|_| "Backup file is not a valid database")?;
+    if has_information == 0 {
+        return Err("Backup file is not a docman database".into());
+    }
+    Ok(())
+}
+
+/// Takes today's backup if it doesn't exist yet along with the attachment
+/// files added since the last one, then keeps only the newest `RETENTION`
+/// daily backups.
+pub fn run_daily_backup(pool: &Pool, key: &str) -> Result<(), String> {
+    let dir = pool.with_reader(|conn| {
+        let dir = backup_dir_of(conn)?;
+        attachment::back_up_files(conn, &dir.join(ATTACHMENT_DIR))?;
+        Ok::<_, String>(dir)
+    })?;
+    let today = dir.join(format!(
+        "{}{}.db",
+        DAILY_PREFIX,
+        Local::now().format("%Y-%m-%d")
+    ));
+    if !today.exists() {
+        pool.with_reader(|conn| {
+            backup_to(conn, &today, key).map_err(|_| String::from("Fail to back up database"))
+        })?;
+    }
+    rotate(&dir, DAILY_PREFIX)
+}
+
+/// Backups of the database at `database` taken before it was encrypted, which
+/// have to be encrypted along with it.
+pub fn plaintext_backups(database: &Path) -> Result<Vec<PathBuf>, String> {
+    Ok(fs::read_dir(backup_dir(database)?)
+        .map_err(|_| "Cannot read backup folder")?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "db"))
+        .filter(|path| vault::is_plaintext(path).unwrap_or(false))
+        .collect())
+}
+
+/// Copies a database taken under another password to `copy`, encrypted with
+/// `key`, since SQLCipher can only restore between databases sharing a key.
+pub fn rekeyed_copy(path: &Path, password: &str, key: &str, copy: &Path) -> Result<(), String> {
+    let _ = fs::remove_file(copy);
+    let conn = vault::open_encrypted(path, password)
+        .map_err(|_| "Wrong password or backup file is not a valid database")?;
+    let version = db::schema_version(&conn).map_err(|_| "Cannot read backup schema version")?;
+    conn.execute(
+        "ATTACH DATABASE ?1 AS rekeyed KEY ?2",
+        params![copy.display().to_string(), key],
+    )
+    .map_err(|_| "Fail to read backup")?;
+    conn.query_row("SELECT sqlcipher_export('rekeyed')", [], |_| Ok(()))
+        .map_err(|_| "Fail to read backup")?;
+    conn.pragma_update(
+        Some(DatabaseName::Attached("rekeyed")),
+        "user_version",
+        version,
+    )
+    .map_err(|_| "Fail to read backup")?;
+    conn.execute("DETACH DATABASE rekeyed", [])
+        .map_err(|_| "Fail to read backup")?;
+    Ok(())
+}
+
+/// Every backup in the backup folder of the database at `database`, newest
+/// first.
+pub fn list_backups(database: &Path) -> Result<Vec<BackupInfo>, String> {
+    let dir = backup_dir(database)?;
+    let mut backups: Vec<BackupInfo> = fs::read_dir(&dir)
+        .map_err(|_| "Cannot read backup folder")?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "db"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some(BackupInfo {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                path: entry.path().display().to_string(),
+                size: metadata.len(),
+                modified_at: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|duration| duration.as_millis() as i64),
+            })
+        })
+        .collect();
+    backups.sort_by_key(|backup| Reverse(backup.modified_at));
+    Ok(backups)
+}
+
+/// Replaces the live database with the backup at `path`. The current data is
+/// saved to a `pre-restore-*` backup first, of which the newest `RETENTION`
+/// are kept, and older backups are migrated to the current schema after being
+/// restored. Attachment files missing from the store are copied back from the
+/// backed up ones. `password` is only needed for backups taken before the
+/// password was last changed.
+pub fn restore(
+    conn: &mut Connection,
+    path: &Path,
+    password: Option<&str>,
+    key: &str,
+) -> Result<(), String> {
+    match password {
+        Some(password) if password != key => {
+            let copy = backup_dir_of(conn)?.join("restore.tmp");
+            rekeyed_copy(path, password, key, &copy)?;
+            let result = restore_from(conn, &copy, key);
+            let _ = fs::remove_file(&copy);
+            result
+        }
+        _ => restore_from(conn, path, key),
+    }
+}
+
+fn restore_from(conn: &mut Connection, source: &Path, key: &str) -> Result<(), String> {
+    validate_backup(source, key)?;
+    // The store has a single key, backups taken before it existed don't carry
+    // it.
+    let attachment_key = db::get_setting(conn, attachment::KEY_SETTING)
+        .map_err(|_| "Fail to read attachment key")?;
+
+    let dir = backup_dir_of(conn)?;
+    let safety_copy = dir.join(format!(
+        "{}{}.db",
+        PRE_RESTORE_PREFIX,
+        Local::now().format("%Y-%m-%d-%H%M%S")
+    ));
+    backup_to(conn, &safety_copy, key).map_err(|_| "Fail to back up current database")?;
+    rotate(&dir, PRE_RESTORE_PREFIX)?;
+
+    let src = vault::open_encrypted(source, key).map_err(|_| "Cannot open backup file")?;
+    Backup::new(&src, conn)
+        .and_then(|restore| restore.run_to_completion(100, Duration::from_millis(50), None))
+        .map_err(|_| "Fail to restore backup")?;
+    db::migrate(conn).map_err(|_| "Fail to upgrade restored database")?;
+    if let Some(attachment_key) = attachment_key {
+        db::set_setting(conn, attachment::KEY_SETTING, &attachment_key)
+            .map_err(|_| "Fail to restore attachment key")?;
+    }
+    attachment::restore_files(conn, &dir.join(ATTACHMENT_DIR))
+}
@@ -0,0 +1,453 @@
+//! Command-line access to the docman database, for scripting exports and
+//! backups without opening the app. It uses the same database code as the app
+//! and can run while the app is open.
+
+use std::{env, fs, path::Path, path::PathBuf, process, sync::atomic::AtomicBool, thread};
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use docman_core::{
+    backup,
+    dates::{self, ColumnKind, DateSettings},
+    export::{self, ExportSetting},
+    merge,
+    pool::Pool,
+    repository::{InformationRepository, ListFilter, SqliteRepository},
+    server::{self, Server},
+    sync, vault, Information, InformationPageQueryOption,
+};
+
+const USAGE: &str = "\
+Usage: docman-cli [--db PATH] <command> [options]
+
+Commands:
+    list [--search TERM] [--unassigned] [--overdue] [--offset N] [--limit N]
+         [--json]
+        Lists reports in creation order. --overdue only lists those past
+        their handling deadline.
+    export --from YYYY-MM-DD --to YYYY-MM-DD --out FILE [--notes]
+        Writes the reports accepted in the range to an Excel file, with a
+        column of their notes if --notes is given.
+    import FILE
+        Creates the reports of a JSON file, in the format of `list --json`.
+    backup [--out FILE]
+        Copies the database to FILE, or takes today's backup in the app's
+        backup folder.
+    stats [--json]
+        Counts reports, in total and by year of acceptance.
+    sync-export --out FILE [--since YYYY-MM-DD]
+        Writes the reports changed since the previous sync export, or since
+        the date, to a changeset file for another installation.
+    sync-import FILE [--conflicts FILE]
+        Applies a changeset from another installation. Changes that conflict
+        with ones made here are not applied; they are listed, and written to
+        the --conflicts file as JSON for resolving by hand.
+    merge FILE [--dry-run]
+        Adds the reports of another unit's database that this one lacks,
+        matched by acceptance number, and lists those whose content differs.
+        --dry-run only lists what would be added. The other database's
+        password is read from DOCMAN_MERGE_PASSWORD if it differs.
+    serve [--bind ADDRESS] [--port N]
+        Shares the database with the apps of the LAN until stopped. Listens
+        on every interface and port 8750 by default.
+
+The database is docman.db in the user's data folder unless --db is given.
+Its password is read from the DOCMAN_PASSWORD environment variable. import,
+sync-export, sync-import and merge refuse to run while the app is connected
+to a server.";
+
+const PASSWORD_VAR: &str = "DOCMAN_PASSWORD";
+const MERGE_PASSWORD_VAR: &str = "DOCMAN_MERGE_PASSWORD";
+/// Options that don't take a value.
+const FLAGS: &[&str] = &[
+    "--unassigned",
+    "--overdue",
+    "--json",
+    "--dry-run",
+    "--notes",
+    "--help",
+];
+const DEFAULT_LIMIT: i64 = 50;
+const COMMANDS: &[&str] = &[
+    "list",
+    "export",
+    "import",
+    "backup",
+    "stats",
+    "sync-export",
+    "sync-import",
+    "merge",
+    "serve",
+];
+
+/// A parsed command line: `--name value` options, flags and the remaining
+/// positional arguments, the first of which is the command.
+struct Args {
+    options: Vec<(String, Option<String>)>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Vec::new();
+        let mut positional = Vec::new();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                positional.push(arg);
+            } else if FLAGS.contains(&arg.as_str()) {
+                options.push((arg, None));
+            } else {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {}", arg))?;
+                options.push((arg, Some(value)));
+            }
+        }
+        Ok(Args {
+            options,
+            positional,
+        })
+    }
+
+    /// Fails on options the command doesn't know, `--db` being allowed
+    /// everywhere.
+    fn check(&self, allowed: &[&str]) -> Result<(), String> {
+        match self
+            .options
+            .iter()
+            .find(|(name, _)| name != "--db" && !allowed.contains(&name.as_str()))
+        {
+            Some((name, _)) => Err(format!("Unknown option {}", name)),
+            None => Ok(()),
+        }
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, String> {
+        self.value(name)
+            .ok_or_else(|| format!("Missing option {}", name))
+    }
+
+    fn number(&self, name: &str, default: i64) -> Result<i64, String> {
+        self.value(name).map_or(Ok(default), |value| {
+            value
+                .parse()
+                .map_err(|_| format!("{} must be a number", name))
+        })
+    }
+}
+
+/// Opens the database named by `--db`, or the app's one, returning the pool
+/// along with the key it was opened with.
+fn database_path(args: &Args) -> Result<PathBuf, String> {
+    match args.value("--db") {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => vault::default_path().ok_or_else(|| "Cannot get data dir".into()),
+    }
+}
+
+/// Fails while the app using the database is connected to a server: what the
+/// command writes would not show in the app until it disconnects.
+fn ensure_local(args: &Args) -> Result<(), String> {
+    match vault::connected_server(&database_path(args)?) {
+        Some(address) => Err(format!(
+            "The app is connected to the server at {}, disconnect it first",
+            address
+        )),
+        None => Ok(()),
+    }
+}
+
+fn open(args: &Args) -> Result<(Pool, String), String> {
+    let path = database_path(args)?;
+    if !path.exists() {
+        return Err(format!("Database {} does not exist", path.display()));
+    }
+    if vault::is_plaintext(&path).map_err(|_| "Cannot read database file")? {
+        return Err("Database is not encrypted yet, choose a password in the app first".into());
+    }
+    let key = env::var(PASSWORD_VAR)
+        .map_err(|_| format!("Set the database password in {}", PASSWORD_VAR))?;
+    Ok((vault::open_pool(&path, &key)?, key))
+}
+
+fn date_settings(pool: &Pool) -> Result<DateSettings, String> {
+    pool.with_reader(|conn| {
+        DateSettings::load(conn).map_err(|_| String::from("Fail to read date settings"))
+    })
+}
+
+fn parse_date(value: &str, tz: &Tz) -> Result<i64, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| dates::midnight(date, tz))
+        .ok_or_else(|| format!("Invalid date {}, expected YYYY-MM-DD", value))
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|_| "Fail to write JSON")?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn list(pool: &Pool, args: &Args) -> Result<(), String> {
+    args.check(&[
+        "--search",
+        "--unassigned",
+        "--overdue",
+        "--offset",
+        "--limit",
+        "--json",
+    ])?;
+    let query = InformationPageQueryOption {
+        offset: args.number("--offset", 0)?,
+        limit: args.number("--limit", DEFAULT_LIMIT)?,
+        search: args.value("--search").map(String::from),
+        overdue: args.flag("--overdue"),
+        ..Default::default()
+    };
+    let filter = if args.flag("--unassigned") {
+        ListFilter::Unassigned
+    } else {
+        ListFilter::All
+    };
+    let (informations, total) =
+        pool.with_reader(|conn| SqliteRepository::new(conn).list(filter, &query))?;
+    if args.flag("--json") {
+        return print_json(&informations);
+    }
+
+    let date_settings = date_settings(pool)?;
+    for information in &informations {
+        // Names may hold several parties, one per line.
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            information.acceptance_no,
+            date_settings.format(information.accepted_at, ColumnKind::Date),
+            information.plaintiff.replace('\n', ", "),
+            information.defendant.replace('\n', ", "),
+            information.inv_investigator.as_deref().unwrap_or_default(),
+        );
+    }
+    eprintln!(
+        "{} of {} reports",
+        informations.len(),
+        total.unwrap_or_default()
+    );
+    Ok(())
+}
+
+fn export(pool: &Pool, args: &Args) -> Result<(), String> {
+    args.check(&["--from", "--to", "--out", "--notes"])?;
+    let tz = date_settings(pool)?.tz();
+    let from = parse_date(args.required("--from")?, &tz)?;
+    let to = parse_date(args.required("--to")?, &tz)?;
+    if from > to {
+        return Err("--from must not be after --to".into());
+    }
+    let out = args.required("--out")?;
+
+    let setting = ExportSetting::new(from, to, out.to_string()).with_notes(args.flag("--notes"));
+    export::write_workbook(pool, &setting, &AtomicBool::new(false), |progress| {
+        eprint!("\rExported {}/{}", progress.done, progress.total);
+    })?;
+    eprintln!();
+    println!("{}", out);
+    Ok(())
+}
+
+fn import(pool: &Pool, args: &Args) -> Result<(), String> {
+    args.check(&[])?;
+    let file = args.positional.get(1).ok_or("Missing file to import")?;
+    let content = fs::read_to_string(file).map_err(|_| format!("Cannot read {}", file))?;
+    let informations: Vec<Information> = serde_json::from_str(&content)
+        .map_err(|err| format!("{} is not a valid report file: {}", file, err))?;
+
+    // Every report is validated and numbered like in the app; invalid ones
+    // are reported and skipped.
+    let count = informations.len();
+    let mut failed = 0;
+    for (index, information) in informations.into_iter().enumerate() {
+        match pool.with_writer(|conn| SqliteRepository::new(conn).create(information)) {
+            Ok(created) => println!("{}", created.acceptance_no),
+            Err(err) => {
+                failed += 1;
+                eprintln!("Report {}: {}", index + 1, err);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} of {} reports were not imported", failed, count));
+    }
+    Ok(())
+}
+
+fn backup(pool: &Pool, key: &str, args: &Args) -> Result<(), String> {
+    args.check(&["--out"])?;
+    match args.value("--out") {
+        Some(out) => {
+            pool.with_reader(|conn| {
+                backup::backup_to(conn, Path::new(out), key)
+                    .map_err(|_| String::from("Fail to back up database"))
+            })?;
+            println!("{}", out);
+            Ok(())
+        }
+        None => backup::run_daily_backup(pool, key),
+    }
+}
+
+fn stats(pool: &Pool, args: &Args) -> Result<(), String> {
+    args.check(&["--json"])?;
+    let stats = pool.with_reader(|conn| SqliteRepository::new(conn).stats())?;
+    if args.flag("--json") {
+        return print_json(&stats);
+    }
+    println!("Total\t{}", stats.total);
+    println!("Unassigned\t{}", stats.unassigned);
+    for (year, count) in &stats.by_year {
+        println!("{}\t{}", year, count);
+    }
+    Ok(())
+}
+
+fn sync_export(pool: &Pool, args: &Args) -> Result<(), String> {
+    args.check(&["--out", "--since"])?;
+    let out = args.required("--out")?;
+    let since = match args.value("--since") {
+        Some(since) => Some(parse_date(since, &date_settings(pool)?.tz())?),
+        None => None,
+    };
+    let summary = pool.with_writer(|conn| sync::export_to(conn, Path::new(out), since))?;
+    eprintln!(
+        "{} changed and {} deleted reports",
+        summary.records, summary.deletions
+    );
+    println!("{}", out);
+    Ok(())
+}
+
+fn sync_import(pool: &Pool, args: &Args) -> Result<(), String> {
+    args.check(&["--conflicts"])?;
+    let file = args
+        .positional
+        .get(1)
+        .ok_or("Missing changeset to import")?;
+    let report = pool.with_writer(|conn| sync::import_from(conn, Path::new(file)))?;
+    println!(
+        "Created {}, updated {}, deleted {}, unchanged {}",
+        report.created, report.updated, report.deleted, report.unchanged
+    );
+    if report.conflicts.is_empty() {
+        return Ok(());
+    }
+    for conflict in &report.conflicts {
+        let acceptance_no = conflict
+            .local
+            .as_ref()
+            .or(conflict.remote.as_ref())
+            .map(|information| information.acceptance_no.as_str())
+            .unwrap_or_default();
+        eprintln!(
+            "Conflict\t{:?}\t{}\t{}",
+            conflict.kind, acceptance_no, conflict.uid
+        );
+    }
+    if let Some(path) = args.value("--conflicts") {
+        let json = serde_json::to_string_pretty(&report.conflicts)
+            .map_err(|_| "Fail to write conflicts")?;
+        fs::write(path, json).map_err(|_| format!("Cannot write {}", path))?;
+    }
+    Err(format!(
+        "{} changes were not applied",
+        report.conflicts.len()
+    ))
+}
+
+fn merge(pool: &Pool, key: &str, args: &Args) -> Result<(), String> {
+    args.check(&["--dry-run"])?;
+    let file = Path::new(args.positional.get(1).ok_or("Missing database to merge")?);
+    let password = env::var(MERGE_PASSWORD_VAR).ok();
+    let password = password.as_deref();
+    let report = if args.flag("--dry-run") {
+        pool.with_writer(|conn| merge::preview(conn, file, password, key))?
+    } else {
+        pool.with_writer(|conn| merge::merge(conn, file, password, key))?
+    };
+    for information in &report.new {
+        println!("New\t{}", information.acceptance_no);
+    }
+    for collision in &report.collisions {
+        println!("Differs\t{}", collision.acceptance_no);
+    }
+    eprintln!(
+        "{} new, {} identical, {} differing reports",
+        report.new.len(),
+        report.identical,
+        report.collisions.len()
+    );
+    Ok(())
+}
+
+fn serve(pool: Pool, args: &Args) -> Result<(), String> {
+    args.check(&["--bind", "--port"])?;
+    let port = args.number("--port", i64::from(server::DEFAULT_PORT))?;
+    let addr = format!("{}:{}", args.value("--bind").unwrap_or("0.0.0.0"), port);
+    let server = Server::start(pool, &addr)?;
+    eprintln!("Serving on {}, press Ctrl+C to stop", server.addr());
+    loop {
+        thread::park();
+    }
+}
+
+fn run() -> Result<(), String> {
+    let args = Args::parse(env::args().skip(1))?;
+    let command = match args.positional.first() {
+        Some(command) if !args.flag("--help") => command.as_str(),
+        _ => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    if !COMMANDS.contains(&command) {
+        return Err(format!("Unknown command {}\n\n{}", command, USAGE));
+    }
+
+    let writes = match command {
+        "import" | "sync-export" | "sync-import" => true,
+        "merge" => !args.flag("--dry-run"),
+        _ => false,
+    };
+    if writes {
+        ensure_local(&args)?;
+    }
+    let (pool, key) = open(&args)?;
+    match command {
+        "list" => list(&pool, &args),
+        "export" => export(&pool, &args),
+        "import" => import(&pool, &args),
+        "backup" => backup(&pool, &key, &args),
+        "sync-export" => sync_export(&pool, &args),
+        "sync-import" => sync_import(&pool, &args),
+        "merge" => merge(&pool, &key, &args),
+        "serve" => serve(pool, &args),
+        _ => stats(&pool, &args),
+    }
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,148 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::{db, Information};
+
+const TIMEZONE_KEY: &str = "date.timezone";
+const FORMAT_KEY: &str = "date.format";
+const DEFAULT_TIMEZONE: Tz = Tz::Asia__Ho_Chi_Minh;
+const DEFAULT_FORMAT: &str = "dd/mm/yyyy";
+
+/// How dates are interpreted and shown. `timezone` is an IANA name and decides
+/// which calendar day a timestamp falls on; `format` uses `dd`, `mm`, `yyyy`
+/// and `yy`, and is used as is for Excel cells.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct DateSettings {
+    timezone: String,
+    format: String,
+}
+
+impl DateSettings {
+    pub fn load(conn: &Connection) -> rusqlite::Result<Self> {
+        Ok(DateSettings {
+            timezone: db::get_setting(conn, TIMEZONE_KEY)?
+                .unwrap_or_else(|| DEFAULT_TIMEZONE.name().to_string()),
+            format: db::get_setting(conn, FORMAT_KEY)?
+                .unwrap_or_else(|| DEFAULT_FORMAT.to_string()),
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> rusqlite::Result<()> {
+        db::set_setting(conn, TIMEZONE_KEY, &self.timezone)?;
+        db::set_setting(conn, FORMAT_KEY, &self.format)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.timezone
+            .parse::<Tz>()
+            .map_err(|_| format!("Unknown timezone {}", self.timezone))?;
+        let separators = self
+            .format
+            .replace("yyyy", "")
+            .replace("yy", "")
+            .replace("dd", "")
+            .replace("mm", "");
+        if separators.chars().any(|c| c.is_alphanumeric())
+            || !self.format.contains("dd")
+            || !self.format.contains("mm")
+            || !self.format.contains("yy")
+        {
+            return Err("Date format must be made of dd, mm and yyyy or yy".into());
+        }
+        Ok(())
+    }
+
+    /// The configured timezone, or the default one if the stored name is not
+    /// known to this version.
+    pub fn tz(&self) -> Tz {
+        self.timezone.parse().unwrap_or(DEFAULT_TIMEZONE)
+    }
+
+    pub fn excel_format(&self) -> &str {
+        &self.format
+    }
+
+    /// The day of `millis`, a value of a column of `kind`, written in the
+    /// configured format.
+    pub fn format(&self, millis: i64, kind: ColumnKind) -> String {
+        let pattern = self
+            .format
+            .replace("yyyy", "%Y")
+            .replace("yy", "%y")
+            .replace("mm", "%m")
+            .replace("dd", "%d");
+        day(millis, kind, &self.tz())
+            .map_or_else(String::new, |date| date.format(&pattern).to_string())
+    }
+}
+
+/// What a millisecond column holds, which decides the day a value stands
+/// for. Every column is read as one kind or the other, whatever its values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnKind {
+    /// A day picked in a form, such as `accepted_at` or `occurred_at`.
+    Date,
+    /// A moment, such as `created_at`.
+    Timestamp,
+}
+
+/// Calendar day in `tz` of `millis`, a value of a column of `kind`.
+///
+/// Dates are picked as days but sent as the midnight of the machine they
+/// were entered on, which is up to 12 hours away from midnight in `tz` when
+/// that machine is set to another timezone. They are rounded to the nearest
+/// midnight to stay on the day that was picked. Timestamps fall on their own
+/// day.
+pub fn day(millis: i64, kind: ColumnKind, tz: &Tz) -> Option<NaiveDate> {
+    let datetime = tz.from_utc_datetime(&NaiveDateTime::from_timestamp_millis(millis)?);
+    match kind {
+        ColumnKind::Date if datetime.time() > NaiveTime::from_hms_opt(12, 0, 0).unwrap() => {
+            datetime.date_naive().succ_opt()
+        }
+        _ => Some(datetime.date_naive()),
+    }
+}
+
+/// Current calendar day in `tz`.
+pub fn today(tz: &Tz) -> NaiveDate {
+    tz.from_utc_datetime(&Utc::now().naive_utc()).date_naive()
+}
+
+/// Year of a date column in `tz`.
+pub fn local_year(millis: i64, tz: &Tz) -> i32 {
+    day(millis, ColumnKind::Date, tz).map_or(1970, |date| date.year())
+}
+
+/// Midnight of `date` in `tz`, the way date columns are stored.
+pub fn midnight(date: NaiveDate, tz: &Tz) -> Option<i64> {
+    tz.from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|datetime| datetime.timestamp_millis())
+}
+
+/// Midnight in `tz` of the day `millis`, a value of a date column, stands
+/// for.
+pub fn start_of_day(millis: i64, tz: &Tz) -> i64 {
+    day(millis, ColumnKind::Date, tz)
+        .and_then(|date| midnight(date, tz))
+        .unwrap_or(millis)
+}
+
+/// Stores every date column of a report as the midnight of its day in `tz`,
+/// whatever the timezone of the machine it was entered on.
+pub fn normalize_information(information: &mut Information, tz: &Tz) {
+    information.accepted_at = start_of_day(information.accepted_at, tz);
+    for date in [
+        &mut information.inv_designated_at,
+        &mut information.inv_handled_at,
+        &mut information.inv_transferred_at,
+        &mut information.inv_extended_at,
+        &mut information.inv_recovered_at,
+        &mut information.inv_canceled_at,
+        &mut information.pro_designated_at,
+    ] {
+        *date = date.map(|millis| start_of_day(millis, tz));
+    }
+}
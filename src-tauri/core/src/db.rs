@@ -0,0 +1,285 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{named_params, Connection, OptionalExtension, Result};
+
+use crate::person;
+
+/// Schema migrations, applied in order. The position of a migration in this
+/// list (starting at 1) is the schema version it brings the database to, which
+/// is tracked in SQLite's `user_version` pragma.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "
+        CREATE TABLE IF NOT EXISTS information (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            acceptance_no VARCHAR(50) NOT NULL UNIQUE,
+            accepted_at INTEGER NOT NULL,
+            plaintiff NVARCHAR(100) NOT NULL,
+            defendant NVARCHAR(100) NOT NULL,
+            description TEXT,
+            law NVARCHAR(200),
+            inv_investigator NVARCHAR(100),
+            inv_designation_no VARCHAR(50),
+            inv_designated_at INTEGER,
+            inv_status TINYINT,
+            inv_handling_no VARCHAR(50),
+            inv_handled_at INTEGER,
+            inv_transferred_at INTEGER,
+            inv_extended_at INTEGER,
+            inv_recovered_at INTEGER,
+            inv_canceled_at INTEGER,
+            pro_procurator NVARCHAR(100),
+            pro_designation_no VARCHAR(50),
+            pro_designated_at INTEGER,
+            pro_additional_evidence_requirement TEXT,
+            pro_non_prosecution_decision TEXT,
+            pro_cessation_decision TEXT,
+            created_at INTEGER NOT NULL,
+            deleted_at INTEGER,
+            updated_at INTEGER
+        );
+    ",
+    // 2: user accounts
+    "
+        CREATE TABLE user (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username VARCHAR(50) NOT NULL UNIQUE,
+            full_name NVARCHAR(100) NOT NULL,
+            password_hash VARCHAR(200) NOT NULL,
+            role VARCHAR(20) NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER
+        );
+    ",
+    // 3: scanned documents attached to reports
+    "
+        CREATE TABLE attachment (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            information_id INTEGER NOT NULL REFERENCES information(id),
+            hash CHAR(64) NOT NULL,
+            file_name NVARCHAR(255) NOT NULL,
+            size INTEGER NOT NULL,
+            doc_type VARCHAR(30) NOT NULL,
+            doc_date INTEGER,
+            description TEXT,
+            created_by INTEGER,
+            created_at INTEGER NOT NULL
+        );
+        CREATE INDEX attachment_information_id ON attachment(information_id);
+        CREATE INDEX attachment_hash ON attachment(hash);
+    ",
+    // 4: several plaintiffs and defendants per report, seeded from the single
+    // names stored so far
+    "
+        CREATE TABLE party (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            information_id INTEGER NOT NULL REFERENCES information(id),
+            role VARCHAR(20) NOT NULL,
+            full_name NVARCHAR(100) NOT NULL,
+            birth_year INTEGER,
+            id_number VARCHAR(20),
+            address NVARCHAR(255),
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER
+        );
+        CREATE INDEX party_information_id ON party(information_id);
+        CREATE INDEX party_full_name ON party(full_name);
+        CREATE INDEX party_id_number ON party(id_number);
+        INSERT INTO party (information_id, role, full_name, created_at)
+            SELECT id, 'plaintiff', trim(plaintiff), created_at FROM information
+            WHERE trim(plaintiff) <> '';
+        INSERT INTO party (information_id, role, full_name, created_at)
+            SELECT id, 'defendant', trim(defendant), created_at FROM information
+            WHERE trim(defendant) <> '';
+    ",
+    // 5: person index on normalized party names
+    "
+        ALTER TABLE party ADD COLUMN normalized_name NVARCHAR(100) NOT NULL DEFAULT '';
+        UPDATE party SET normalized_name = normalize_name(full_name);
+        CREATE INDEX party_normalized_name ON party(normalized_name);
+    ",
+    // 6: reports confirmed as duplicates of, or related to, each other
+    "
+        CREATE TABLE information_link (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            information_id INTEGER NOT NULL REFERENCES information(id),
+            linked_id INTEGER NOT NULL REFERENCES information(id),
+            kind VARCHAR(20) NOT NULL,
+            created_by INTEGER,
+            created_at INTEGER NOT NULL,
+            UNIQUE (information_id, linked_id)
+        );
+        CREATE INDEX information_link_linked_id ON information_link(linked_id);
+    ",
+    // 7: application settings and acceptance number sequences
+    "
+        CREATE TABLE setting (
+            key VARCHAR(100) PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        CREATE TABLE acceptance_sequence (
+            unit NVARCHAR(50) NOT NULL,
+            year INTEGER NOT NULL,
+            last_value INTEGER NOT NULL,
+            PRIMARY KEY (unit, year)
+        );
+    ",
+    // 8: globally unique report ids and deletion records for changeset sync
+    // between installations
+    "
+        ALTER TABLE information ADD COLUMN uid CHAR(32);
+        UPDATE information SET uid = lower(hex(randomblob(16)));
+        CREATE UNIQUE INDEX information_uid ON information(uid);
+        CREATE TABLE information_tombstone (
+            uid CHAR(32) PRIMARY KEY,
+            deleted_at INTEGER NOT NULL
+        );
+    ",
+    // 9: investigators and procurators designated through bulk assignment
+    "
+        CREATE TABLE assignment_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            information_id INTEGER NOT NULL REFERENCES information(id),
+            kind VARCHAR(20) NOT NULL,
+            assignee NVARCHAR(100) NOT NULL,
+            designation_no VARCHAR(50) NOT NULL,
+            designated_at INTEGER NOT NULL,
+            assigned_by INTEGER,
+            assigned_at INTEGER NOT NULL
+        );
+        CREATE INDEX assignment_history_information_id ON assignment_history(information_id);
+    ",
+    // 10: tags put on reports
+    "
+        CREATE TABLE tag (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name NVARCHAR(50) NOT NULL UNIQUE,
+            created_at INTEGER NOT NULL
+        );
+        CREATE TABLE information_tag (
+            information_id INTEGER NOT NULL REFERENCES information(id),
+            tag_id INTEGER NOT NULL REFERENCES tag(id),
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (information_id, tag_id)
+        );
+        CREATE INDEX information_tag_tag_id ON information_tag(tag_id);
+    ",
+    // 11: notes added to reports over time
+    "
+        CREATE TABLE note (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            information_id INTEGER NOT NULL REFERENCES information(id),
+            author_id INTEGER NOT NULL,
+            text TEXT NOT NULL,
+            attachment_id INTEGER REFERENCES attachment(id),
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER
+        );
+        CREATE INDEX note_information_id ON note(information_id);
+    ",
+    // 12: repeatable investigation steps, seeded from the single dates stored
+    // so far, which now hold the date of the latest step of their kind
+    "
+        CREATE TABLE information_event (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            information_id INTEGER NOT NULL REFERENCES information(id),
+            kind VARCHAR(20) NOT NULL,
+            occurred_at INTEGER NOT NULL,
+            document_no VARCHAR(50),
+            note TEXT,
+            created_by INTEGER,
+            created_at INTEGER NOT NULL
+        );
+        CREATE INDEX information_event_information_id ON information_event(information_id);
+        INSERT INTO information_event (information_id, kind, occurred_at, created_at)
+            SELECT id, 'extended', inv_extended_at, coalesce(updated_at, created_at)
+            FROM information WHERE inv_extended_at IS NOT NULL;
+        INSERT INTO information_event (information_id, kind, occurred_at, created_at)
+            SELECT id, 'transferred', inv_transferred_at, coalesce(updated_at, created_at)
+            FROM information WHERE inv_transferred_at IS NOT NULL;
+        INSERT INTO information_event (information_id, kind, occurred_at, created_at)
+            SELECT id, 'recovered', inv_recovered_at, coalesce(updated_at, created_at)
+            FROM information WHERE inv_recovered_at IS NOT NULL;
+        INSERT INTO information_event (information_id, kind, occurred_at, created_at)
+            SELECT id, 'canceled', inv_canceled_at, coalesce(updated_at, created_at)
+            FROM information WHERE inv_canceled_at IS NOT NULL;
+    ",
+    // 13: assignees stored the way they are compared
+    "
+        UPDATE information SET
+            inv_investigator = nullif(trim(inv_investigator), ''),
+            pro_procurator = nullif(trim(pro_procurator), '');
+    ",
+    // 14: when a report was last changed here, by this installation's clock,
+    // apart from the `updated_at` an imported report keeps from its source
+    "
+        ALTER TABLE information ADD COLUMN local_updated_at INTEGER;
+        UPDATE information SET local_updated_at = updated_at;
+    ",
+];
+
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Current time in milliseconds since the epoch, the unit of every timestamp
+/// column.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM setting WHERE key = :key",
+        named_params! { ":key": key },
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "
+        INSERT INTO setting (key, value) VALUES (:key, :value)
+        ON CONFLICT (key) DO UPDATE SET value = excluded.value
+        ",
+        named_params! { ":key": key, ":value": value },
+    )?;
+    Ok(())
+}
+
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Brings the database up to `SCHEMA_VERSION`, running every pending migration
+/// in a single transaction.
+pub fn migrate(conn: &Connection) -> Result<()> {
+    migrate_to(conn, SCHEMA_VERSION)
+}
+
+/// Brings the database up to `version` only, as databases of that version
+/// were, for tests of the later migrations.
+pub fn migrate_to(conn: &Connection, version: i64) -> Result<()> {
+    person::register_functions(conn)?;
+    let version = version.min(SCHEMA_VERSION);
+    let current = schema_version(conn)?;
+    if current >= version {
+        return Ok(());
+    }
+    let tx = conn.unchecked_transaction()?;
+    for migration in &MIGRATIONS[current as usize..version as usize] {
+        tx.execute_batch(migration)?;
+    }
+    tx.pragma_update(None, "user_version", version)?;
+    tx.commit()
+}
+
+/// A new, migrated database living only as long as the connection. Used by
+/// tests and tools that don't need the encrypted file.
+pub fn open_in_memory() -> Result<Connection> {
+    let conn = Connection::open_in_memory()?;
+    migrate(&conn)?;
+    Ok(conn)
+}
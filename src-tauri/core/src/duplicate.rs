@@ -0,0 +1,268 @@
+use std::cmp::Ordering;
+
+use rusqlite::{named_params, Connection, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::{auth::User, db, model::get_information, person};
+
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+/// Reports accepted further apart than this are never considered duplicates.
+const DATE_WINDOW: i64 = 30 * DAY_MILLIS;
+/// Score from which a report is returned as a probable duplicate.
+const THRESHOLD: f64 = 0.75;
+const MAX_CANDIDATES: usize = 5;
+
+const PLAINTIFF_WEIGHT: f64 = 0.3;
+const DEFENDANT_WEIGHT: f64 = 0.3;
+const DESCRIPTION_WEIGHT: f64 = 0.25;
+const DATE_WEIGHT: f64 = 0.15;
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DuplicateCandidate {
+    information_id: i64,
+    acceptance_no: String,
+    accepted_at: i64,
+    plaintiff: String,
+    defendant: String,
+    /// Between 0 and 1, 1 being identical.
+    score: f64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    /// Confirmed to be the same incident registered twice.
+    Duplicate,
+    /// A different report about a related incident.
+    Related,
+}
+
+impl LinkKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LinkKind::Duplicate => "duplicate",
+            LinkKind::Related => "related",
+        }
+    }
+
+    fn parse(value: &str) -> LinkKind {
+        match value {
+            "duplicate" => LinkKind::Duplicate,
+            _ => LinkKind::Related,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct InformationLink {
+    id: i64,
+    /// The other report of the link, seen from the report it was listed for.
+    information_id: i64,
+    acceptance_no: String,
+    accepted_at: i64,
+    kind: LinkKind,
+    created_by: Option<i64>,
+    created_at: i64,
+}
+
+fn similarity(a: &str, b: &str) -> f64 {
+    let a = person::normalize_name(a);
+    let b = person::normalize_name(b);
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    strsim::normalized_levenshtein(&a, &b)
+}
+
+/// Descriptions are long free text, so they are compared on character pairs
+/// rather than edits, which is insensitive to sentences being reordered.
+fn description_similarity(a: Option<&str>, b: Option<&str>) -> f64 {
+    let a = person::normalize_name(a.unwrap_or_default());
+    let b = person::normalize_name(b.unwrap_or_default());
+    if a.is_empty() || b.is_empty() {
+        // Nothing to compare; neither for nor against a duplicate.
+        return 0.5;
+    }
+    strsim::sorensen_dice(&a, &b)
+}
+
+fn date_closeness(a: i64, b: i64) -> f64 {
+    1.0 - ((a - b).abs() as f64 / DATE_WINDOW as f64).min(1.0)
+}
+
+struct Fields {
+    plaintiff: String,
+    defendant: String,
+    description: Option<String>,
+    accepted_at: i64,
+}
+
+fn read_fields(row: &Row) -> Fields {
+    Fields {
+        plaintiff: row.get_unwrap("plaintiff"),
+        defendant: row.get_unwrap("defendant"),
+        description: row.get_unwrap("description"),
+        accepted_at: row.get_unwrap("accepted_at"),
+    }
+}
+
+fn score(a: &Fields, b: &Fields) -> f64 {
+    PLAINTIFF_WEIGHT * similarity(&a.plaintiff, &b.plaintiff)
+        + DEFENDANT_WEIGHT * similarity(&a.defendant, &b.defendant)
+        + DESCRIPTION_WEIGHT
+            * description_similarity(a.description.as_deref(), b.description.as_deref())
+        + DATE_WEIGHT * date_closeness(a.accepted_at, b.accepted_at)
+}
+
+/// Reports accepted around the same time as `information_id` that probably
+/// describe the same incident, best match first. Reports already linked to it
+/// are left out.
+pub fn find_candidates(
+    conn: &Connection,
+    information_id: i64,
+) -> rusqlite::Result<Vec<DuplicateCandidate>> {
+    let report = conn.query_row(
+        "SELECT * FROM information WHERE id = :id",
+        named_params! { ":id": information_id },
+        |row| Ok(read_fields(row)),
+    )?;
+    let mut stmt = conn.prepare(
+        "
+        SELECT * FROM information
+        WHERE
+            id <> :id AND
+            accepted_at BETWEEN :from AND :to AND
+            id NOT IN (
+                SELECT linked_id FROM information_link WHERE information_id = :id
+                UNION
+                SELECT information_id FROM information_link WHERE linked_id = :id
+            )
+        ",
+    )?;
+    let mut rows = stmt.query(named_params! {
+        ":id": information_id,
+        ":from": report.accepted_at - DATE_WINDOW,
+        ":to": report.accepted_at + DATE_WINDOW,
+    })?;
+    let mut candidates = Vec::new();
+    while let Some(row) = rows.next()? {
+        let fields = read_fields(row);
+        let score = score(&report, &fields);
+        if score >= THRESHOLD {
+            candidates.push(DuplicateCandidate {
+                information_id: row.get_unwrap("id"),
+                acceptance_no: row.get_unwrap("acceptance_no"),
+                accepted_at: fields.accepted_at,
+                plaintiff: fields.plaintiff,
+                defendant: fields.defendant,
+                score,
+            });
+        }
+    }
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    candidates.truncate(MAX_CANDIDATES);
+    Ok(candidates)
+}
+
+pub fn delete_for_information(conn: &Connection, ids: &[i64]) -> rusqlite::Result<()> {
+    let mut stmt =
+        conn.prepare("DELETE FROM information_link WHERE information_id = :id OR linked_id = :id")?;
+    for id in ids {
+        stmt.execute(named_params! { ":id": id })?;
+    }
+    Ok(())
+}
+
+/// The links of a report, each showing the other report.
+pub fn list_links(conn: &Connection, information_id: i64) -> Result<Vec<InformationLink>, String> {
+    let mut stmt = conn
+        .prepare(
+            "
+            SELECT
+                information_link.id, information.id AS other_id, information.acceptance_no,
+                information.accepted_at, information_link.kind, information_link.created_by,
+                information_link.created_at
+            FROM information_link
+            JOIN information ON information.id = CASE
+                WHEN information_link.information_id = :information_id
+                THEN information_link.linked_id
+                ELSE information_link.information_id
+            END
+            WHERE
+                information_link.information_id = :information_id OR
+                information_link.linked_id = :information_id
+            ORDER BY information.accepted_at
+            ",
+        )
+        .map_err(|_| "Fail to read links")?;
+    let mut rows = stmt
+        .query(named_params! { ":information_id": information_id })
+        .map_err(|_| "Fail to read links")?;
+    let mut links = Vec::new();
+    while let Ok(Some(row)) = rows.next() {
+        let kind: String = row.get_unwrap("kind");
+        links.push(InformationLink {
+            id: row.get_unwrap("id"),
+            information_id: row.get_unwrap("other_id"),
+            acceptance_no: row.get_unwrap("acceptance_no"),
+            accepted_at: row.get_unwrap("accepted_at"),
+            kind: LinkKind::parse(&kind),
+            created_by: row.get_unwrap("created_by"),
+            created_at: row.get_unwrap("created_at"),
+        });
+    }
+    Ok(links)
+}
+
+/// Confirms two reports as duplicates or links them as related. Linking a pair
+/// again replaces the previous kind.
+pub fn link(
+    conn: &Connection,
+    user: &User,
+    information_id: i64,
+    linked_id: i64,
+    kind: LinkKind,
+) -> Result<(), String> {
+    if information_id == linked_id {
+        return Err("Cannot link a report to itself".into());
+    }
+    // Each pair is stored once, smallest id first.
+    let (first, second) = if information_id < linked_id {
+        (information_id, linked_id)
+    } else {
+        (linked_id, information_id)
+    };
+    for id in [first, second] {
+        get_information(conn, id)
+            .map_err(|_| "Fail to read information")?
+            .ok_or("Information not found")?;
+    }
+    conn.execute(
+        "
+        INSERT INTO information_link (information_id, linked_id, kind, created_by, created_at)
+        VALUES (:information_id, :linked_id, :kind, :created_by, :created_at)
+        ON CONFLICT (information_id, linked_id) DO UPDATE SET
+            kind = excluded.kind,
+            created_by = excluded.created_by,
+            created_at = excluded.created_at
+        ",
+        named_params! {
+            ":information_id": first,
+            ":linked_id": second,
+            ":kind": kind.as_str(),
+            ":created_by": user.id,
+            ":created_at": db::now(),
+        },
+    )
+    .map_err(|_| "Fail to link information")?;
+    Ok(())
+}
+
+pub fn unlink(conn: &Connection, id: i64) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM information_link WHERE id = :id",
+        named_params! { ":id": id },
+    )
+    .map_err(|_| "Fail to unlink information")?;
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A problem with one field of a submitted form.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct FieldError {
+    field: String,
+    message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+}
+
+/// Error of commands that validate their input. It serializes to a plain
+/// string like the other commands' errors, or to `{ "fields": [...] }` when
+/// the input is invalid so the form can show each message next to its field.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+pub enum CommandError {
+    Message(String),
+    Invalid { fields: Vec<FieldError> },
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Message(message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::Message(message.to_string())
+    }
+}
+
+impl From<Vec<FieldError>> for CommandError {
+    fn from(fields: Vec<FieldError>) -> Self {
+        CommandError::Invalid { fields }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::Message(message) => write!(f, "{}", message),
+            CommandError::Invalid { fields } => {
+                let messages: Vec<String> = fields
+                    .iter()
+                    .map(|error| format!("{}: {}", error.field, error.message))
+                    .collect();
+                write!(f, "{}", messages.join(", "))
+            }
+        }
+    }
+}
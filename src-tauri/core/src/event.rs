@@ -0,0 +1,433 @@
+//! Steps of an investigation that can happen several times: extensions,
+//! transfers, recoveries and cancellations. The `inv_*_at` column of each kind
+//! keeps the date of its latest event, for the list, the form and the export.
+
+use rusqlite::{named_params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{Role, User},
+    dates::{self, ColumnKind},
+    db,
+    model::get_information,
+    Information,
+};
+
+/// Maximum length, in characters, of a document number.
+const DOCUMENT_NO_LENGTH: usize = 50;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Extended,
+    Transferred,
+    Recovered,
+    Canceled,
+}
+
+impl EventKind {
+    const ALL: [EventKind; 4] = [
+        EventKind::Extended,
+        EventKind::Transferred,
+        EventKind::Recovered,
+        EventKind::Canceled,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Extended => "extended",
+            EventKind::Transferred => "transferred",
+            EventKind::Recovered => "recovered",
+            EventKind::Canceled => "canceled",
+        }
+    }
+
+    fn parse(value: &str) -> EventKind {
+        match value {
+            "extended" => EventKind::Extended,
+            "transferred" => EventKind::Transferred,
+            "recovered" => EventKind::Recovered,
+            _ => EventKind::Canceled,
+        }
+    }
+
+    /// The column holding the date of the latest event of this kind.
+    fn column(&self) -> &'static str {
+        match self {
+            EventKind::Extended => "inv_extended_at",
+            EventKind::Transferred => "inv_transferred_at",
+            EventKind::Recovered => "inv_recovered_at",
+            EventKind::Canceled => "inv_canceled_at",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct InformationEvent {
+    pub id: i64,
+    pub information_id: i64,
+    pub kind: EventKind,
+    pub occurred_at: i64,
+    pub document_no: Option<String>,
+    pub note: Option<String>,
+    pub created_by: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct NewEvent {
+    pub kind: EventKind,
+    pub occurred_at: i64,
+    pub document_no: Option<String>,
+    pub note: Option<String>,
+}
+
+/// An event as carried to another installation by changesets, without the
+/// ids that only make sense in its database.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct EventDetails {
+    pub kind: EventKind,
+    pub occurred_at: i64,
+    pub document_no: Option<String>,
+    pub note: Option<String>,
+    pub created_at: i64,
+}
+
+fn read_event(row: &Row) -> rusqlite::Result<InformationEvent> {
+    let kind: String = row.get("kind")?;
+    Ok(InformationEvent {
+        id: row.get("id")?,
+        information_id: row.get("information_id")?,
+        kind: EventKind::parse(&kind),
+        occurred_at: row.get("occurred_at")?,
+        document_no: row.get("document_no")?,
+        note: row.get("note")?,
+        created_by: row.get("created_by")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+fn insert(
+    conn: &Connection,
+    information_id: i64,
+    event: &NewEvent,
+    created_by: Option<i64>,
+    created_at: i64,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "
+        INSERT INTO information_event (
+            information_id, kind, occurred_at, document_no, note, created_by, created_at
+        )
+        VALUES (
+            :information_id, :kind, :occurred_at, :document_no, :note, :created_by, :created_at
+        )
+        ",
+        named_params! {
+            ":information_id": information_id,
+            ":kind": event.kind.as_str(),
+            ":occurred_at": event.occurred_at,
+            ":document_no": event.document_no,
+            ":note": event.note,
+            ":created_by": created_by,
+            ":created_at": created_at,
+        },
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Sets the `inv_*_at` columns of a report to the dates of its latest events,
+/// or clears those without events.
+fn derive_latest(conn: &Connection, information_id: i64) -> rusqlite::Result<()> {
+    for kind in EventKind::ALL {
+        // The column name comes from `column`, never from input.
+        conn.execute(
+            &format!(
+                "
+                UPDATE information SET {} = (
+                    SELECT max(occurred_at) FROM information_event
+                    WHERE information_id = :id AND kind = :kind
+                )
+                WHERE id = :id
+                ",
+                kind.column()
+            ),
+            named_params! { ":id": information_id, ":kind": kind.as_str() },
+        )?;
+    }
+    Ok(())
+}
+
+/// Records the `inv_*_at` dates of a report saved from the form as events,
+/// unless one of that kind already has the date, then derives the columns
+/// again. A date cleared in the form comes back while its events remain.
+pub fn record_columns(
+    conn: &Connection,
+    information_id: i64,
+    created_by: Option<i64>,
+) -> rusqlite::Result<()> {
+    let information = match get_information(conn, information_id)? {
+        Some(information) => information,
+        None => return Ok(()),
+    };
+    for (kind, date) in [
+        (EventKind::Extended, information.inv_extended_at),
+        (EventKind::Transferred, information.inv_transferred_at),
+        (EventKind::Recovered, information.inv_recovered_at),
+        (EventKind::Canceled, information.inv_canceled_at),
+    ] {
+        let date = match date {
+            Some(date) => date,
+            None => continue,
+        };
+        let recorded = conn
+            .query_row(
+                "
+                SELECT id FROM information_event
+                WHERE information_id = :id AND kind = :kind AND occurred_at = :occurred_at
+                ",
+                named_params! {
+                    ":id": information_id,
+                    ":kind": kind.as_str(),
+                    ":occurred_at": date,
+                },
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        if recorded.is_none() {
+            let event = NewEvent {
+                kind,
+                occurred_at: date,
+                document_no: None,
+                note: None,
+            };
+            insert(conn, information_id, &event, created_by, db::now())?;
+        }
+    }
+    derive_latest(conn, information_id)
+}
+
+/// The events of a report, in the order they happened.
+pub fn list(conn: &Connection, information_id: i64) -> Result<Vec<InformationEvent>, String> {
+    let read = || -> rusqlite::Result<Vec<InformationEvent>> {
+        let mut stmt = conn.prepare(
+            "
+            SELECT * FROM information_event
+            WHERE information_id = :information_id
+            ORDER BY occurred_at, id
+            ",
+        )?;
+        let events = stmt
+            .query_map(
+                named_params! { ":information_id": information_id },
+                read_event,
+            )?
+            .collect();
+        events
+    };
+    read().map_err(|_| "Fail to read events".into())
+}
+
+fn get(conn: &Connection, id: i64) -> Result<InformationEvent, String> {
+    conn.query_row(
+        "SELECT * FROM information_event WHERE id = :id",
+        named_params! { ":id": id },
+        read_event,
+    )
+    .optional()
+    .map_err(|_| "Fail to read events")?
+    .ok_or_else(|| "Event not found".into())
+}
+
+/// `event` trimmed and dated at the start of its day, once checked against
+/// `information`.
+fn checked_event(
+    conn: &Connection,
+    information: &Information,
+    event: NewEvent,
+) -> Result<NewEvent, String> {
+    let document_no = event
+        .document_no
+        .as_deref()
+        .map(str::trim)
+        .filter(|no| !no.is_empty());
+    if document_no.map_or(false, |no| no.chars().count() > DOCUMENT_NO_LENGTH) {
+        return Err(format!(
+            "Document number must have at most {} characters",
+            DOCUMENT_NO_LENGTH
+        ));
+    }
+
+    let tz = dates::DateSettings::load(conn)
+        .map_err(|_| "Fail to read date settings")?
+        .tz();
+    let occurred_at = dates::start_of_day(event.occurred_at, &tz);
+    let start = information
+        .inv_designated_at
+        .unwrap_or(information.accepted_at);
+    if dates::day(occurred_at, ColumnKind::Date, &tz) < dates::day(start, ColumnKind::Date, &tz) {
+        return Err("Must not be before the designation date".into());
+    }
+
+    Ok(NewEvent {
+        occurred_at,
+        document_no: document_no.map(String::from),
+        note: event
+            .note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty()),
+        ..event
+    })
+}
+
+/// Derives the latest dates of the report again after its events changed,
+/// and marks it updated for changeset sync.
+fn events_changed(conn: &Connection, information_id: i64) -> rusqlite::Result<()> {
+    derive_latest(conn, information_id)?;
+    conn.execute(
+        "
+        UPDATE information SET updated_at = :updated_at, local_updated_at = :updated_at
+        WHERE id = :id
+        ",
+        named_params! { ":updated_at": db::now(), ":id": information_id },
+    )?;
+    Ok(())
+}
+
+/// Records an event of an investigation, by its investigator or a supervisor,
+/// and updates the latest date of its kind.
+pub fn add(
+    conn: &Connection,
+    user: &User,
+    information_id: i64,
+    event: NewEvent,
+) -> Result<InformationEvent, String> {
+    let information = get_information(conn, information_id)
+        .map_err(|_| "Fail to read information")?
+        .ok_or("Information not found")?;
+    let investigator = information
+        .inv_investigator
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .ok_or("Requires an investigator")?;
+    match user.role {
+        Role::Supervisor | Role::Admin => {}
+        Role::Investigator if investigator == user.full_name => {}
+        Role::Investigator => return Err("You can only edit reports assigned to you".into()),
+        _ => return Err("You don't have permission to do this".into()),
+    }
+    let event = checked_event(conn, &information, event)?;
+    let result = conn.unchecked_transaction().and_then(|tx| {
+        let id = insert(&tx, information_id, &event, Some(user.id), db::now())?;
+        events_changed(&tx, information_id)?;
+        tx.commit()?;
+        Ok(id)
+    });
+    let id = result.map_err(|_| "Fail to save event")?;
+    get(conn, id)
+}
+
+/// An event `user` may correct or remove: one they recorded, or any for a
+/// supervisor.
+fn owned_event(conn: &Connection, user: &User, id: i64) -> Result<InformationEvent, String> {
+    let event = get(conn, id)?;
+    match user.role {
+        Role::Supervisor | Role::Admin => Ok(event),
+        _ if event.created_by == Some(user.id) => Ok(event),
+        _ => Err("You can only change the events you recorded".into()),
+    }
+}
+
+/// Corrects an event, by the user who recorded it or a supervisor, and
+/// updates the latest date of its kind and of the kind it had.
+pub fn edit(
+    conn: &Connection,
+    user: &User,
+    id: i64,
+    event: NewEvent,
+) -> Result<InformationEvent, String> {
+    let existing = owned_event(conn, user, id)?;
+    let information = get_information(conn, existing.information_id)
+        .map_err(|_| "Fail to read information")?
+        .ok_or("Information not found")?;
+    let event = checked_event(conn, &information, event)?;
+    let result = conn.unchecked_transaction().and_then(|tx| {
+        tx.execute(
+            "
+            UPDATE information_event
+            SET kind = :kind, occurred_at = :occurred_at, document_no = :document_no, note = :note
+            WHERE id = :id
+            ",
+            named_params! {
+                ":kind": event.kind.as_str(),
+                ":occurred_at": event.occurred_at,
+                ":document_no": event.document_no,
+                ":note": event.note,
+                ":id": id,
+            },
+        )?;
+        events_changed(&tx, existing.information_id)?;
+        tx.commit()
+    });
+    result.map_err(|_| "Fail to save event")?;
+    get(conn, id)
+}
+
+/// Removes an event recorded by mistake, by the user who recorded it or a
+/// supervisor. The date of its kind falls back to the latest remaining event.
+pub fn remove(conn: &Connection, user: &User, id: i64) -> Result<(), String> {
+    let existing = owned_event(conn, user, id)?;
+    let result = conn.unchecked_transaction().and_then(|tx| {
+        tx.execute(
+            "DELETE FROM information_event WHERE id = :id",
+            named_params! { ":id": id },
+        )?;
+        events_changed(&tx, existing.information_id)?;
+        tx.commit()
+    });
+    result.map_err(|_| "Fail to delete event".into())
+}
+
+/// The events of a report as carried by changesets, in the order they
+/// happened.
+pub fn details_of(conn: &Connection, information_id: i64) -> Result<Vec<EventDetails>, String> {
+    Ok(list(conn, information_id)?
+        .into_iter()
+        .map(|event| EventDetails {
+            kind: event.kind,
+            occurred_at: event.occurred_at,
+            document_no: event.document_no,
+            note: event.note,
+            created_at: event.created_at,
+        })
+        .collect())
+}
+
+/// Replaces the events of a report with `events` from another installation,
+/// and derives its latest dates from them.
+pub fn replace_all(
+    conn: &Connection,
+    information_id: i64,
+    events: &[EventDetails],
+) -> rusqlite::Result<()> {
+    delete_for_information(conn, &[information_id])?;
+    for event in events {
+        let new_event = NewEvent {
+            kind: event.kind,
+            occurred_at: event.occurred_at,
+            document_no: event.document_no.clone(),
+            note: event.note.clone(),
+        };
+        insert(conn, information_id, &new_event, None, event.created_at)?;
+    }
+    derive_latest(conn, information_id)
+}
+
+pub fn delete_for_information(conn: &Connection, ids: &[i64]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("DELETE FROM information_event WHERE information_id = :id")?;
+    for id in ids {
+        stmt.execute(named_params! { ":id": id })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,516 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use xlsxwriter::{
+    prelude::{DateTime, FormatAlignment, FormatVerticalAlignment, PaperType},
+    Format, Workbook, Worksheet, XlsxError,
+};
+
+use crate::{
+    dates::{self, ColumnKind, DateSettings},
+    note,
+    pool::Pool,
+    repository::{InformationRepository, SqliteRepository},
+    settings::Settings,
+    tag, Information,
+};
+
+/// Row holding the column titles; rows above it are the merged group titles.
+const HEADER_ROW: u32 = 2;
+const LAST_COLUMN: u16 = 22;
+/// Column of the notes in detailed exports, after every other one.
+const NOTES_COLUMN: u16 = LAST_COLUMN + 1;
+/// Rows fetched per lock of the connection while exporting.
+const CHUNK_SIZE: i64 = 500;
+/// Most characters Excel keeps in a cell; longer strings fail the export.
+const MAX_CELL_LENGTH: usize = 32_767;
+/// Ends text cut to fit in its cell.
+const TRUNCATED_MARKER: &str = "\n… (xem tiếp trong ứng dụng)";
+
+fn init_template(
+    sheet: &mut Worksheet,
+    agency_name: &str,
+    last_column: u16,
+) -> Result<(), XlsxError> {
+    let title_format = Format::new()
+        .set_align(FormatAlignment::CenterAcross)
+        .set_align(FormatAlignment::Center)
+        .set_bold()
+        .to_owned();
+    let title = if agency_name.is_empty() {
+        String::from("Số liệu tin báo")
+    } else {
+        format!("Số liệu tin báo - {}", agency_name)
+    };
+    sheet.merge_range(0, 0, 0, last_column, &title, Some(&title_format))?;
+    sheet.merge_range(1, 0, 1, 6, "Nội dung tin báo", Some(&title_format))?;
+    sheet.merge_range(1, 7, 1, 15, "Cơ quan điếu tra", Some(&title_format))?;
+    sheet.merge_range(1, 16, 1, 21, "Viện kiểm sát", Some(&title_format))?;
+
+    sheet.write_string(2, 0, "STT", Some(&title_format))?;
+    sheet.write_string(2, 1, "Số TL", Some(&title_format))?;
+    sheet.write_string(2, 2, "Ngày TL", Some(&title_format))?;
+    sheet.write_string(2, 3, "Nguyên đơn", Some(&title_format))?;
+    sheet.write_string(2, 4, "Bị đơn", Some(&title_format))?;
+    sheet.write_string(2, 5, "Nội dung", Some(&title_format))?;
+    sheet.write_string(2, 6, "Điều luật", Some(&title_format))?;
+    sheet.write_string(2, 7, "Điều tra viên", Some(&title_format))?;
+    sheet.write_string(2, 8, "Số PC", Some(&title_format))?;
+    sheet.write_string(2, 9, "Ngày PC", Some(&title_format))?;
+    sheet.write_string(2, 10, "Số", Some(&title_format))?;
+    sheet.write_string(2, 11, "Ngày", Some(&title_format))?;
+    sheet.write_string(2, 12, "Chuyển", Some(&title_format))?;
+    sheet.write_string(2, 13, "Gia hạn", Some(&title_format))?;
+    sheet.write_string(2, 14, "Phục hồi", Some(&title_format))?;
+    sheet.write_string(2, 15, "Hủy", Some(&title_format))?;
+    sheet.write_string(2, 16, "KSV thụ lý", Some(&title_format))?;
+    sheet.write_string(2, 17, "Số QĐPC", Some(&title_format))?;
+    sheet.write_string(2, 18, "Ngày", Some(&title_format))?;
+    sheet.write_string(2, 19, "Trao đổi/Yêu cầu BSCC", Some(&title_format))?;
+    sheet.write_string(2, 20, "Kết luận QĐKKT", Some(&title_format))?;
+    sheet.write_string(2, 21, "Kết luận TĐC", Some(&title_format))?;
+    sheet.merge_range(1, 22, 2, 22, "Nhãn", Some(&title_format))?;
+    if last_column == NOTES_COLUMN {
+        sheet.merge_range(
+            1,
+            NOTES_COLUMN,
+            2,
+            NOTES_COLUMN,
+            "Ghi chú",
+            Some(&title_format),
+        )?;
+    }
+
+    sheet.set_column(0, 0, 6.0, None)?;
+    sheet.set_column(1, 1, 12.0, None)?;
+    sheet.set_column(2, 2, 12.0, None)?;
+    sheet.set_column(3, 4, 24.0, None)?;
+    sheet.set_column(5, 5, 48.0, None)?;
+    sheet.set_column(6, 6, 18.0, None)?;
+    sheet.set_column(7, 7, 20.0, None)?;
+    sheet.set_column(8, 8, 10.0, None)?;
+    sheet.set_column(9, 9, 12.0, None)?;
+    sheet.set_column(10, 10, 10.0, None)?;
+    sheet.set_column(11, 15, 12.0, None)?;
+    sheet.set_column(16, 16, 20.0, None)?;
+    sheet.set_column(17, 17, 10.0, None)?;
+    sheet.set_column(18, 18, 12.0, None)?;
+    sheet.set_column(19, 21, 30.0, None)?;
+    sheet.set_column(22, 22, 20.0, None)?;
+    sheet.set_column(NOTES_COLUMN, NOTES_COLUMN, 48.0, None)?;
+
+    sheet.freeze_panes(HEADER_ROW + 1, 0);
+    sheet.repeat_rows(0, HEADER_ROW)?;
+    sheet.set_landscape();
+    sheet.set_paper(PaperType::A4);
+    sheet.fit_to_pages(1, 0);
+    Ok(())
+}
+
+/// Writes `text` cut with a marker where it does not fit in the cell, since
+/// Excel refuses longer strings.
+fn write_text(
+    sheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    text: &str,
+    format: &Format,
+) -> Result<(), XlsxError> {
+    sheet.write_string(row, col, &truncate_cell(text), Some(format))
+}
+
+fn write_date(
+    sheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    millis: Option<i64>,
+    tz: &Tz,
+    format: &Format,
+) -> Result<(), XlsxError> {
+    let date = millis.and_then(|millis| dates::day(millis, ColumnKind::Date, tz));
+    match date {
+        Some(date) => sheet.write_datetime(row, col, &DateTime::from(date), Some(format)),
+        None => sheet.write_blank(row, col, Some(format)),
+    }
+}
+
+struct CellFormats {
+    date: Format,
+    text: Format,
+    wrap: Format,
+}
+
+impl CellFormats {
+    fn new(date_settings: &DateSettings) -> Self {
+        CellFormats {
+            date: Format::new()
+                .set_num_format(date_settings.excel_format())
+                .set_align(FormatAlignment::Center)
+                .set_vertical_align(FormatVerticalAlignment::VerticalTop)
+                .to_owned(),
+            text: Format::new()
+                .set_vertical_align(FormatVerticalAlignment::VerticalTop)
+                .to_owned(),
+            wrap: Format::new()
+                .set_text_wrap()
+                .set_vertical_align(FormatVerticalAlignment::VerticalTop)
+                .to_owned(),
+        }
+    }
+}
+
+/// Writes one chunk of rows; `first_index` is the number of rows already
+/// written so numbering and row positions continue across chunks. `tags` holds
+/// the tag names of the reports that have any, `notes` their notes in detailed
+/// exports.
+fn fill_data(
+    sheet: &mut Worksheet,
+    first_index: usize,
+    data: &[Information],
+    tags: &HashMap<i64, String>,
+    notes: Option<&HashMap<i64, String>>,
+    formats: &CellFormats,
+    tz: &Tz,
+) -> Result<(), XlsxError> {
+    let empty = String::from("");
+    for (offset, information) in data.iter().enumerate() {
+        let index = first_index + offset;
+        let row: u32 = (HEADER_ROW as usize + index + 1).try_into().unwrap();
+        sheet.write_number(row, 0, (index + 1) as f64, Some(&formats.text))?;
+        write_text(sheet, row, 1, &information.acceptance_no, &formats.text)?;
+        write_date(
+            sheet,
+            row,
+            2,
+            Some(information.accepted_at),
+            tz,
+            &formats.date,
+        )?;
+        write_text(sheet, row, 3, &information.plaintiff, &formats.wrap)?;
+        write_text(sheet, row, 4, &information.defendant, &formats.wrap)?;
+        write_text(
+            sheet,
+            row,
+            5,
+            information.description.as_ref().unwrap_or(&empty),
+            &formats.wrap,
+        )?;
+        write_text(
+            sheet,
+            row,
+            6,
+            information.law.as_ref().unwrap_or(&empty),
+            &formats.wrap,
+        )?;
+
+        write_text(
+            sheet,
+            row,
+            7,
+            information.inv_investigator.as_ref().unwrap_or(&empty),
+            &formats.text,
+        )?;
+        write_text(
+            sheet,
+            row,
+            8,
+            information.inv_designation_no.as_ref().unwrap_or(&empty),
+            &formats.text,
+        )?;
+        write_date(
+            sheet,
+            row,
+            9,
+            information.inv_designated_at,
+            tz,
+            &formats.date,
+        )?;
+        write_text(
+            sheet,
+            row,
+            10,
+            information.inv_handling_no.as_ref().unwrap_or(&empty),
+            &formats.text,
+        )?;
+        write_date(
+            sheet,
+            row,
+            11,
+            information.inv_handled_at,
+            tz,
+            &formats.date,
+        )?;
+        write_date(
+            sheet,
+            row,
+            12,
+            information.inv_transferred_at,
+            tz,
+            &formats.date,
+        )?;
+        write_date(
+            sheet,
+            row,
+            13,
+            information.inv_extended_at,
+            tz,
+            &formats.date,
+        )?;
+        write_date(
+            sheet,
+            row,
+            14,
+            information.inv_recovered_at,
+            tz,
+            &formats.date,
+        )?;
+        write_date(
+            sheet,
+            row,
+            15,
+            information.inv_canceled_at,
+            tz,
+            &formats.date,
+        )?;
+
+        write_text(
+            sheet,
+            row,
+            16,
+            information.pro_procurator.as_ref().unwrap_or(&empty),
+            &formats.text,
+        )?;
+        write_text(
+            sheet,
+            row,
+            17,
+            information.pro_designation_no.as_ref().unwrap_or(&empty),
+            &formats.text,
+        )?;
+        write_date(
+            sheet,
+            row,
+            18,
+            information.pro_designated_at,
+            tz,
+            &formats.date,
+        )?;
+        write_text(
+            sheet,
+            row,
+            19,
+            information
+                .pro_additional_evidence_requirement
+                .as_ref()
+                .unwrap_or(&empty),
+            &formats.wrap,
+        )?;
+        write_text(
+            sheet,
+            row,
+            20,
+            information
+                .pro_non_prosecution_decision
+                .as_ref()
+                .unwrap_or(&empty),
+            &formats.wrap,
+        )?;
+        write_text(
+            sheet,
+            row,
+            21,
+            information
+                .pro_cessation_decision
+                .as_ref()
+                .unwrap_or(&empty),
+            &formats.wrap,
+        )?;
+        write_text(
+            sheet,
+            row,
+            22,
+            tags.get(&information.id).unwrap_or(&empty),
+            &formats.wrap,
+        )?;
+        if let Some(notes) = notes {
+            write_text(
+                sheet,
+                row,
+                NOTES_COLUMN,
+                notes.get(&information.id).unwrap_or(&empty),
+                &formats.wrap,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// One line per note: its date, author and text.
+fn format_notes(
+    notes: HashMap<i64, Vec<note::Note>>,
+    date_settings: &DateSettings,
+) -> HashMap<i64, String> {
+    notes
+        .into_iter()
+        .map(|(id, notes)| {
+            let lines: Vec<String> = notes
+                .iter()
+                .map(|note| {
+                    format!(
+                        "{} {}: {}",
+                        date_settings.format(note.created_at, ColumnKind::Timestamp),
+                        note.author_name.as_deref().unwrap_or("?"),
+                        note.text
+                    )
+                })
+                .collect();
+            (id, lines.join("\n"))
+        })
+        .collect()
+}
+
+fn truncate_cell(text: &str) -> Cow<str> {
+    if text.chars().count() <= MAX_CELL_LENGTH {
+        return Cow::Borrowed(text);
+    }
+    let kept = MAX_CELL_LENGTH - TRUNCATED_MARKER.chars().count();
+    let mut truncated: String = text.chars().take(kept).collect();
+    truncated.push_str(TRUNCATED_MARKER);
+    Cow::Owned(truncated)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExportSetting {
+    from: i64,
+    to: i64,
+    path: String,
+    /// Adds a column with the notes of each report.
+    #[serde(default)]
+    notes: bool,
+}
+
+impl ExportSetting {
+    pub fn new(from: i64, to: i64, path: String) -> Self {
+        ExportSetting {
+            from,
+            to,
+            path,
+            notes: false,
+        }
+    }
+
+    pub fn with_notes(self, notes: bool) -> Self {
+        ExportSetting { notes, ..self }
+    }
+
+    pub fn notes(&self) -> bool {
+        self.notes
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Acceptance times the export covers, both ends included.
+    pub fn range(&self) -> (i64, i64) {
+        (self.from, self.to)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Writes the reports accepted in the range of `setting` to its path, calling
+/// `on_progress` after every chunk. Stops and removes the file once
+/// `cancelled` is set.
+pub fn write_workbook(
+    pool: &Pool,
+    setting: &ExportSetting,
+    cancelled: &AtomicBool,
+    on_progress: impl Fn(ExportProgress),
+) -> Result<(), String> {
+    let (total, settings) = pool.with_reader(|conn| -> Result<_, String> {
+        Ok((
+            SqliteRepository::new(conn).count_accepted(setting.from, setting.to)?,
+            Settings::load(conn).map_err(|_| "Fail to read settings")?,
+        ))
+    })?;
+    let tz = settings.dates().tz();
+
+    let workbook = Workbook::new(&setting.path).map_err(|_| "Cannot create workbook")?;
+    let mut sheet = workbook
+        .add_worksheet(None)
+        .map_err(|_| "Cannot create worksheet")?;
+    let last_column = if setting.notes {
+        NOTES_COLUMN
+    } else {
+        LAST_COLUMN
+    };
+    init_template(&mut sheet, settings.agency_name(), last_column)
+        .map_err(|_| "Fail to initialize template")?;
+
+    let formats = CellFormats::new(settings.dates());
+    let mut done = 0;
+    let mut after = (i64::MIN, i64::MIN);
+    on_progress(ExportProgress { done, total });
+    loop {
+        if cancelled.load(Ordering::SeqCst) {
+            // Dropping the workbook flushes it to disk, so remove it afterwards.
+            drop(workbook);
+            let _ = fs::remove_file(&setting.path);
+            return Err("Export cancelled".into());
+        }
+
+        let (chunk, tags, notes) = pool.with_reader(|conn| -> Result<_, String> {
+            let chunk = SqliteRepository::new(conn).export_chunk(
+                setting.from,
+                setting.to,
+                after,
+                CHUNK_SIZE,
+            )?;
+            let ids: Vec<i64> = chunk.iter().map(|information| information.id).collect();
+            let tags = tag::names_for(conn, &ids).map_err(|_| "Fail to read tags")?;
+            let notes = if setting.notes {
+                let notes = note::for_information(conn, &ids).map_err(|_| "Fail to read notes")?;
+                Some(format_notes(notes, settings.dates()))
+            } else {
+                None
+            };
+            Ok((chunk, tags, notes))
+        })?;
+        match chunk.last() {
+            Some(last) => after = (last.accepted_at, last.id),
+            None => break,
+        }
+
+        fill_data(
+            &mut sheet,
+            done,
+            &chunk,
+            &tags,
+            notes.as_ref(),
+            &formats,
+            &tz,
+        )
+        .map_err(|_| "Fail to fill data into file")?;
+        done += chunk.len();
+        on_progress(ExportProgress { done, total });
+    }
+
+    // The filter range has to cover every data row, so it is added last.
+    let last_row: u32 = (HEADER_ROW as usize + done).try_into().unwrap();
+    sheet
+        .autofilter(HEADER_ROW, 0, last_row, last_column)
+        .map_err(|_| "Fail to fill data into file")?;
+    workbook.close().map_err(|_| "Fail to close workbook")?;
+    Ok(())
+}
@@ -0,0 +1,34 @@
+//! Everything docman does with its database that doesn't need a window: the
+//! report model, migrations, queries, validation, backups and Excel export.
+//! The desktop app wraps it in Tauri commands; tools such as `docman-cli` use
+//! it directly.
+
+pub mod assignment;
+pub mod attachment;
+pub mod auth;
+pub mod backup;
+pub mod dates;
+pub mod db;
+pub mod duplicate;
+pub mod error;
+pub mod event;
+pub mod export;
+pub mod merge;
+pub mod model;
+pub mod note;
+pub mod numbering;
+pub mod party;
+pub mod person;
+pub mod pool;
+pub mod remote;
+pub mod repository;
+pub mod server;
+pub mod settings;
+pub mod sync;
+pub mod tag;
+pub mod validation;
+pub mod vault;
+
+pub use model::{
+    Information, InformationCreated, InformationPageQueryOption, InformationPatch, Order,
+};
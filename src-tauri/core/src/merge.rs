@@ -0,0 +1,288 @@
+//! Merging the database of another unit into this one, when units are
+//! consolidated. Reports are matched by acceptance number. Attachments and
+//! links between reports stay in the other database.
+
+use std::{env, fs, path::Path};
+
+use rusqlite::{named_params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::{backup, db, event, model::read_from_row, numbering, vault, Information};
+
+/// Name the other database is attached under.
+const SCHEMA: &str = "merged";
+
+/// A report both databases have, with different content, parties or events.
+#[derive(Serialize, Debug)]
+pub struct MergeCollision {
+    pub acceptance_no: String,
+    pub local: Information,
+    pub other: Information,
+}
+
+/// What `preview` would do, or what `merge` did. Collisions are never merged.
+#[derive(Serialize, Debug, Default)]
+pub struct MergeReport {
+    /// Reports only the other database has. After `merge`, their ids are the
+    /// ones given here.
+    pub new: Vec<Information>,
+    /// Reports both databases have with the same content, parties and events.
+    pub identical: usize,
+    pub collisions: Vec<MergeCollision>,
+}
+
+/// Content of a report, leaving out what differs between databases holding
+/// the same report.
+fn content(information: &Information) -> Information {
+    Information {
+        id: 0,
+        created_at: None,
+        deleted_at: None,
+        updated_at: None,
+        ..information.clone()
+    }
+}
+
+/// Parties and events of report `id` in `schema`, leaving out what differs
+/// between databases holding the same report. They are sorted, so the order
+/// they were added in doesn't matter.
+fn related_content(
+    conn: &Connection,
+    schema: &str,
+    id: i64,
+) -> rusqlite::Result<(Vec<String>, Vec<String>)> {
+    let rows = |table: &str, excluded: &[&str]| -> rusqlite::Result<Vec<String>> {
+        let values = columns(conn, table, excluded)?
+            .iter()
+            .map(|column| format!("quote({})", column))
+            .collect::<Vec<_>>()
+            .join(" || ',' || ");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM {}.{} WHERE information_id = :id",
+            values, schema, table
+        ))?;
+        let mut rows = stmt
+            .query_map(named_params! { ":id": id }, |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        rows.sort();
+        Ok(rows)
+    };
+    Ok((
+        rows(
+            "party",
+            &["id", "information_id", "created_at", "updated_at"],
+        )?,
+        rows(
+            "information_event",
+            &["id", "information_id", "created_by", "created_at"],
+        )?,
+    ))
+}
+
+/// Columns of `table` other than `excluded`, which both databases have since
+/// the attached one is migrated first.
+fn columns(conn: &Connection, table: &str, excluded: &[&str]) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA main.table_info({})", table))?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>("name"))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(names
+        .into_iter()
+        .filter(|name| !excluded.contains(&name.as_str()))
+        .collect())
+}
+
+/// Runs `f` with the database at `path` attached. It is attached through an
+/// upgraded copy encrypted with `key`, so that the original file is left as
+/// it is whatever its password and version.
+fn with_attached<T>(
+    conn: &Connection,
+    path: &Path,
+    password: Option<&str>,
+    key: &str,
+    f: impl FnOnce(&Connection) -> Result<T, String>,
+) -> Result<T, String> {
+    if !path.exists() {
+        return Err(format!("{} does not exist", path.display()));
+    }
+    let password = if vault::is_plaintext(path).map_err(|_| "Cannot read database file")? {
+        ""
+    } else {
+        password.unwrap_or(key)
+    };
+    let suffix: String = conn
+        .query_row("SELECT lower(hex(randomblob(8)))", [], |row| row.get(0))
+        .map_err(|_| "Fail to read database")?;
+    let copy = env::temp_dir().join(format!("docman-merge-{}.db", suffix));
+
+    let result = prepare_copy(path, password, key, &copy).and_then(|_| {
+        conn.execute(
+            &format!("ATTACH DATABASE :path AS {} KEY :key", SCHEMA),
+            named_params! { ":path": copy.display().to_string(), ":key": key },
+        )
+        .map_err(|_| "Fail to open the other database")?;
+        let result = f(conn);
+        let _ = conn.execute(&format!("DETACH DATABASE {}", SCHEMA), []);
+        result
+    });
+    let _ = fs::remove_file(&copy);
+    result
+}
+
+fn prepare_copy(path: &Path, password: &str, key: &str, copy: &Path) -> Result<(), String> {
+    backup::rekeyed_copy(path, password, key, copy)?;
+    let conn = vault::open_encrypted(copy, key).map_err(|_| "Fail to read the other database")?;
+    let version = db::schema_version(&conn).map_err(|_| "Fail to read the other database")?;
+    if version > db::SCHEMA_VERSION {
+        return Err("The other database was made by a newer version of the app".into());
+    }
+    let has_information: i64 = conn
+        .query_row(
+            "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'information'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|_| "Fail to read the other database")?;
+    if has_information == 0 {
+        return Err("The other file is not a docman database".into());
+    }
+    db::migrate(&conn).map_err(|_| "Fail to upgrade the other database".into())
+}
+
+/// Compares every report of the attached database with the one having its
+/// acceptance number here.
+fn plan(conn: &Connection) -> rusqlite::Result<MergeReport> {
+    let mut report = MergeReport::default();
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {}.information ORDER BY id", SCHEMA))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let other = read_from_row(row);
+        let local = conn
+            .query_row(
+                "SELECT * FROM main.information WHERE acceptance_no = :acceptance_no",
+                named_params! { ":acceptance_no": other.acceptance_no },
+                |row| Ok(read_from_row(row)),
+            )
+            .optional()?;
+        match local {
+            None => report.new.push(other),
+            Some(local)
+                if content(&local) == content(&other)
+                    && related_content(conn, "main", local.id)?
+                        == related_content(conn, SCHEMA, other.id)? =>
+            {
+                report.identical += 1
+            }
+            Some(local) => report.collisions.push(MergeCollision {
+                acceptance_no: other.acceptance_no.clone(),
+                local,
+                other,
+            }),
+        }
+    }
+    Ok(report)
+}
+
+/// Copies a report of the attached database along with its parties and
+/// events, keeping its `uid` unless a report here already has it, and marks it
+/// changed here for changeset sync. Returns its id here.
+fn copy_information(conn: &Connection, other_id: i64) -> rusqlite::Result<i64> {
+    let information_columns =
+        columns(conn, "information", &["id", "uid", "local_updated_at"])?.join(", ");
+    conn.execute(
+        &format!(
+            "
+            INSERT INTO main.information (uid, local_updated_at, {columns})
+            SELECT
+                CASE WHEN uid IN (SELECT uid FROM main.information)
+                    THEN lower(hex(randomblob(16))) ELSE uid END,
+                :local_updated_at,
+                {columns}
+            FROM {schema}.information WHERE id = :id
+            ",
+            columns = information_columns,
+            schema = SCHEMA
+        ),
+        named_params! { ":id": other_id, ":local_updated_at": db::now() },
+    )?;
+    let id = conn.last_insert_rowid();
+
+    let party_columns = columns(conn, "party", &["id", "information_id"])?.join(", ");
+    conn.execute(
+        &format!(
+            "
+            INSERT INTO main.party (information_id, {columns})
+            SELECT :information_id, {columns}
+            FROM {schema}.party WHERE information_id = :other_id ORDER BY id
+            ",
+            columns = party_columns,
+            schema = SCHEMA
+        ),
+        named_params! { ":information_id": id, ":other_id": other_id },
+    )?;
+    // Their authors are accounts of the other database.
+    let event_columns = columns(
+        conn,
+        "information_event",
+        &["id", "information_id", "created_by"],
+    )?
+    .join(", ");
+    conn.execute(
+        &format!(
+            "
+            INSERT INTO main.information_event (information_id, {columns})
+            SELECT :information_id, {columns}
+            FROM {schema}.information_event WHERE information_id = :other_id ORDER BY id
+            ",
+            columns = event_columns,
+            schema = SCHEMA
+        ),
+        named_params! { ":information_id": id, ":other_id": other_id },
+    )?;
+    // Dates without events, from before events were kept, become events.
+    event::record_columns(conn, id, None)?;
+    let (acceptance_no, accepted_at): (String, i64) = conn.query_row(
+        "SELECT acceptance_no, accepted_at FROM main.information WHERE id = :id",
+        named_params! { ":id": id },
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    numbering::advance_past(conn, &acceptance_no, accepted_at)?;
+    Ok(id)
+}
+
+/// Lists the reports `merge` would add from the database at `path` and the
+/// ones that collide, without changing anything. `password` defaults to
+/// `key`, the password of this database.
+pub fn preview(
+    conn: &Connection,
+    path: &Path,
+    password: Option<&str>,
+    key: &str,
+) -> Result<MergeReport, String> {
+    with_attached(conn, path, password, key, |conn| {
+        plan(conn).map_err(|_| "Fail to compare databases".into())
+    })
+}
+
+/// Adds the reports of the database at `path` that this one lacks, in a single
+/// transaction. Reports that collide are left for resolving by hand, see
+/// `preview`.
+pub fn merge(
+    conn: &Connection,
+    path: &Path,
+    password: Option<&str>,
+    key: &str,
+) -> Result<MergeReport, String> {
+    with_attached(conn, path, password, key, |conn| {
+        let run = || -> rusqlite::Result<MergeReport> {
+            let tx = conn.unchecked_transaction()?;
+            let mut report = plan(&tx)?;
+            for information in &mut report.new {
+                information.id = copy_information(&tx, information.id)?;
+            }
+            tx.commit()?;
+            Ok(report)
+        };
+        run().map_err(|_| "Fail to merge databases".into())
+    })
+}
@@ -0,0 +1,248 @@
+use std::fmt;
+
+use rusqlite::{named_params, Connection, OptionalExtension, Result, Row};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{duplicate, person};
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Information {
+    pub id: i64,
+    pub acceptance_no: String,
+    pub accepted_at: i64,
+    pub plaintiff: String,
+    pub defendant: String,
+    pub description: Option<String>,
+    pub law: Option<String>,
+    //Investigation
+    pub inv_investigator: Option<String>,
+    pub inv_designation_no: Option<String>,
+    pub inv_designated_at: Option<i64>,
+    pub inv_status: Option<i64>,
+    pub inv_handling_no: Option<String>,
+    pub inv_handled_at: Option<i64>,
+    pub inv_transferred_at: Option<i64>,
+    pub inv_extended_at: Option<i64>,
+    pub inv_recovered_at: Option<i64>,
+    pub inv_canceled_at: Option<i64>,
+    //Prosecution
+    pub pro_procurator: Option<String>,
+    pub pro_designation_no: Option<String>,
+    pub pro_designated_at: Option<i64>,
+    pub pro_additional_evidence_requirement: Option<String>,
+    pub pro_non_prosecution_decision: Option<String>,
+    pub pro_cessation_decision: Option<String>,
+    pub created_at: Option<i64>,
+    pub deleted_at: Option<i64>,
+    pub updated_at: Option<i64>,
+}
+
+/// Result of `create_information`, warning about parties already named in
+/// earlier reports and about reports that are probably the same incident.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct InformationCreated {
+    pub id: i64,
+    pub acceptance_no: String,
+    pub warnings: Vec<person::KnownPerson>,
+    pub duplicates: Vec<duplicate::DuplicateCandidate>,
+}
+
+/// Changes to some fields of a report, for `InformationRepository::patch`. A
+/// field left out of the JSON is unchanged; a nullable field given as `null`
+/// is cleared, while `null` for a required one is refused. A new plaintiff or
+/// defendant renames the report's only party of that role, as in `update`.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct InformationPatch {
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub acceptance_no: Option<String>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub accepted_at: Option<i64>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub plaintiff: Option<String>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub defendant: Option<String>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub description: Option<Option<String>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub law: Option<Option<String>>,
+    //Investigation
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub inv_investigator: Option<Option<String>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub inv_designation_no: Option<Option<String>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub inv_designated_at: Option<Option<i64>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub inv_status: Option<Option<i64>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub inv_handling_no: Option<Option<String>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub inv_handled_at: Option<Option<i64>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub inv_transferred_at: Option<Option<i64>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub inv_extended_at: Option<Option<i64>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub inv_recovered_at: Option<Option<i64>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub inv_canceled_at: Option<Option<i64>>,
+    //Prosecution
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub pro_procurator: Option<Option<String>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub pro_designation_no: Option<Option<String>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub pro_designated_at: Option<Option<i64>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub pro_additional_evidence_requirement: Option<Option<String>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub pro_non_prosecution_decision: Option<Option<String>>,
+    #[serde(deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub pro_cessation_decision: Option<Option<String>>,
+}
+
+/// Reads a field that is present, `null` included, as `Some`. Absent fields
+/// are `None` through `#[serde(default)]`.
+fn present<'de, T, D>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+impl InformationPatch {
+    /// `information` with the fields of the patch replaced.
+    pub fn apply(self, information: Information) -> Information {
+        Information {
+            acceptance_no: self.acceptance_no.unwrap_or(information.acceptance_no),
+            accepted_at: self.accepted_at.unwrap_or(information.accepted_at),
+            plaintiff: self.plaintiff.unwrap_or(information.plaintiff),
+            defendant: self.defendant.unwrap_or(information.defendant),
+            description: self.description.unwrap_or(information.description),
+            law: self.law.unwrap_or(information.law),
+            inv_investigator: self
+                .inv_investigator
+                .unwrap_or(information.inv_investigator),
+            inv_designation_no: self
+                .inv_designation_no
+                .unwrap_or(information.inv_designation_no),
+            inv_designated_at: self
+                .inv_designated_at
+                .unwrap_or(information.inv_designated_at),
+            inv_status: self.inv_status.unwrap_or(information.inv_status),
+            inv_handling_no: self.inv_handling_no.unwrap_or(information.inv_handling_no),
+            inv_handled_at: self.inv_handled_at.unwrap_or(information.inv_handled_at),
+            inv_transferred_at: self
+                .inv_transferred_at
+                .unwrap_or(information.inv_transferred_at),
+            inv_extended_at: self.inv_extended_at.unwrap_or(information.inv_extended_at),
+            inv_recovered_at: self
+                .inv_recovered_at
+                .unwrap_or(information.inv_recovered_at),
+            inv_canceled_at: self.inv_canceled_at.unwrap_or(information.inv_canceled_at),
+            pro_procurator: self.pro_procurator.unwrap_or(information.pro_procurator),
+            pro_designation_no: self
+                .pro_designation_no
+                .unwrap_or(information.pro_designation_no),
+            pro_designated_at: self
+                .pro_designated_at
+                .unwrap_or(information.pro_designated_at),
+            pro_additional_evidence_requirement: self
+                .pro_additional_evidence_requirement
+                .unwrap_or(information.pro_additional_evidence_requirement),
+            pro_non_prosecution_decision: self
+                .pro_non_prosecution_decision
+                .unwrap_or(information.pro_non_prosecution_decision),
+            pro_cessation_decision: self
+                .pro_cessation_decision
+                .unwrap_or(information.pro_cessation_decision),
+            ..information
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
+pub enum Order {
+    ASC,
+    DESC,
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Order::ASC => write!(f, "ASC"),
+            Order::DESC => write!(f, "DESC"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct InformationPageQueryOption {
+    pub offset: i64,
+    pub limit: i64,
+    pub order: Order,
+    pub search: Option<String>,
+    /// Ids of tags the listed reports must all carry.
+    #[serde(default)]
+    pub tags: Vec<i64>,
+    /// Only reports past their handling deadline, see `DeadlineSettings`.
+    #[serde(default)]
+    pub overdue: bool,
+}
+
+impl Default for InformationPageQueryOption {
+    fn default() -> Self {
+        InformationPageQueryOption {
+            offset: 0,
+            limit: 10,
+            order: Order::DESC,
+            search: None,
+            tags: Vec::new(),
+            overdue: false,
+        }
+    }
+}
+
+pub fn get_information(conn: &Connection, id: i64) -> Result<Option<Information>> {
+    conn.query_row(
+        "SELECT * FROM information WHERE id = :id",
+        named_params! { ":id": id },
+        |row| Ok(read_from_row(row)),
+    )
+    .optional()
+}
+
+pub fn read_from_row(row: &Row) -> Information {
+    Information {
+        id: row.get_unwrap("id"),
+        acceptance_no: row.get_unwrap("acceptance_no"),
+        accepted_at: row.get_unwrap("accepted_at"),
+        plaintiff: row.get_unwrap("plaintiff"),
+        defendant: row.get_unwrap("defendant"),
+        description: row.get_unwrap::<&str, Option<String>>("description"),
+        law: row.get_unwrap::<&str, Option<String>>("law"),
+        inv_investigator: row.get_unwrap::<&str, Option<String>>("inv_investigator"),
+        inv_designated_at: row.get_unwrap::<&str, Option<i64>>("inv_designated_at"),
+        inv_designation_no: row.get_unwrap::<&str, Option<String>>("inv_designation_no"),
+        inv_status: row.get_unwrap::<&str, Option<i64>>("inv_status"),
+        inv_handled_at: row.get_unwrap::<&str, Option<i64>>("inv_handled_at"),
+        inv_handling_no: row.get_unwrap::<&str, Option<String>>("inv_handling_no"),
+        inv_transferred_at: row.get_unwrap::<&str, Option<i64>>("inv_transferred_at"),
+        inv_canceled_at: row.get_unwrap::<&str, Option<i64>>("inv_canceled_at"),
+        inv_recovered_at: row.get_unwrap::<&str, Option<i64>>("inv_recovered_at"),
+        inv_extended_at: row.get_unwrap::<&str, Option<i64>>("inv_extended_at"),
+        pro_procurator: row.get_unwrap::<&str, Option<String>>("pro_procurator"),
+        pro_designated_at: row.get_unwrap::<&str, Option<i64>>("pro_designated_at"),
+        pro_designation_no: row.get_unwrap::<&str, Option<String>>("pro_designation_no"),
+        pro_additional_evidence_requirement: row
+            .get_unwrap::<&str, Option<String>>("pro_additional_evidence_requirement"),
+        pro_cessation_decision: row.get_unwrap::<&str, Option<String>>("pro_cessation_decision"),
+        pro_non_prosecution_decision: row
+            .get_unwrap::<&str, Option<String>>("pro_non_prosecution_decision"),
+        created_at: row.get_unwrap::<&str, Option<i64>>("created_at"),
+        updated_at: row.get_unwrap::<&str, Option<i64>>("updated_at"),
+        deleted_at: row.get_unwrap::<&str, Option<i64>>("deleted_at"),
+    }
+}
@@ -0,0 +1,192 @@
+//! Remarks added to a report over time. Notes are never deleted; their author
+//! may correct their text.
+
+use std::collections::HashMap;
+
+use rusqlite::{named_params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{self, User},
+    db,
+    model::get_information,
+};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Note {
+    pub id: i64,
+    pub information_id: i64,
+    pub author_id: i64,
+    /// Full name of the author, if the account still exists.
+    pub author_name: Option<String>,
+    pub text: String,
+    /// An attachment of the same report the note is about.
+    pub attachment_id: Option<i64>,
+    pub attachment_name: Option<String>,
+    pub created_at: i64,
+    /// Set once the author has edited the note.
+    pub updated_at: Option<i64>,
+}
+
+const SELECT_NOTES: &str = "
+    SELECT
+        note.*,
+        user.full_name AS author_name,
+        attachment.file_name AS attachment_name
+    FROM note
+    LEFT JOIN user ON user.id = note.author_id
+    LEFT JOIN attachment ON attachment.id = note.attachment_id
+";
+
+fn read_note(row: &Row) -> rusqlite::Result<Note> {
+    Ok(Note {
+        id: row.get("id")?,
+        information_id: row.get("information_id")?,
+        author_id: row.get("author_id")?,
+        author_name: row.get("author_name")?,
+        text: row.get("text")?,
+        attachment_id: row.get("attachment_id")?,
+        attachment_name: row.get("attachment_name")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+fn checked_text(text: &str) -> Result<&str, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Note text is required".into());
+    }
+    Ok(text)
+}
+
+fn get(conn: &Connection, id: i64) -> Result<Note, String> {
+    conn.query_row(
+        &format!("{} WHERE note.id = :id", SELECT_NOTES),
+        named_params! { ":id": id },
+        read_note,
+    )
+    .optional()
+    .map_err(|_| "Fail to read notes")?
+    .ok_or_else(|| "Note not found".into())
+}
+
+/// The notes of a report, oldest first.
+pub fn list(conn: &Connection, information_id: i64) -> Result<Vec<Note>, String> {
+    let read = || -> rusqlite::Result<Vec<Note>> {
+        let mut stmt = conn.prepare(&format!(
+            "
+            {}
+            WHERE note.information_id = :information_id
+            ORDER BY note.created_at, note.id
+            ",
+            SELECT_NOTES
+        ))?;
+        let notes = stmt
+            .query_map(
+                named_params! { ":information_id": information_id },
+                read_note,
+            )?
+            .collect();
+        notes
+    };
+    read().map_err(|_| "Fail to read notes".into())
+}
+
+/// Adds a note, by a supervisor or by whom the report is assigned to, see
+/// `auth::ensure_assigned`.
+pub fn add(
+    conn: &Connection,
+    user: &User,
+    information_id: i64,
+    text: &str,
+    attachment_id: Option<i64>,
+) -> Result<Note, String> {
+    let text = checked_text(text)?;
+    let information = get_information(conn, information_id)
+        .map_err(|_| "Fail to read information")?
+        .ok_or("Information not found")?;
+    auth::ensure_assigned(user, &information)?;
+    if let Some(attachment_id) = attachment_id {
+        let attached_to: Option<i64> = conn
+            .query_row(
+                "SELECT information_id FROM attachment WHERE id = :id",
+                named_params! { ":id": attachment_id },
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|_| "Fail to read attachment")?;
+        if attached_to != Some(information_id) {
+            return Err("Attachment not found".into());
+        }
+    }
+    conn.execute(
+        "
+        INSERT INTO note (information_id, author_id, text, attachment_id, created_at)
+        VALUES (:information_id, :author_id, :text, :attachment_id, :created_at)
+        ",
+        named_params! {
+            ":information_id": information_id,
+            ":author_id": user.id,
+            ":text": text,
+            ":attachment_id": attachment_id,
+            ":created_at": db::now(),
+        },
+    )
+    .map_err(|_| "Fail to save note")?;
+    get(conn, conn.last_insert_rowid())
+}
+
+/// Replaces the text of a note; only its author may.
+pub fn edit(conn: &Connection, user: &User, id: i64, text: &str) -> Result<Note, String> {
+    let text = checked_text(text)?;
+    if get(conn, id)?.author_id != user.id {
+        return Err("You can only edit your own notes".into());
+    }
+    conn.execute(
+        "UPDATE note SET text = :text, updated_at = :updated_at WHERE id = :id",
+        named_params! { ":text": text, ":updated_at": db::now(), ":id": id },
+    )
+    .map_err(|_| "Fail to save note")?;
+    get(conn, id)
+}
+
+/// The notes of each report of `ids` that has any, for the detailed export.
+pub fn for_information(
+    conn: &Connection,
+    ids: &[i64],
+) -> rusqlite::Result<HashMap<i64, Vec<Note>>> {
+    let id_list = ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE note.information_id IN ({}) ORDER BY note.created_at, note.id",
+        SELECT_NOTES, id_list
+    ))?;
+    let mut rows = stmt.query([])?;
+    let mut notes: HashMap<i64, Vec<Note>> = HashMap::new();
+    while let Some(row) = rows.next()? {
+        let note = read_note(row)?;
+        notes.entry(note.information_id).or_default().push(note);
+    }
+    Ok(notes)
+}
+
+/// Drops references to an attachment being removed; the notes stay.
+pub fn detach_attachment(conn: &Connection, attachment_id: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE note SET attachment_id = NULL WHERE attachment_id = :attachment_id",
+        named_params! { ":attachment_id": attachment_id },
+    )?;
+    Ok(())
+}
+
+pub fn delete_for_information(conn: &Connection, ids: &[i64]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("DELETE FROM note WHERE information_id = :id")?;
+    for id in ids {
+        stmt.execute(named_params! { ":id": id })?;
+    }
+    Ok(())
+}
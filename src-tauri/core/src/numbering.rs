@@ -0,0 +1,271 @@
+use rusqlite::{named_params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dates::{self, DateSettings},
+    db,
+};
+
+const PATTERN_KEY: &str = "numbering.pattern";
+const UNIT_KEY: &str = "numbering.unit";
+const DEFAULT_PATTERN: &str = "{seq}/TB-{yyyy}";
+/// Widest zero padding of `{seq:N}`, more digits than a year ever needs.
+const MAX_SEQ_WIDTH: usize = 10;
+
+/// How acceptance numbers are generated. `pattern` may contain `{seq}` (or
+/// `{seq:N}` to zero-pad it to N digits, at most `MAX_SEQ_WIDTH`), `{yyyy}`,
+/// `{yy}` and `{unit}`. The sequence restarts at 1 every year and for every
+/// unit.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct NumberingScheme {
+    pattern: String,
+    unit: String,
+}
+
+impl NumberingScheme {
+    pub fn load(conn: &Connection) -> rusqlite::Result<Self> {
+        Ok(NumberingScheme {
+            pattern: db::get_setting(conn, PATTERN_KEY)?
+                .unwrap_or_else(|| DEFAULT_PATTERN.to_string()),
+            unit: db::get_setting(conn, UNIT_KEY)?.unwrap_or_default(),
+        })
+    }
+
+    pub fn save(&self, conn: &Connection) -> rusqlite::Result<()> {
+        db::set_setting(conn, PATTERN_KEY, &self.pattern)?;
+        db::set_setting(conn, UNIT_KEY, self.unit.trim())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.pattern.contains("{seq") {
+            return Err("Pattern must contain {seq}".into());
+        }
+        // The sequence restarts every year, so numbers would repeat otherwise.
+        if !self.pattern.contains("{yyyy}") && !self.pattern.contains("{yy}") {
+            return Err("Pattern must contain {yyyy} or {yy}".into());
+        }
+        // Checks every placeholder is known.
+        self.format(1, 2000)?;
+        Ok(())
+    }
+
+    fn format(&self, seq: i64, year: i32) -> Result<String, String> {
+        let mut result = String::new();
+        let mut rest = self.pattern.as_str();
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or("Unclosed placeholder in pattern")?;
+            let value = match &rest[start + 1..end] {
+                "seq" => seq.to_string(),
+                "yyyy" => format!("{:04}", year),
+                "yy" => format!("{:02}", year % 100),
+                "unit" => self.unit.clone(),
+                placeholder => match placeholder.strip_prefix("seq:") {
+                    Some(width) => {
+                        let width: usize = width
+                            .parse()
+                            .ok()
+                            .filter(|width| *width <= MAX_SEQ_WIDTH)
+                            .ok_or_else(|| format!("Invalid placeholder {{{}}}", placeholder))?;
+                        format!("{:0width$}", seq, width = width)
+                    }
+                    None => return Err(format!("Unknown placeholder {{{}}}", placeholder)),
+                },
+            };
+            result.push_str(&value);
+            rest = &rest[end + 1..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Sequence value `acceptance_no` was generated from in `year`, if it
+    /// follows the pattern.
+    fn sequence_of(&self, acceptance_no: &str, year: i32) -> Option<i64> {
+        let start = self.pattern.find("{seq")?;
+        let end = start + self.pattern[start..].find('}')?;
+        let part = |pattern: &str| {
+            NumberingScheme {
+                pattern: pattern.to_string(),
+                unit: self.unit.clone(),
+            }
+            .format(0, year)
+            .ok()
+        };
+        let digits = acceptance_no
+            .strip_prefix(part(&self.pattern[..start])?.as_str())?
+            .strip_suffix(part(&self.pattern[end + 1..])?.as_str())?;
+        if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let seq = digits.parse().ok()?;
+        // Rules out padding the pattern doesn't produce.
+        if self.format(seq, year).ok()? != acceptance_no {
+            return None;
+        }
+        Some(seq)
+    }
+}
+
+/// Year a report accepted at `accepted_at` is numbered in.
+fn year_of(conn: &Connection, accepted_at: i64) -> Result<i32, String> {
+    let settings = DateSettings::load(conn).map_err(|_| "Fail to read date settings")?;
+    Ok(dates::local_year(accepted_at, &settings.tz()))
+}
+
+fn last_value(conn: &Connection, unit: &str, year: i32) -> rusqlite::Result<i64> {
+    Ok(conn
+        .query_row(
+            "SELECT last_value FROM acceptance_sequence WHERE unit = :unit AND year = :year",
+            named_params! { ":unit": unit, ":year": year },
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or(0))
+}
+
+fn set_last_value(conn: &Connection, unit: &str, year: i32, seq: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "
+        INSERT INTO acceptance_sequence (unit, year, last_value)
+        VALUES (:unit, :year, :seq)
+        ON CONFLICT (unit, year) DO UPDATE SET last_value = excluded.last_value
+        ",
+        named_params! { ":unit": unit, ":year": year, ":seq": seq },
+    )?;
+    Ok(())
+}
+
+/// Last value of the sequence for `unit` and `year`, counting the numbers
+/// after it that reports already have: those typed in before the sequence
+/// existed, or under a pattern changed back to since.
+fn last_taken(conn: &Connection, scheme: &NumberingScheme, year: i32) -> rusqlite::Result<i64> {
+    let mut last = last_value(conn, &scheme.unit, year)?;
+    // A pattern that doesn't format was never saved, so has no numbers.
+    while let Ok(next) = scheme.format(last + 1, year) {
+        if !is_used(conn, &next, None)? {
+            break;
+        }
+        last += 1;
+    }
+    Ok(last)
+}
+
+/// Next sequence value for `unit` and `year` with the number it formats to.
+fn next_number(
+    conn: &Connection,
+    scheme: &NumberingScheme,
+    year: i32,
+) -> Result<(i64, String), String> {
+    let seq = last_taken(conn, scheme, year).map_err(|_| "Fail to read sequence")? + 1;
+    Ok((seq, scheme.format(seq, year)?))
+}
+
+/// Allocates the next acceptance number. Must run inside the transaction
+/// inserting the report, so a failed insert gives the number back and the
+/// sequence stays gapless.
+pub fn allocate(conn: &Connection, accepted_at: i64) -> Result<String, String> {
+    let scheme = NumberingScheme::load(conn).map_err(|_| "Fail to read numbering scheme")?;
+    let year = year_of(conn, accepted_at)?;
+    let (seq, acceptance_no) = next_number(conn, &scheme, year)?;
+    set_last_value(conn, &scheme.unit, year, seq).map_err(|_| "Fail to allocate number")?;
+    Ok(acceptance_no)
+}
+
+/// Where `acceptance_no` falls in the sequence of the current scheme: the
+/// unit, year and sequence value it was generated from, and the last value
+/// taken. `None` when it doesn't follow the pattern.
+fn position(
+    conn: &Connection,
+    acceptance_no: &str,
+    accepted_at: i64,
+) -> rusqlite::Result<Option<(String, i32, i64, i64)>> {
+    let scheme = NumberingScheme::load(conn)?;
+    let tz = DateSettings::load(conn)?.tz();
+    let year = dates::local_year(accepted_at, &tz);
+    match scheme.sequence_of(acceptance_no, year) {
+        Some(seq) => {
+            let last = last_taken(conn, &scheme, year)?;
+            Ok(Some((scheme.unit, year, seq, last)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Takes a number typed in by hand out of the sequence. Only the next number
+/// of the sequence can be typed in, a later one would leave a gap; numbers
+/// outside the pattern are not part of the sequence.
+pub fn claim(conn: &Connection, acceptance_no: &str, accepted_at: i64) -> Result<(), String> {
+    let position =
+        position(conn, acceptance_no, accepted_at).map_err(|_| "Fail to read numbering scheme")?;
+    if let Some((unit, year, seq, last)) = position {
+        if seq > last + 1 {
+            return Err(format!(
+                "Acceptance number {} would skip numbers of the sequence",
+                acceptance_no
+            ));
+        }
+        if seq == last + 1 {
+            set_last_value(conn, &unit, year, seq).map_err(|_| "Fail to allocate number")?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves the sequence past a number imported from another installation, so
+/// it is never generated here. Gaps left by the other installation are kept.
+pub fn advance_past(
+    conn: &Connection,
+    acceptance_no: &str,
+    accepted_at: i64,
+) -> rusqlite::Result<()> {
+    if let Some((unit, year, seq, last)) = position(conn, acceptance_no, accepted_at)? {
+        if seq > last {
+            set_last_value(conn, &unit, year, seq)?;
+        }
+    }
+    Ok(())
+}
+
+fn is_used(
+    conn: &Connection,
+    acceptance_no: &str,
+    except_id: Option<i64>,
+) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "
+        SELECT count(*) FROM information
+        WHERE acceptance_no = :acceptance_no AND id IS NOT :except_id
+        ",
+        named_params! { ":acceptance_no": acceptance_no, ":except_id": except_id },
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+/// Fails with a message naming the number when another report already has it,
+/// rather than the generic error of the UNIQUE constraint.
+pub fn ensure_unused(
+    conn: &Connection,
+    acceptance_no: &str,
+    except_id: Option<i64>,
+) -> Result<(), String> {
+    if is_used(conn, acceptance_no, except_id).map_err(|_| "Fail to read information")? {
+        return Err(format!(
+            "Acceptance number {} is already used",
+            acceptance_no
+        ));
+    }
+    Ok(())
+}
+
+/// Number the next report accepted at `accepted_at` would get, without
+/// allocating it.
+pub fn preview(conn: &Connection, accepted_at: i64) -> Result<String, String> {
+    let scheme = NumberingScheme::load(conn).map_err(|_| "Fail to read numbering scheme")?;
+    let year = year_of(conn, accepted_at)?;
+    next_number(conn, &scheme, year).map(|(_, acceptance_no)| acceptance_no)
+}
@@ -0,0 +1,455 @@
+use rusqlite::{named_params, types::Type, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db,
+    model::{get_information, Information},
+    person,
+};
+
+/// Separator used when parties are listed in a single cell or column.
+const NAME_SEPARATOR: &str = ", ";
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PartyRole {
+    /// Nguyên đơn
+    Plaintiff,
+    /// Bị đơn
+    Defendant,
+}
+
+impl PartyRole {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PartyRole::Plaintiff => "plaintiff",
+            PartyRole::Defendant => "defendant",
+        }
+    }
+
+    fn parse(value: &str) -> Result<PartyRole, String> {
+        match value {
+            "plaintiff" => Ok(PartyRole::Plaintiff),
+            "defendant" => Ok(PartyRole::Defendant),
+            _ => Err(format!("Unknown party role {}", value)),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Party {
+    id: i64,
+    information_id: i64,
+    #[serde(flatten)]
+    details: PartyDetails,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PartyDetails {
+    role: PartyRole,
+    full_name: String,
+    birth_year: Option<i64>,
+    id_number: Option<String>,
+    address: Option<String>,
+}
+
+/// A party found by `search`, with the report it belongs to.
+#[derive(Serialize, Debug)]
+pub struct PartyMatch {
+    #[serde(flatten)]
+    party: Party,
+    acceptance_no: String,
+    accepted_at: i64,
+}
+
+/// Fails on a role this version doesn't know rather than guessing one.
+fn read_party(row: &Row) -> rusqlite::Result<Party> {
+    let role: String = row.get("role")?;
+    let role = PartyRole::parse(&role).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(
+            row.as_ref().column_index("role").unwrap_or_default(),
+            Type::Text,
+            err.into(),
+        )
+    })?;
+    Ok(Party {
+        id: row.get_unwrap("id"),
+        information_id: row.get_unwrap("information_id"),
+        details: PartyDetails {
+            role,
+            full_name: row.get_unwrap("full_name"),
+            birth_year: row.get_unwrap("birth_year"),
+            id_number: row.get_unwrap("id_number"),
+            address: row.get_unwrap("address"),
+        },
+    })
+}
+
+fn get_party(conn: &Connection, id: i64) -> Result<Party, String> {
+    conn.query_row(
+        "SELECT * FROM party WHERE id = :id",
+        named_params! { ":id": id },
+        read_party,
+    )
+    .optional()
+    .map_err(|_| "Fail to read party")?
+    .ok_or_else(|| "Party not found".into())
+}
+
+fn validate(details: &PartyDetails) -> Result<(), String> {
+    if details.full_name.trim().is_empty() {
+        return Err("Full name is required".into());
+    }
+    Ok(())
+}
+
+/// ID number as stored, so the person index can match it exactly.
+fn id_number(details: &PartyDetails) -> Option<String> {
+    details
+        .id_number
+        .as_deref()
+        .map(person::normalize_id_number)
+        .filter(|id_number| !id_number.is_empty())
+}
+
+fn insert_party(
+    conn: &Connection,
+    information_id: i64,
+    details: &PartyDetails,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "
+        INSERT INTO party (
+            information_id, role, full_name, normalized_name, birth_year, id_number, address,
+            created_at
+        )
+        VALUES (
+            :information_id, :role, :full_name, :normalized_name, :birth_year, :id_number,
+            :address, :created_at
+        )
+        ",
+        named_params! {
+            ":information_id": information_id,
+            ":role": details.role.as_str(),
+            ":full_name": details.full_name.trim(),
+            ":normalized_name": person::normalize_name(&details.full_name),
+            ":birth_year": details.birth_year,
+            ":id_number": id_number(details),
+            ":address": details.address,
+            ":created_at": db::now(),
+        },
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Rewrites the `plaintiff` and `defendant` columns of a report as its parties'
+/// names joined, so list views, search and exports keep working on them. A
+/// role without parties is left as it is.
+pub fn sync_names(conn: &Connection, information_id: i64) -> rusqlite::Result<()> {
+    for role in [PartyRole::Plaintiff, PartyRole::Defendant] {
+        let names: Option<String> = conn.query_row(
+            "
+            SELECT group_concat(full_name, :separator) FROM (
+                SELECT full_name FROM party
+                WHERE information_id = :information_id AND role = :role
+                ORDER BY id
+            )
+            ",
+            named_params! {
+                ":separator": NAME_SEPARATOR,
+                ":information_id": information_id,
+                ":role": role.as_str(),
+            },
+            |row| row.get(0),
+        )?;
+        if let Some(names) = names {
+            // The column name comes from `as_str`, never from input.
+            conn.execute(
+                &format!(
+                    "UPDATE information SET {} = :names WHERE id = :id",
+                    role.as_str()
+                ),
+                named_params! { ":names": names, ":id": information_id },
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Applies the names edited in the report form to its parties: a role without
+/// parties gets one and a single party is renamed. Roles with several parties
+/// are only edited in the parties list.
+pub fn apply_names(
+    conn: &Connection,
+    existing: &Information,
+    edited: &Information,
+) -> Result<(), String> {
+    for (role, old_name, name) in [
+        (PartyRole::Plaintiff, &existing.plaintiff, &edited.plaintiff),
+        (PartyRole::Defendant, &existing.defendant, &edited.defendant),
+    ] {
+        if old_name == name {
+            continue;
+        }
+        let mut stmt = conn
+            .prepare("SELECT id FROM party WHERE information_id = :information_id AND role = :role")
+            .map_err(|_| "Fail to read parties")?;
+        let ids = stmt
+            .query_map(
+                named_params! { ":information_id": existing.id, ":role": role.as_str() },
+                |row| row.get::<_, i64>(0),
+            )
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<i64>>>())
+            .map_err(|_| "Fail to read parties")?;
+        let details = PartyDetails {
+            role,
+            full_name: name.to_string(),
+            birth_year: None,
+            id_number: None,
+            address: None,
+        };
+        match ids.as_slice() {
+            [] if name.trim().is_empty() => {}
+            [] => {
+                insert_party(conn, existing.id, &details).map_err(|_| "Fail to save party")?;
+            }
+            [id] => {
+                validate(&details)?;
+                conn.execute(
+                    "
+                    UPDATE party
+                    SET full_name = :full_name, normalized_name = :normalized_name,
+                        updated_at = :updated_at
+                    WHERE id = :id
+                    ",
+                    named_params! {
+                        ":id": id,
+                        ":full_name": name.trim(),
+                        ":normalized_name": person::normalize_name(name),
+                        ":updated_at": db::now(),
+                    },
+                )
+                .map_err(|_| "Fail to update party")?;
+            }
+            _ => {
+                return Err(format!(
+                    "The report has several {}s, edit them in the parties list",
+                    role.as_str()
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Marks a report as changed when its parties are, so changeset sync picks it
+/// up.
+fn touch_information(conn: &Connection, information_id: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "
+        UPDATE information SET updated_at = :updated_at, local_updated_at = :updated_at
+        WHERE id = :id
+        ",
+        named_params! { ":updated_at": db::now(), ":id": information_id },
+    )?;
+    Ok(())
+}
+
+/// Creates the parties of a new report from the names typed in the report
+/// form, one party per role.
+pub fn create_from_names(
+    conn: &Connection,
+    information_id: i64,
+    plaintiff: &str,
+    defendant: &str,
+) -> rusqlite::Result<()> {
+    for (role, name) in [
+        (PartyRole::Plaintiff, plaintiff),
+        (PartyRole::Defendant, defendant),
+    ] {
+        if name.trim().is_empty() {
+            continue;
+        }
+        let details = PartyDetails {
+            role,
+            full_name: name.to_string(),
+            birth_year: None,
+            id_number: None,
+            address: None,
+        };
+        insert_party(conn, information_id, &details)?;
+    }
+    Ok(())
+}
+
+pub fn delete_for_information(conn: &Connection, ids: &[i64]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("DELETE FROM party WHERE information_id = :information_id")?;
+    for id in ids {
+        stmt.execute(named_params! { ":information_id": id })?;
+    }
+    Ok(())
+}
+
+pub fn list(conn: &Connection, information_id: i64) -> Result<Vec<Party>, String> {
+    let mut stmt = conn
+        .prepare("SELECT * FROM party WHERE information_id = :information_id ORDER BY role, id")
+        .map_err(|_| "Fail to read parties")?;
+    let mut rows = stmt
+        .query(named_params! { ":information_id": information_id })
+        .map_err(|_| "Fail to read parties")?;
+    let mut parties = Vec::new();
+    while let Ok(Some(row)) = rows.next() {
+        parties.push(read_party(row).map_err(|_| "Fail to read parties")?);
+    }
+    Ok(parties)
+}
+
+/// Details of the parties of a report, as carried by changesets.
+pub fn details_of(conn: &Connection, information_id: i64) -> Result<Vec<PartyDetails>, String> {
+    Ok(list(conn, information_id)?
+        .into_iter()
+        .map(|party| party.details)
+        .collect())
+}
+
+/// Replaces the parties of a report with `parties`, without touching the
+/// report, and derives its names from them.
+pub fn replace_all(
+    conn: &Connection,
+    information_id: i64,
+    parties: &[PartyDetails],
+) -> rusqlite::Result<()> {
+    delete_for_information(conn, &[information_id])?;
+    for party in parties {
+        insert_party(conn, information_id, party)?;
+    }
+    sync_names(conn, information_id)
+}
+
+pub fn add(conn: &Connection, information_id: i64, party: &PartyDetails) -> Result<Party, String> {
+    validate(party)?;
+    get_information(conn, information_id)
+        .map_err(|_| "Fail to read information")?
+        .ok_or("Information not found")?;
+    let id = conn
+        .unchecked_transaction()
+        .and_then(|tx| {
+            let id = insert_party(&tx, information_id, party)?;
+            sync_names(&tx, information_id)?;
+            touch_information(&tx, information_id)?;
+            tx.commit()?;
+            Ok(id)
+        })
+        .map_err(|_| "Fail to save party")?;
+    get_party(conn, id)
+}
+
+/// Whether `party` is the only one of its role on its report.
+fn is_last_of_role(conn: &Connection, party: &Party) -> Result<bool, String> {
+    let same_role: i64 = conn
+        .query_row(
+            "SELECT count(*) FROM party WHERE information_id = :information_id AND role = :role",
+            named_params! {
+                ":information_id": party.information_id,
+                ":role": party.details.role.as_str(),
+            },
+            |row| row.get(0),
+        )
+        .map_err(|_| "Fail to read parties")?;
+    Ok(same_role <= 1)
+}
+
+/// Updates a party. Its role only changes while another party keeps the old
+/// one, like for `remove`.
+pub fn update(conn: &Connection, id: i64, party: &PartyDetails) -> Result<Party, String> {
+    validate(party)?;
+    let existing = get_party(conn, id)?;
+    if party.role != existing.details.role && is_last_of_role(conn, &existing)? {
+        return Err("Cannot change the role of the last party of this role".into());
+    }
+    conn.unchecked_transaction()
+        .and_then(|tx| {
+            tx.execute(
+                "
+                UPDATE party
+                SET
+                    role = :role,
+                    full_name = :full_name,
+                    normalized_name = :normalized_name,
+                    birth_year = :birth_year,
+                    id_number = :id_number,
+                    address = :address,
+                    updated_at = :updated_at
+                WHERE id = :id
+                ",
+                named_params! {
+                    ":id": id,
+                    ":role": party.role.as_str(),
+                    ":full_name": party.full_name.trim(),
+                    ":normalized_name": person::normalize_name(&party.full_name),
+                    ":birth_year": party.birth_year,
+                    ":id_number": id_number(party),
+                    ":address": party.address,
+                    ":updated_at": db::now(),
+                },
+            )?;
+            sync_names(&tx, existing.information_id)?;
+            touch_information(&tx, existing.information_id)?;
+            tx.commit()
+        })
+        .map_err(|_| "Fail to update party")?;
+    get_party(conn, id)
+}
+
+/// Removes a party. A report keeps at least one plaintiff and one defendant
+/// once it has them, since its name columns are derived from its parties.
+pub fn remove(conn: &Connection, id: i64) -> Result<(), String> {
+    let existing = get_party(conn, id)?;
+    if is_last_of_role(conn, &existing)? {
+        return Err("Cannot remove the last party of this role".into());
+    }
+    conn.unchecked_transaction()
+        .and_then(|tx| {
+            tx.execute(
+                "DELETE FROM party WHERE id = :id",
+                named_params! { ":id": id },
+            )?;
+            sync_names(&tx, existing.information_id)?;
+            touch_information(&tx, existing.information_id)?;
+            tx.commit()
+        })
+        .map_err(|_| "Fail to remove party")?;
+    Ok(())
+}
+
+/// Finds parties by name or ID number across all reports. ID numbers are
+/// matched without the spaces typed in the term, as they are stored.
+pub fn search(conn: &Connection, term: &str) -> Result<Vec<PartyMatch>, String> {
+    let mut stmt = conn
+        .prepare(
+            "
+            SELECT party.*, information.acceptance_no, information.accepted_at
+            FROM party
+            JOIN information ON information.id = party.information_id
+            WHERE party.full_name LIKE :term OR party.id_number LIKE :id_number
+            ORDER BY party.full_name, information.accepted_at DESC
+            LIMIT 100
+            ",
+        )
+        .map_err(|_| "Fail to search parties")?;
+    let mut rows = stmt
+        .query(named_params! {
+            ":term": format!("%{}%", term.trim()),
+            ":id_number": format!("%{}%", person::normalize_id_number(term)),
+        })
+        .map_err(|_| "Fail to search parties")?;
+    let mut matches = Vec::new();
+    while let Ok(Some(row)) = rows.next() {
+        matches.push(PartyMatch {
+            party: read_party(row).map_err(|_| "Fail to search parties")?,
+            acceptance_no: row.get_unwrap("acceptance_no"),
+            accepted_at: row.get_unwrap("accepted_at"),
+        });
+    }
+    Ok(matches)
+}
@@ -0,0 +1,129 @@
+use rusqlite::{functions::FunctionFlags, named_params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// A report in which a person appears, as returned by lookups.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PersonReport {
+    information_id: i64,
+    acceptance_no: String,
+    accepted_at: i64,
+    role: String,
+    full_name: String,
+    id_number: Option<String>,
+    inv_status: Option<i64>,
+    inv_investigator: Option<String>,
+    pro_procurator: Option<String>,
+}
+
+/// Earlier reports naming a party of a report that was just saved.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct KnownPerson {
+    full_name: String,
+    reports: Vec<PersonReport>,
+}
+
+/// Key under which a name is indexed: lowercase, without Vietnamese diacritics
+/// and with whitespace collapsed, so "Nguyễn Văn  An" matches "nguyen van an".
+pub fn normalize_name(name: &str) -> String {
+    name.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| match c {
+            'đ' | 'Đ' => 'd',
+            c => c,
+        })
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// ID numbers are compared without the spaces people type in them.
+pub fn normalize_id_number(id_number: &str) -> String {
+    id_number.split_whitespace().collect()
+}
+
+/// Makes `normalize_name` available to SQL, for migrations indexing existing
+/// rows.
+pub fn register_functions(conn: &Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "normalize_name",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| Ok(normalize_name(&ctx.get::<String>(0)?)),
+    )
+}
+
+fn read_person_report(row: &Row) -> PersonReport {
+    PersonReport {
+        information_id: row.get_unwrap("information_id"),
+        acceptance_no: row.get_unwrap("acceptance_no"),
+        accepted_at: row.get_unwrap("accepted_at"),
+        role: row.get_unwrap("role"),
+        full_name: row.get_unwrap("full_name"),
+        id_number: row.get_unwrap("id_number"),
+        inv_status: row.get_unwrap("inv_status"),
+        inv_investigator: row.get_unwrap("inv_investigator"),
+        pro_procurator: row.get_unwrap("pro_procurator"),
+    }
+}
+
+/// Reports with a party matching `name`, or `id_number` when given, leaving
+/// out `exclude_information_id`.
+pub fn find_reports(
+    conn: &Connection,
+    name: &str,
+    id_number: Option<&str>,
+    exclude_information_id: Option<i64>,
+) -> rusqlite::Result<Vec<PersonReport>> {
+    let id_number = id_number
+        .map(normalize_id_number)
+        .filter(|id_number| !id_number.is_empty());
+    let mut stmt = conn.prepare(
+        "
+        SELECT
+            party.information_id, party.role, party.full_name, party.id_number,
+            information.acceptance_no, information.accepted_at, information.inv_status,
+            information.inv_investigator, information.pro_procurator
+        FROM party
+        JOIN information ON information.id = party.information_id
+        WHERE
+            (party.normalized_name = :name OR party.id_number = :id_number) AND
+            party.information_id IS NOT :exclude
+        ORDER BY information.accepted_at DESC, party.id
+        ",
+    )?;
+    let mut rows = stmt.query(named_params! {
+        ":name": normalize_name(name),
+        ":id_number": id_number,
+        ":exclude": exclude_information_id,
+    })?;
+    let mut reports = Vec::new();
+    while let Some(row) = rows.next()? {
+        reports.push(read_person_report(row));
+    }
+    Ok(reports)
+}
+
+/// Looks up the parties of `information_id` in the other reports, for the
+/// warning shown when a report naming a known person is saved.
+pub fn known_persons(conn: &Connection, information_id: i64) -> rusqlite::Result<Vec<KnownPerson>> {
+    let mut stmt = conn.prepare(
+        "SELECT full_name, id_number FROM party WHERE information_id = :information_id ORDER BY id",
+    )?;
+    let parties = stmt
+        .query_map(named_params! { ":information_id": information_id }, |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut known = Vec::new();
+    for (full_name, id_number) in parties {
+        let reports = find_reports(conn, &full_name, id_number.as_deref(), Some(information_id))?;
+        if !reports.is_empty() {
+            known.push(KnownPerson { full_name, reports });
+        }
+    }
+    Ok(known)
+}
@@ -0,0 +1,183 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use rusqlite::Connection;
+
+use crate::{person, vault};
+
+/// How long a statement waits for a lock held by another connection before
+/// failing with `SQLITE_BUSY`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a read waits for one of the readers to be free.
+const CHECKOUT_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_READERS: usize = 4;
+
+/// Connections to the unlocked database. SQLite only lets one connection write
+/// at a time, so writes go through a single writer, while in WAL mode up to
+/// `MAX_READERS` read-only connections read alongside it without waiting.
+/// Clones share the same connections.
+#[derive(Clone)]
+pub struct Pool {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    path: PathBuf,
+    key: Mutex<String>,
+    writer: Mutex<Connection>,
+    readers: Mutex<Readers>,
+    reader_returned: Condvar,
+}
+
+struct Readers {
+    idle: Vec<Connection>,
+    /// Readers currently open, idle or in use.
+    open: usize,
+    /// Bumped when the key changes, so readers opened with the old key are
+    /// closed instead of going back to `idle`.
+    generation: u64,
+}
+
+/// A reader checked out of the pool, given back when dropped.
+struct Reader<'a> {
+    pool: &'a Pool,
+    conn: Option<Connection>,
+    generation: u64,
+}
+
+impl Drop for Reader<'_> {
+    fn drop(&mut self) {
+        let mut readers = self.pool.lock_readers();
+        if readers.generation == self.generation {
+            if let Some(conn) = self.conn.take() {
+                readers.idle.push(conn);
+            }
+        }
+        self.pool.inner.reader_returned.notify_one();
+    }
+}
+
+/// Settings every connection of the pool needs.
+fn configure(conn: &Connection) -> rusqlite::Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    person::register_functions(conn)
+}
+
+/// A panic in a command leaves its lock poisoned, but SQLite has rolled back
+/// whatever it was doing, so the connection can still be used.
+fn recover<T>(result: Result<T, PoisonError<T>>) -> T {
+    result.unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Pool {
+    /// Turns the migrated `writer` connection into a pool, switching the
+    /// database to WAL mode.
+    pub fn new(path: PathBuf, key: &str, writer: Connection) -> rusqlite::Result<Self> {
+        configure(&writer)?;
+        writer.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        Ok(Pool {
+            inner: Arc::new(Inner {
+                path,
+                key: Mutex::new(key.to_string()),
+                writer: Mutex::new(writer),
+                readers: Mutex::new(Readers {
+                    idle: Vec::new(),
+                    open: 0,
+                    generation: 0,
+                }),
+                reader_returned: Condvar::new(),
+            }),
+        })
+    }
+
+    fn lock_readers(&self) -> MutexGuard<'_, Readers> {
+        recover(self.inner.readers.lock())
+    }
+
+    fn open_reader(&self) -> rusqlite::Result<Connection> {
+        let key = recover(self.inner.key.lock()).clone();
+        let conn = vault::open_encrypted(&self.inner.path, &key)?;
+        configure(&conn)?;
+        conn.pragma_update(None, "query_only", true)?;
+        Ok(conn)
+    }
+
+    fn checkout(&self) -> Result<Reader<'_>, String> {
+        let mut readers = self.lock_readers();
+        loop {
+            let generation = readers.generation;
+            if let Some(conn) = readers.idle.pop() {
+                return Ok(Reader {
+                    pool: self,
+                    conn: Some(conn),
+                    generation,
+                });
+            }
+            if readers.open < MAX_READERS {
+                readers.open += 1;
+                drop(readers);
+                return match self.open_reader() {
+                    Ok(conn) => Ok(Reader {
+                        pool: self,
+                        conn: Some(conn),
+                        generation,
+                    }),
+                    Err(_) => {
+                        let mut readers = self.lock_readers();
+                        if readers.generation == generation {
+                            readers.open -= 1;
+                        }
+                        Err("Fail to get connection".into())
+                    }
+                };
+            }
+            let (guard, timeout) = recover(
+                self.inner
+                    .reader_returned
+                    .wait_timeout(readers, CHECKOUT_TIMEOUT),
+            );
+            readers = guard;
+            if timeout.timed_out() && readers.idle.is_empty() && readers.open >= MAX_READERS {
+                return Err("Database is busy, please try again".into());
+            }
+        }
+    }
+
+    /// Runs `f` on a read-only connection, on the calling thread.
+    pub fn with_reader<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        E: From<String>,
+        F: FnOnce(&Connection) -> Result<T, E>,
+    {
+        let reader = self.checkout().map_err(E::from)?;
+        f(reader.conn.as_ref().unwrap())
+    }
+
+    /// Runs `f` on the writer, on the calling thread. Waits for the write in
+    /// progress, if any.
+    pub fn with_writer<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, E>,
+    {
+        let mut writer = recover(self.inner.writer.lock());
+        f(&mut writer)
+    }
+
+    /// Re-keys the database. Idle readers are closed, and readers still in use
+    /// are closed when given back, since they were opened with the old key.
+    pub fn rekey(&self, key: &str) -> rusqlite::Result<()> {
+        let writer = recover(self.inner.writer.lock());
+        // Pages left in the WAL were written under the old key.
+        writer.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        writer.pragma_update(None, "rekey", key)?;
+        *recover(self.inner.key.lock()) = key.to_string();
+        let mut readers = self.lock_readers();
+        readers.idle.clear();
+        readers.open = 0;
+        readers.generation += 1;
+        Ok(())
+    }
+}
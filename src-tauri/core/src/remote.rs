@@ -0,0 +1,292 @@
+use std::{fs::File, io, time::Duration};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    assignment::Assignment,
+    auth::User,
+    error::CommandError,
+    export::ExportSetting,
+    note::Note,
+    repository::{InformationRepository, ListFilter, Stats},
+    server::{
+        AssignRequest, ChunkRequest, ErrorResponse, ExportRequest, IdRequest, IdsRequest,
+        ListRequest, LoginRequest, LoginResponse, NameRequest, NoteEditRequest, NoteRequest,
+        PatchRequest, RangeRequest, RenameRequest, TagRequest,
+    },
+    settings::Settings,
+    tag::Tag,
+    Information, InformationCreated, InformationPageQueryOption, InformationPatch,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Exports of many reports take a while to write.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// The reports of a `server::Server`, logged in as one user. Requests block,
+/// so the app makes them on its blocking thread pool.
+#[derive(Clone)]
+pub struct RemoteRepository {
+    agent: ureq::Agent,
+    base_url: String,
+    token: String,
+}
+
+impl RemoteRepository {
+    /// Logs in to the server at `address`, either `host:port` or a URL.
+    pub fn connect(address: &str, username: &str, password: &str) -> Result<(Self, User), String> {
+        let address = address.trim().trim_end_matches('/');
+        let base_url = if address.starts_with("http://") || address.starts_with("https://") {
+            address.to_string()
+        } else {
+            format!("http://{}", address)
+        };
+        let mut remote = RemoteRepository {
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            base_url,
+            token: String::new(),
+        };
+        let LoginResponse { token, user } = remote
+            .call(
+                "/api/login",
+                &LoginRequest {
+                    username: username.to_string(),
+                    password: password.to_string(),
+                },
+            )
+            .map_err(|err| err.to_string())?;
+        remote.token = token;
+        Ok((remote, user))
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Ends the session on the server. The repository can't be used after.
+    pub fn logout(&self) -> Result<(), String> {
+        self.call("/api/logout", &()).map_err(|err| err.to_string())
+    }
+
+    pub fn settings(&self) -> Result<Settings, String> {
+        self.call("/api/settings", &())
+            .map_err(|err| err.to_string())
+    }
+
+    /// Has the server write the workbook of `setting` and saves it to the
+    /// path of `setting`.
+    pub fn export(&self, setting: &ExportSetting) -> Result<(), String> {
+        let (from, to) = setting.range();
+        let request = ExportRequest {
+            from,
+            to,
+            notes: setting.notes(),
+        };
+        let response = self
+            .post("/api/export", &request, EXPORT_TIMEOUT)
+            .map_err(|err| err.to_string())?;
+        let mut file = File::create(setting.path()).map_err(|_| "Cannot create export file")?;
+        io::copy(&mut response.into_reader(), &mut file)
+            .map_err(|_| "Fail to download exported file")?;
+        Ok(())
+    }
+
+    fn call<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &impl Serialize,
+    ) -> Result<T, CommandError> {
+        self.post(path, body, TIMEOUT)?
+            .into_json()
+            .map_err(|_| "Invalid response from the server".into())
+    }
+
+    fn post(
+        &self,
+        path: &str,
+        body: &impl Serialize,
+        timeout: Duration,
+    ) -> Result<ureq::Response, CommandError> {
+        let result = self
+            .agent
+            .post(&format!("{}{}", self.base_url, path))
+            .timeout(timeout)
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .send_json(body);
+        match result {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(status, response)) => Err(response
+                .into_json::<ErrorResponse>()
+                .map(|response| response.error)
+                .unwrap_or_else(|_| format!("Server error {}", status).into())),
+            Err(ureq::Error::Transport(_)) => Err("Cannot reach the server".into()),
+        }
+    }
+}
+
+impl InformationRepository for RemoteRepository {
+    fn create(&self, information: Information) -> Result<InformationCreated, CommandError> {
+        self.call("/api/information/create", &information)
+    }
+
+    /// The server applies the restrictions of the logged-in user instead of
+    /// `user`.
+    fn update(&self, _user: &User, information: Information) -> Result<(), CommandError> {
+        self.call("/api/information/update", &information)
+    }
+
+    /// Same as `update`, the server applies its own user's restrictions.
+    fn patch(&self, _user: &User, id: i64, patch: InformationPatch) -> Result<(), CommandError> {
+        self.call("/api/information/patch", &PatchRequest { id, patch })
+    }
+
+    fn get(&self, id: i64) -> Result<Option<Information>, String> {
+        self.call("/api/information/get", &IdRequest { id })
+            .map_err(|err| err.to_string())
+    }
+
+    fn list(
+        &self,
+        filter: ListFilter,
+        query: &InformationPageQueryOption,
+    ) -> Result<(Vec<Information>, Option<i64>), String> {
+        let request = ListRequest {
+            filter,
+            query: query.clone(),
+        };
+        self.call("/api/information/list", &request)
+            .map_err(|err| err.to_string())
+    }
+
+    /// The server records the logged-in user as the one assigning instead of
+    /// `user`.
+    fn assign(
+        &self,
+        _user: &User,
+        ids: &[i64],
+        assignment: &Assignment,
+    ) -> Result<(), CommandError> {
+        let request = AssignRequest {
+            ids: ids.to_vec(),
+            assignment: assignment.clone(),
+        };
+        self.call("/api/information/assign", &request)
+    }
+
+    fn delete(&self, ids: &[i64]) -> Result<(), String> {
+        self.call("/api/information/delete", &IdsRequest { ids: ids.to_vec() })
+            .map_err(|err| err.to_string())
+    }
+
+    fn count_accepted(&self, from: i64, to: i64) -> Result<usize, String> {
+        self.call(
+            "/api/information/count_accepted",
+            &RangeRequest { from, to },
+        )
+        .map_err(|err| err.to_string())
+    }
+
+    fn export_chunk(
+        &self,
+        from: i64,
+        to: i64,
+        after: (i64, i64),
+        limit: i64,
+    ) -> Result<Vec<Information>, String> {
+        let request = ChunkRequest {
+            from,
+            to,
+            after,
+            limit,
+        };
+        self.call("/api/information/export_chunk", &request)
+            .map_err(|err| err.to_string())
+    }
+
+    fn stats(&self) -> Result<Stats, String> {
+        self.call("/api/information/stats", &())
+            .map_err(|err| err.to_string())
+    }
+
+    fn list_tags(&self) -> Result<Vec<Tag>, String> {
+        self.call("/api/tag/list", &())
+            .map_err(|err| err.to_string())
+    }
+
+    fn create_tag(&self, name: &str) -> Result<Tag, String> {
+        let request = NameRequest {
+            name: name.to_string(),
+        };
+        self.call("/api/tag/create", &request)
+            .map_err(|err| err.to_string())
+    }
+
+    fn rename_tag(&self, id: i64, name: &str) -> Result<Tag, String> {
+        let request = RenameRequest {
+            id,
+            name: name.to_string(),
+        };
+        self.call("/api/tag/rename", &request)
+            .map_err(|err| err.to_string())
+    }
+
+    fn delete_tag(&self, id: i64) -> Result<(), String> {
+        self.call("/api/tag/delete", &IdRequest { id })
+            .map_err(|err| err.to_string())
+    }
+
+    fn tags_of(&self, information_id: i64) -> Result<Vec<Tag>, String> {
+        self.call("/api/tag/of_information", &IdRequest { id: information_id })
+            .map_err(|err| err.to_string())
+    }
+
+    fn tag(&self, ids: &[i64], tag_ids: &[i64]) -> Result<(), String> {
+        let request = TagRequest {
+            ids: ids.to_vec(),
+            tag_ids: tag_ids.to_vec(),
+        };
+        self.call("/api/tag/add", &request)
+            .map_err(|err| err.to_string())
+    }
+
+    fn untag(&self, ids: &[i64], tag_ids: &[i64]) -> Result<(), String> {
+        let request = TagRequest {
+            ids: ids.to_vec(),
+            tag_ids: tag_ids.to_vec(),
+        };
+        self.call("/api/tag/remove", &request)
+            .map_err(|err| err.to_string())
+    }
+
+    fn list_notes(&self, information_id: i64) -> Result<Vec<Note>, String> {
+        self.call("/api/note/list", &IdRequest { id: information_id })
+            .map_err(|err| err.to_string())
+    }
+
+    /// The server records the logged-in user as the author instead of `user`.
+    fn add_note(
+        &self,
+        _user: &User,
+        information_id: i64,
+        text: &str,
+        attachment_id: Option<i64>,
+    ) -> Result<Note, String> {
+        let request = NoteRequest {
+            information_id,
+            text: text.to_string(),
+            attachment_id,
+        };
+        self.call("/api/note/add", &request)
+            .map_err(|err| err.to_string())
+    }
+
+    /// Same as `add_note`, the server checks the author against its own user.
+    fn edit_note(&self, _user: &User, id: i64, text: &str) -> Result<Note, String> {
+        let request = NoteEditRequest {
+            id,
+            text: text.to_string(),
+        };
+        self.call("/api/note/edit", &request)
+            .map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,693 @@
+use std::collections::BTreeMap;
+
+use rusqlite::{named_params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assignment::{self, Assignment},
+    attachment,
+    auth::{self, User},
+    dates, db, duplicate,
+    error::CommandError,
+    event,
+    model::{get_information, read_from_row},
+    note::{self, Note},
+    numbering, party, person,
+    settings::DeadlineSettings,
+    tag::{self, Tag},
+    validation, Information, InformationCreated, InformationPageQueryOption, InformationPatch,
+};
+
+/// Reports without any designation yet, listed for assignment.
+const UNASSIGNED_CONDITION: &str = "
+    inv_investigator IS NULL AND
+    inv_designation_no IS NULL AND
+    pro_procurator IS NULL AND
+    pro_designation_no IS NULL
+";
+const SEARCH_CONDITION: &str = "
+    (
+        acceptance_no like :term OR
+        plaintiff like :term OR
+        defendant like :term OR
+        inv_investigator like :term OR
+        pro_procurator like :term OR
+        inv_designation_no like :term OR
+        pro_designation_no like :term OR
+        id IN (
+            SELECT information_id FROM party
+            WHERE full_name like :term OR id_number like :term
+        ) OR
+        id IN (SELECT information_id FROM note WHERE text like :term)
+    )
+";
+
+/// Overview of the stored reports.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Stats {
+    pub total: i64,
+    pub unassigned: i64,
+    /// Number of reports accepted each year, oldest year first.
+    pub by_year: Vec<(i32, i64)>,
+}
+
+/// Which reports `InformationRepository::list` returns.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListFilter {
+    All,
+    /// Reports no investigator or procurator has been designated for.
+    Unassigned,
+}
+
+/// Storage of reports. Commands go through it instead of running SQL
+/// themselves, so the queries can be tested against a database of their own.
+pub trait InformationRepository {
+    /// Saves a new report, generating its acceptance number when left blank.
+    fn create(&self, information: Information) -> Result<InformationCreated, CommandError>;
+
+    /// Saves the changes `user` is allowed to make to a report. An edited
+    /// plaintiff or defendant renames the report's only party of that role.
+    fn update(&self, user: &User, information: Information) -> Result<(), CommandError>;
+
+    /// Changes only the fields given in `patch`, with the same checks as
+    /// `update`, reading and writing the report in one transaction.
+    fn patch(&self, user: &User, id: i64, patch: InformationPatch) -> Result<(), CommandError>;
+
+    fn get(&self, id: i64) -> Result<Option<Information>, String>;
+
+    /// A page of reports in creation order, with the number of matches.
+    fn list(
+        &self,
+        filter: ListFilter,
+        query: &InformationPageQueryOption,
+    ) -> Result<(Vec<Information>, Option<i64>), String>;
+
+    /// Designates the same investigator or procurator for many reports at
+    /// once, recording it in their assignment history. `user` is the one
+    /// assigning.
+    fn assign(&self, user: &User, ids: &[i64], assignment: &Assignment)
+        -> Result<(), CommandError>;
+
+    /// Deletes reports along with their parties, links and attachments.
+    fn delete(&self, ids: &[i64]) -> Result<(), String>;
+
+    /// Number of reports accepted between `from` and `to`.
+    fn count_accepted(&self, from: i64, to: i64) -> Result<usize, String>;
+
+    /// Up to `limit` reports accepted between `from` and `to` following
+    /// `after`, an `(accepted_at, id)` key, in the order they are exported.
+    /// Parties are listed one per line in the plaintiff and defendant.
+    fn export_chunk(
+        &self,
+        from: i64,
+        to: i64,
+        after: (i64, i64),
+        limit: i64,
+    ) -> Result<Vec<Information>, String>;
+
+    fn stats(&self) -> Result<Stats, String>;
+
+    /// Every tag, by name.
+    fn list_tags(&self) -> Result<Vec<Tag>, String>;
+
+    fn create_tag(&self, name: &str) -> Result<Tag, String>;
+
+    fn rename_tag(&self, id: i64, name: &str) -> Result<Tag, String>;
+
+    /// Deletes a tag, taking it off every report.
+    fn delete_tag(&self, id: i64) -> Result<(), String>;
+
+    /// The tags of a report, by name.
+    fn tags_of(&self, information_id: i64) -> Result<Vec<Tag>, String>;
+
+    /// Puts every tag of `tag_ids` on every report of `ids`.
+    fn tag(&self, ids: &[i64], tag_ids: &[i64]) -> Result<(), String>;
+
+    /// Takes every tag of `tag_ids` off every report of `ids`.
+    fn untag(&self, ids: &[i64], tag_ids: &[i64]) -> Result<(), String>;
+
+    /// The notes of a report, oldest first.
+    fn list_notes(&self, information_id: i64) -> Result<Vec<Note>, String>;
+
+    /// Adds a note written by `user`, optionally about one of the report's
+    /// attachments.
+    fn add_note(
+        &self,
+        user: &User,
+        information_id: i64,
+        text: &str,
+        attachment_id: Option<i64>,
+    ) -> Result<Note, String>;
+
+    /// Replaces the text of a note; only its author may.
+    fn edit_note(&self, user: &User, id: i64, text: &str) -> Result<Note, String>;
+}
+
+/// Deletes reports along with their parties and links, in the transaction of
+/// the caller, leaving a tombstone for changeset sync. Returns the hashes of
+/// the removed attachments, to pass to `attachment::remove_unused_files` once
+/// committed.
+pub fn delete_information(conn: &Connection, ids: &[i64]) -> rusqlite::Result<Vec<String>> {
+    let id_list = ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+    let hashes = attachment::delete_for_information(conn, ids)?;
+    party::delete_for_information(conn, ids)?;
+    assignment::delete_for_information(conn, ids)?;
+    tag::delete_for_information(conn, ids)?;
+    note::delete_for_information(conn, ids)?;
+    event::delete_for_information(conn, ids)?;
+    duplicate::delete_for_information(conn, ids)?;
+    conn.execute(
+        &format!(
+            "
+            INSERT OR REPLACE INTO information_tombstone (uid, deleted_at)
+            SELECT uid, :deleted_at FROM information WHERE id IN ({})
+            ",
+            id_list
+        ),
+        named_params! { ":deleted_at": db::now() },
+    )?;
+    conn.execute(
+        &format!("DELETE FROM information WHERE id IN ({})", id_list),
+        (),
+    )?;
+    Ok(hashes)
+}
+
+/// The repository over a connection of the app's database, or of an
+/// in-memory one from `db::open_in_memory`.
+pub struct SqliteRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> SqliteRepository<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        SqliteRepository { conn }
+    }
+}
+
+/// `InformationRepository::update` in the transaction of the caller.
+fn update_information(
+    conn: &Connection,
+    user: &User,
+    information: Information,
+) -> Result<(), CommandError> {
+    let existing = get_information(conn, information.id)
+        .map_err(|_| "Fail to update information")?
+        .ok_or("Information not found")?;
+    let tz = dates::DateSettings::load(conn)
+        .map_err(|_| "Fail to read date settings")?
+        .tz();
+    let mut information = auth::restrict_update(user, existing.clone(), information)?;
+    dates::normalize_information(&mut information, &tz);
+    auth::normalize_assignees(&mut information);
+    validation::validate_information(&information, Some(&existing), &tz)?;
+    numbering::ensure_unused(conn, &information.acceptance_no, Some(information.id))?;
+    if information.acceptance_no != existing.acceptance_no {
+        numbering::claim(conn, &information.acceptance_no, information.accepted_at)?;
+    }
+    let query = "
+        UPDATE information
+        SET
+            acceptance_no = :acceptance_no,
+            accepted_at = :accepted_at,
+            plaintiff = :plaintiff,
+            defendant = :defendant,
+            description = :description,
+            law = :law,
+            inv_investigator = :inv_investigator,
+            inv_designation_no = :inv_designation_no,
+            inv_designated_at = :inv_designated_at,
+            inv_status=  :inv_status,
+            inv_handling_no = :inv_handling_no,
+            inv_handled_at = :inv_handled_at,
+            inv_transferred_at = :inv_transferred_at,
+            inv_extended_at = :inv_extended_at,
+            inv_recovered_at = :inv_recovered_at,
+            inv_canceled_at = :inv_canceled_at,
+            pro_procurator = :pro_procurator,
+            pro_designation_no = :pro_designation_no,
+            pro_designated_at = :pro_designated_at,
+            pro_additional_evidence_requirement = :pro_additional_evidence_requirement,
+            pro_non_prosecution_decision = :pro_non_prosecution_decision,
+            pro_cessation_decision = :pro_cessation_decision,
+            updated_at = :updated_at,
+            local_updated_at = :updated_at
+        WHERE
+            id = :id
+        ";
+
+    let mut stmt = conn
+        .prepare(query)
+        .map_err(|_| "Fail to update information")?;
+    let result = stmt.execute(named_params! {
+        ":id": information.id,
+        ":acceptance_no": information.acceptance_no,
+        ":accepted_at": information.accepted_at,
+        ":plaintiff": information.plaintiff,
+        ":defendant": information.defendant,
+        ":description": information.description,
+        ":law": information.law,
+        ":inv_investigator": information.inv_investigator,
+        ":inv_designation_no": information.inv_designation_no,
+        ":inv_designated_at": information.inv_designated_at,
+        ":inv_status": information.inv_status,
+        ":inv_handling_no": information.inv_handling_no,
+        ":inv_handled_at": information.inv_handled_at,
+        ":inv_transferred_at": information.inv_transferred_at,
+        ":inv_extended_at": information.inv_extended_at,
+        ":inv_recovered_at": information.inv_recovered_at,
+        ":inv_canceled_at": information.inv_canceled_at,
+        ":pro_procurator": information.pro_procurator,
+        ":pro_designation_no": information.pro_designation_no,
+        ":pro_designated_at": information.pro_designated_at,
+        ":pro_additional_evidence_requirement":information.pro_additional_evidence_requirement,
+        ":pro_non_prosecution_decision":information.pro_non_prosecution_decision,
+        ":pro_cessation_decision":information.pro_cessation_decision,
+        ":updated_at": db::now()
+    });
+
+    if result.is_err() {
+        return Err("Fail to update information".into());
+    }
+    party::apply_names(conn, &existing, &information)?;
+
+    // Names of reports with parties are derived from them, and the dates of
+    // repeatable steps from their events.
+    let result = party::sync_names(conn, information.id)
+        .and_then(|_| event::record_columns(conn, information.id, Some(user.id)))
+        .and_then(|_| get_information(conn, information.id));
+    let derived = match result {
+        Ok(Some(derived)) => derived,
+        _ => return Err("Fail to update information".into()),
+    };
+    // A date cleared or moved back in the form comes back from the events
+    // that remain, so the dates as saved are checked too.
+    validation::validate_information(&derived, Some(&existing), &tz)?;
+
+    Ok(())
+}
+
+impl InformationRepository for SqliteRepository<'_> {
+    fn create(&self, mut information: Information) -> Result<InformationCreated, CommandError> {
+        let tz = dates::DateSettings::load(self.conn)
+            .map_err(|_| "Fail to read date settings")?
+            .tz();
+        dates::normalize_information(&mut information, &tz);
+        auth::normalize_assignees(&mut information);
+        validation::validate_information(&information, None, &tz)?;
+        let query = "
+            INSERT INTO information (
+                acceptance_no,
+                accepted_at,
+                plaintiff,
+                defendant,
+                description,
+                law,
+                inv_investigator,
+                inv_designation_no,
+                inv_designated_at,
+                inv_status,
+                inv_handling_no,
+                inv_handled_at,
+                inv_transferred_at,
+                inv_extended_at,
+                inv_recovered_at,
+                inv_canceled_at,
+                pro_procurator,
+                pro_designation_no,
+                pro_designated_at,
+                pro_additional_evidence_requirement,
+                pro_non_prosecution_decision,
+                pro_cessation_decision,
+                created_at,
+                uid
+            ) 
+            values (
+                :acceptance_no,
+                :accepted_at,
+                :plaintiff,
+                :defendant,
+                :description,
+                :law,
+                :inv_investigator,
+                :inv_designation_no,
+                :inv_designated_at,
+                :inv_status,
+                :inv_handling_no,
+                :inv_handled_at,
+                :inv_transferred_at,
+                :inv_extended_at,
+                :inv_recovered_at,
+                :inv_canceled_at,
+                :pro_procurator,
+                :pro_designation_no,
+                :pro_designated_at,
+                :pro_additional_evidence_requirement,
+                :pro_non_prosecution_decision,
+                :pro_cessation_decision,
+                :created_at,
+                lower(hex(randomblob(16)))
+            )
+            ";
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|_| "Fail to save new information")?;
+        // A blank acceptance number is generated from the numbering scheme.
+        let acceptance_no = if information.acceptance_no.trim().is_empty() {
+            numbering::allocate(&tx, information.accepted_at)?
+        } else {
+            let acceptance_no = information.acceptance_no.trim().to_string();
+            numbering::ensure_unused(&tx, &acceptance_no, None)?;
+            numbering::claim(&tx, &acceptance_no, information.accepted_at)?;
+            acceptance_no
+        };
+        let mut stmt = tx
+            .prepare(query)
+            .map_err(|_| "Fail to save new information")?;
+        let save_result = stmt.execute(named_params! {
+            ":acceptance_no": acceptance_no,
+            ":accepted_at": information.accepted_at,
+            ":plaintiff": information.plaintiff,
+            ":defendant": information.defendant,
+            ":description": information.description,
+            ":law": information.law,
+            ":inv_investigator": information.inv_investigator,
+            ":inv_designation_no": information.inv_designation_no,
+            ":inv_designated_at": information.inv_designated_at,
+            ":inv_status": information.inv_status,
+            ":inv_handling_no": information.inv_handling_no,
+            ":inv_handled_at": information.inv_handled_at,
+            ":inv_transferred_at": information.inv_transferred_at,
+            ":inv_extended_at": information.inv_extended_at,
+            ":inv_recovered_at": information.inv_recovered_at,
+            ":inv_canceled_at": information.inv_canceled_at,
+            ":pro_procurator": information.pro_procurator,
+            ":pro_designation_no": information.pro_designation_no,
+            ":pro_designated_at": information.pro_designated_at,
+            ":pro_additional_evidence_requirement":information.pro_additional_evidence_requirement,
+            ":pro_non_prosecution_decision":information.pro_non_prosecution_decision,
+            ":pro_cessation_decision":information.pro_cessation_decision,
+            ":created_at": db::now()
+        });
+
+        drop(stmt);
+        let id = tx.last_insert_rowid();
+        let save_result = save_result
+            .and_then(|_| {
+                party::create_from_names(&tx, id, &information.plaintiff, &information.defendant)
+            })
+            .and_then(|_| event::record_columns(&tx, id, None))
+            .and_then(|_| tx.commit());
+        if save_result.is_err() {
+            return Err("Fail to save new information".into());
+        }
+
+        // The report is saved either way; failing to look up earlier reports
+        // only loses the warnings.
+        let warnings = person::known_persons(self.conn, id).unwrap_or_default();
+        let duplicates = duplicate::find_candidates(self.conn, id).unwrap_or_default();
+        Ok(InformationCreated {
+            id,
+            acceptance_no,
+            warnings,
+            duplicates,
+        })
+    }
+
+    fn update(&self, user: &User, information: Information) -> Result<(), CommandError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|_| "Fail to update information")?;
+        update_information(&tx, user, information)?;
+        tx.commit().map_err(|_| "Fail to update information")?;
+        Ok(())
+    }
+
+    fn patch(&self, user: &User, id: i64, patch: InformationPatch) -> Result<(), CommandError> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|_| "Fail to update information")?;
+        let existing = get_information(&tx, id)
+            .map_err(|_| "Fail to update information")?
+            .ok_or("Information not found")?;
+        update_information(&tx, user, patch.apply(existing))?;
+        tx.commit().map_err(|_| "Fail to update information")?;
+        Ok(())
+    }
+
+    fn get(&self, id: i64) -> Result<Option<Information>, String> {
+        get_information(self.conn, id).map_err(|_| "Fail to read information".into())
+    }
+
+    fn list(
+        &self,
+        filter: ListFilter,
+        query_opt: &InformationPageQueryOption,
+    ) -> Result<(Vec<Information>, Option<i64>), String> {
+        let mut conditions = Vec::new();
+        if filter == ListFilter::Unassigned {
+            conditions.push(UNASSIGNED_CONDITION.to_string());
+        }
+        if query_opt.search.is_some() {
+            conditions.push(SEARCH_CONDITION.to_string());
+        }
+        if !query_opt.tags.is_empty() {
+            conditions.push(tag::filter_condition(&query_opt.tags));
+        }
+        if query_opt.overdue {
+            let tz = dates::DateSettings::load(self.conn)
+                .map_err(|_| "Fail to read date settings")?
+                .tz();
+            let today = dates::midnight(dates::today(&tz), &tz).unwrap_or_else(db::now);
+            let deadlines =
+                DeadlineSettings::load(self.conn).map_err(|_| "Fail to read deadline settings")?;
+            conditions.push(deadlines.overdue_condition(today));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let query = format!(
+            "
+            SELECT *, count(*) OVER() as total
+            FROM information
+            {}
+            ORDER BY
+                created_at ASC
+            LIMIT :limit
+            OFFSET :offset
+            ",
+            where_clause
+        );
+
+        let mut stmt = self
+            .conn
+            .prepare(&query)
+            .map_err(|_| "Fail to read information")?;
+        let mut rows = if let Some(term) = &query_opt.search {
+            stmt.query(named_params! {
+                ":term": format!("%{}%", term),
+                ":limit": query_opt.limit,
+                ":offset": query_opt.offset
+            })
+            .map_err(|_| "Fail to read information")?
+        } else {
+            stmt.query(named_params! {
+                ":limit": query_opt.limit,
+                ":offset": query_opt.offset
+            })
+            .map_err(|_| "Fail to read information")?
+        };
+
+        let mut information_list: Vec<Information> = Vec::new();
+        let mut total_item: Option<i64> = None;
+
+        while let Ok(Some(row)) = rows.next() {
+            information_list.push(read_from_row(row));
+            if total_item.is_none() {
+                total_item = row.get_unwrap("total");
+            }
+        }
+
+        Ok((information_list, total_item))
+    }
+
+    fn assign(
+        &self,
+        user: &User,
+        ids: &[i64],
+        assignment: &Assignment,
+    ) -> Result<(), CommandError> {
+        assignment::assign(self.conn, user, ids, assignment)
+    }
+
+    fn delete(&self, ids: &[i64]) -> Result<(), String> {
+        let result = self.conn.unchecked_transaction().and_then(|tx| {
+            let hashes = delete_information(&tx, ids)?;
+            tx.commit()?;
+            Ok(hashes)
+        });
+        match result {
+            Ok(hashes) => {
+                attachment::remove_unused_files(self.conn, &hashes);
+                Ok(())
+            }
+            Err(_) => Err("Fail to delete information".into()),
+        }
+    }
+
+    fn count_accepted(&self, from: i64, to: i64) -> Result<usize, String> {
+        self.conn
+            .query_row(
+                "SELECT count(*) FROM information WHERE accepted_at BETWEEN :from AND :to",
+                named_params! { ":from": from, ":to": to },
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|count| count as usize)
+            .map_err(|_| "Fail to read information".into())
+    }
+
+    fn export_chunk(
+        &self,
+        from: i64,
+        to: i64,
+        after: (i64, i64),
+        limit: i64,
+    ) -> Result<Vec<Information>, String> {
+        let fetch = || -> rusqlite::Result<Vec<Information>> {
+            let mut stmt = self.conn.prepare(
+                "
+                SELECT
+                    *,
+                    (
+                        SELECT group_concat(full_name, char(10)) FROM (
+                            SELECT full_name FROM party
+                            WHERE information_id = information.id AND role = 'plaintiff'
+                            ORDER BY id
+                        )
+                    ) AS plaintiff_names,
+                    (
+                        SELECT group_concat(full_name, char(10)) FROM (
+                            SELECT full_name FROM party
+                            WHERE information_id = information.id AND role = 'defendant'
+                            ORDER BY id
+                        )
+                    ) AS defendant_names
+                FROM information
+                WHERE
+                    accepted_at BETWEEN :from AND :to AND
+                    (accepted_at, id) > (:after_accepted_at, :after_id)
+                ORDER BY accepted_at, id
+                LIMIT :limit
+                ",
+            )?;
+            let mut rows = stmt.query(named_params! {
+                ":from": from,
+                ":to": to,
+                ":after_accepted_at": after.0,
+                ":after_id": after.1,
+                ":limit": limit,
+            })?;
+            let mut informations = Vec::new();
+            while let Some(row) = rows.next()? {
+                let mut information = read_from_row(row);
+                if let Some(names) = row.get("plaintiff_names")? {
+                    information.plaintiff = names;
+                }
+                if let Some(names) = row.get("defendant_names")? {
+                    information.defendant = names;
+                }
+                informations.push(information);
+            }
+            Ok(informations)
+        };
+        fetch().map_err(|_| "Fail to read information".into())
+    }
+    fn stats(&self) -> Result<Stats, String> {
+        let read = || -> rusqlite::Result<Stats> {
+            let unassigned = self.conn.query_row(
+                &format!(
+                    "SELECT count(*) FROM information WHERE {}",
+                    UNASSIGNED_CONDITION
+                ),
+                [],
+                |row| row.get(0),
+            )?;
+            // Years depend on the configured timezone, so they are counted
+            // here rather than with SQLite's date functions.
+            let tz = dates::DateSettings::load(self.conn)?.tz();
+            let mut by_year = BTreeMap::new();
+            let mut total = 0;
+            let mut stmt = self.conn.prepare("SELECT accepted_at FROM information")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                total += 1;
+                *by_year
+                    .entry(dates::local_year(row.get(0)?, &tz))
+                    .or_insert(0) += 1;
+            }
+            Ok(Stats {
+                total,
+                unassigned,
+                by_year: by_year.into_iter().collect(),
+            })
+        };
+        read().map_err(|_| "Fail to read information".into())
+    }
+
+    fn list_tags(&self) -> Result<Vec<Tag>, String> {
+        tag::list(self.conn)
+    }
+
+    fn create_tag(&self, name: &str) -> Result<Tag, String> {
+        tag::create(self.conn, name)
+    }
+
+    fn rename_tag(&self, id: i64, name: &str) -> Result<Tag, String> {
+        tag::rename(self.conn, id, name)
+    }
+
+    fn delete_tag(&self, id: i64) -> Result<(), String> {
+        tag::delete(self.conn, id)
+    }
+
+    fn tags_of(&self, information_id: i64) -> Result<Vec<Tag>, String> {
+        tag::tags_of(self.conn, information_id)
+    }
+
+    fn tag(&self, ids: &[i64], tag_ids: &[i64]) -> Result<(), String> {
+        tag::add(self.conn, ids, tag_ids)
+    }
+
+    fn untag(&self, ids: &[i64], tag_ids: &[i64]) -> Result<(), String> {
+        tag::remove(self.conn, ids, tag_ids)
+    }
+
+    fn list_notes(&self, information_id: i64) -> Result<Vec<Note>, String> {
+        note::list(self.conn, information_id)
+    }
+
+    fn add_note(
+        &self,
+        user: &User,
+        information_id: i64,
+        text: &str,
+        attachment_id: Option<i64>,
+    ) -> Result<Note, String> {
+        note::add(self.conn, user, information_id, text, attachment_id)
+    }
+
+    fn edit_note(&self, user: &User, id: i64, text: &str) -> Result<Note, String> {
+        note::edit(self.conn, user, id, text)
+    }
+}
@@ -0,0 +1,597 @@
+//! HTTP/JSON access to a database over the LAN, so several office PCs can
+//! share one register. Every endpoint takes a POST with a JSON body; all but
+//! `/api/login` need the token it returns as `Authorization: Bearer <token>`.
+//! `remote::RemoteRepository` is the matching client.
+//!
+//! The transport is plain HTTP: passwords, tokens and reports cross the network
+//! unencrypted, so the server is only meant for a trusted office LAN, not for
+//! networks shared with others or reachable from the internet.
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::Read,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::{
+    assignment::Assignment,
+    auth::{self, Role, User},
+    error::CommandError,
+    export::{self, ExportSetting},
+    pool::Pool,
+    repository::{InformationRepository, ListFilter, SqliteRepository},
+    settings::Settings,
+    Information, InformationPageQueryOption, InformationPatch,
+};
+
+/// Port the app and the CLI serve on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 8750;
+/// Threads answering requests, each holding at most one database connection.
+const WORKERS: usize = 4;
+/// Reports are small; anything larger is not a request of ours.
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+const TOKEN_SIZE: usize = 32;
+/// Sessions unused for this long have to log in again.
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Failed logins from an address before it has to wait between attempts.
+const FREE_LOGIN_ATTEMPTS: u32 = 3;
+/// The wait after the free attempts, doubled on every further failure.
+const LOGIN_DELAY: Duration = Duration::from_secs(2);
+const MAX_LOGIN_DELAY: Duration = Duration::from_secs(5 * 60);
+const XLSX_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LoginResponse {
+    pub token: String,
+    pub user: User,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListRequest {
+    pub filter: ListFilter,
+    pub query: InformationPageQueryOption,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IdRequest {
+    pub id: i64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IdsRequest {
+    pub ids: Vec<i64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NameRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RenameRequest {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TagRequest {
+    pub ids: Vec<i64>,
+    pub tag_ids: Vec<i64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NoteRequest {
+    pub information_id: i64,
+    pub text: String,
+    pub attachment_id: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NoteEditRequest {
+    pub id: i64,
+    pub text: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PatchRequest {
+    pub id: i64,
+    pub patch: InformationPatch,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssignRequest {
+    pub ids: Vec<i64>,
+    pub assignment: Assignment,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RangeRequest {
+    pub from: i64,
+    pub to: i64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ExportRequest {
+    pub from: i64,
+    pub to: i64,
+    #[serde(default)]
+    pub notes: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChunkRequest {
+    pub from: i64,
+    pub to: i64,
+    pub after: (i64, i64),
+    pub limit: i64,
+}
+
+/// Body of every failed request.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ErrorResponse {
+    pub error: CommandError,
+}
+
+/// A failed request, with the status it is answered with.
+struct Failure {
+    status: u16,
+    error: CommandError,
+}
+
+impl Failure {
+    fn new(status: u16, message: &str) -> Self {
+        Failure {
+            status,
+            error: message.into(),
+        }
+    }
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure {
+            status: 400,
+            error: message.into(),
+        }
+    }
+}
+
+impl From<CommandError> for Failure {
+    fn from(error: CommandError) -> Self {
+        Failure { status: 400, error }
+    }
+}
+
+enum Reply {
+    Json(String),
+    File(Vec<u8>, &'static str),
+}
+
+/// Only the id of the account is kept, it is read again on every request so
+/// that a changed role or a deleted account takes effect straight away.
+struct Session {
+    user_id: i64,
+    last_used: Instant,
+}
+
+/// Failed logins from one address since its last successful one.
+struct LoginFailures {
+    count: u32,
+    last: Instant,
+}
+
+impl LoginFailures {
+    /// How long the address still has to wait before its next attempt.
+    fn wait(&self) -> Option<Duration> {
+        let extra = self.count.checked_sub(FREE_LOGIN_ATTEMPTS)?;
+        let delay = LOGIN_DELAY
+            .checked_mul(1 << extra.min(16))
+            .map_or(MAX_LOGIN_DELAY, |delay| delay.min(MAX_LOGIN_DELAY));
+        delay.checked_sub(self.last.elapsed())
+    }
+}
+
+/// State shared by the worker threads.
+struct Shared {
+    pool: Pool,
+    /// Logged-in users by token. Tokens last until logout, until they are left
+    /// unused for `SESSION_IDLE_TIMEOUT` or until the server stops.
+    sessions: Mutex<HashMap<String, Session>>,
+    login_failures: Mutex<HashMap<IpAddr, LoginFailures>>,
+    stopped: AtomicBool,
+}
+
+/// A running server. It stops, letting the requests in progress finish, when
+/// dropped.
+pub struct Server {
+    http: Arc<tiny_http::Server>,
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+    addr: SocketAddr,
+}
+
+impl Server {
+    /// Serves the database of `pool` on `addr`, such as `0.0.0.0:8750`. Port 0
+    /// picks a free one, see `addr`.
+    pub fn start(pool: Pool, addr: &str) -> Result<Server, String> {
+        let http =
+            tiny_http::Server::http(addr).map_err(|_| format!("Cannot listen on {}", addr))?;
+        let local_addr = http
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| format!("Cannot listen on {}", addr))?;
+        let http = Arc::new(http);
+        let shared = Arc::new(Shared {
+            pool,
+            sessions: Mutex::new(HashMap::new()),
+            login_failures: Mutex::new(HashMap::new()),
+            stopped: AtomicBool::new(false),
+        });
+        let workers = (0..WORKERS)
+            .map(|_| {
+                let http = http.clone();
+                let shared = shared.clone();
+                thread::spawn(move || loop {
+                    match http.recv() {
+                        Ok(request) => handle(&shared, request),
+                        Err(_) if shared.stopped.load(Ordering::SeqCst) => break,
+                        // A connection that failed before sending a request,
+                        // nothing to answer.
+                        Err(_) => {}
+                    }
+                })
+            })
+            .collect();
+        Ok(Server {
+            http,
+            shared,
+            workers,
+            addr: local_addr,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::SeqCst);
+        // Each call wakes up one worker waiting for a request.
+        for _ in &self.workers {
+            self.http.unblock();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn handle(shared: &Shared, mut request: Request) {
+    let response = match route(shared, &mut request) {
+        Ok(Reply::Json(body)) => {
+            Response::from_string(body).with_header(content_type("application/json"))
+        }
+        Ok(Reply::File(data, mime)) => Response::from_data(data).with_header(content_type(mime)),
+        Err(failure) => {
+            let body = serde_json::to_string(&ErrorResponse {
+                error: failure.error,
+            })
+            .unwrap_or_default();
+            Response::from_string(body)
+                .with_status_code(failure.status)
+                .with_header(content_type("application/json"))
+        }
+    };
+    let _ = request.respond(response);
+}
+
+fn content_type(mime: &str) -> Header {
+    Header::from_bytes(&b"Content-Type"[..], mime.as_bytes()).unwrap()
+}
+
+fn route(shared: &Shared, request: &mut Request) -> Result<Reply, Failure> {
+    if *request.method() != Method::Post {
+        return Err(Failure::new(405, "Only POST requests are served"));
+    }
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE)
+        .read_to_string(&mut body)
+        .map_err(|_| Failure::new(400, "Cannot read request"))?;
+
+    if path == "/api/login" {
+        let LoginRequest { username, password } = parse(&body)?;
+        let address = request.remote_addr().map(|addr| addr.ip());
+        let user = login(shared, address, &username, &password)?;
+        let token = new_token();
+        lock_sessions(shared)?.insert(
+            token.clone(),
+            Session {
+                user_id: user.id,
+                last_used: Instant::now(),
+            },
+        );
+        return json(&LoginResponse { token, user });
+    }
+
+    let token = bearer_token(request).ok_or_else(|| Failure::new(401, "Please log in"))?;
+    let user = session_user(shared, &token)?;
+    let pool = &shared.pool;
+    match path.as_str() {
+        "/api/logout" => {
+            lock_sessions(shared)?.remove(&token);
+            json(&())
+        }
+        "/api/information/list" => {
+            let ListRequest { filter, query } = parse(&body)?;
+            json(&pool.with_reader(|conn| SqliteRepository::new(conn).list(filter, &query))?)
+        }
+        "/api/information/get" => {
+            let IdRequest { id } = parse(&body)?;
+            json(&pool.with_reader(|conn| SqliteRepository::new(conn).get(id))?)
+        }
+        "/api/information/create" => {
+            require(&user, auth::REGISTRARS)?;
+            let information: Information = parse(&body)?;
+            json(&pool.with_writer(|conn| SqliteRepository::new(conn).create(information))?)
+        }
+        "/api/information/update" => {
+            let information: Information = parse(&body)?;
+            json(&pool.with_writer(|conn| SqliteRepository::new(conn).update(&user, information))?)
+        }
+        "/api/information/patch" => {
+            let PatchRequest { id, patch } = parse(&body)?;
+            json(&pool.with_writer(|conn| SqliteRepository::new(conn).patch(&user, id, patch))?)
+        }
+        "/api/information/assign" => {
+            require(&user, auth::MANAGERS)?;
+            let AssignRequest { ids, assignment } = parse(&body)?;
+            let assigned = pool
+                .with_writer(|conn| SqliteRepository::new(conn).assign(&user, &ids, &assignment));
+            json(&assigned?)
+        }
+        "/api/information/delete" => {
+            require(&user, auth::MANAGERS)?;
+            let IdsRequest { ids } = parse(&body)?;
+            json(&pool.with_writer(|conn| SqliteRepository::new(conn).delete(&ids))?)
+        }
+        "/api/information/count_accepted" => {
+            require(&user, auth::MANAGERS)?;
+            let RangeRequest { from, to } = parse(&body)?;
+            json(&pool.with_reader(|conn| SqliteRepository::new(conn).count_accepted(from, to))?)
+        }
+        "/api/information/export_chunk" => {
+            require(&user, auth::MANAGERS)?;
+            let ChunkRequest {
+                from,
+                to,
+                after,
+                limit,
+            } = parse(&body)?;
+            json(&pool.with_reader(|conn| {
+                SqliteRepository::new(conn).export_chunk(from, to, after, limit)
+            })?)
+        }
+        "/api/information/stats" => {
+            json(&pool.with_reader(|conn| SqliteRepository::new(conn).stats())?)
+        }
+        "/api/tag/list" => json(&pool.with_reader(|conn| SqliteRepository::new(conn).list_tags())?),
+        "/api/tag/create" => {
+            require(&user, auth::MANAGERS)?;
+            let NameRequest { name } = parse(&body)?;
+            json(&pool.with_writer(|conn| SqliteRepository::new(conn).create_tag(&name))?)
+        }
+        "/api/tag/rename" => {
+            require(&user, auth::MANAGERS)?;
+            let RenameRequest { id, name } = parse(&body)?;
+            json(&pool.with_writer(|conn| SqliteRepository::new(conn).rename_tag(id, &name))?)
+        }
+        "/api/tag/delete" => {
+            require(&user, auth::MANAGERS)?;
+            let IdRequest { id } = parse(&body)?;
+            json(&pool.with_writer(|conn| SqliteRepository::new(conn).delete_tag(id))?)
+        }
+        "/api/tag/of_information" => {
+            let IdRequest { id } = parse(&body)?;
+            json(&pool.with_reader(|conn| SqliteRepository::new(conn).tags_of(id))?)
+        }
+        "/api/tag/add" => {
+            require(&user, auth::REGISTRARS)?;
+            let TagRequest { ids, tag_ids } = parse(&body)?;
+            json(&pool.with_writer(|conn| SqliteRepository::new(conn).tag(&ids, &tag_ids))?)
+        }
+        "/api/tag/remove" => {
+            require(&user, auth::REGISTRARS)?;
+            let TagRequest { ids, tag_ids } = parse(&body)?;
+            json(&pool.with_writer(|conn| SqliteRepository::new(conn).untag(&ids, &tag_ids))?)
+        }
+        "/api/note/list" => {
+            let IdRequest { id } = parse(&body)?;
+            json(&pool.with_reader(|conn| SqliteRepository::new(conn).list_notes(id))?)
+        }
+        "/api/note/add" => {
+            let NoteRequest {
+                information_id,
+                text,
+                attachment_id,
+            } = parse(&body)?;
+            json(&pool.with_writer(|conn| {
+                SqliteRepository::new(conn).add_note(&user, information_id, &text, attachment_id)
+            })?)
+        }
+        "/api/note/edit" => {
+            let NoteEditRequest { id, text } = parse(&body)?;
+            json(
+                &pool
+                    .with_writer(|conn| SqliteRepository::new(conn).edit_note(&user, id, &text))?,
+            )
+        }
+        "/api/settings" => json(&pool.with_reader(|conn| {
+            Settings::load(conn).map_err(|_| String::from("Fail to read settings"))
+        })?),
+        "/api/export" => {
+            require(&user, auth::MANAGERS)?;
+            let ExportRequest { from, to, notes } = parse(&body)?;
+            Ok(Reply::File(
+                export_workbook(pool, from, to, notes)?,
+                XLSX_TYPE,
+            ))
+        }
+        _ => Err(Failure::new(404, "Unknown request")),
+    }
+}
+
+fn parse<T: DeserializeOwned>(body: &str) -> Result<T, Failure> {
+    serde_json::from_str(body).map_err(|err| Failure::from(format!("Invalid request: {}", err)))
+}
+
+fn json<T: Serialize>(value: &T) -> Result<Reply, Failure> {
+    serde_json::to_string(value)
+        .map(Reply::Json)
+        .map_err(|_| Failure::new(500, "Fail to write response"))
+}
+
+fn require(user: &User, roles: &[Role]) -> Result<(), Failure> {
+    if !roles.contains(&user.role) {
+        return Err(Failure::new(403, "You don't have permission to do this"));
+    }
+    Ok(())
+}
+
+/// Checks the credentials, making an address that keeps failing wait longer
+/// and longer between attempts.
+fn login(
+    shared: &Shared,
+    address: Option<IpAddr>,
+    username: &str,
+    password: &str,
+) -> Result<User, Failure> {
+    if let Some(address) = address {
+        let failures = lock_login_failures(shared)?;
+        if let Some(wait) = failures.get(&address).and_then(LoginFailures::wait) {
+            return Err(Failure::new(
+                429,
+                &format!(
+                    "Too many failed logins, try again in {} seconds",
+                    wait.as_secs() + 1
+                ),
+            ));
+        }
+    }
+    // Checked without holding the lock, hashing the password takes a while.
+    let result = shared
+        .pool
+        .with_reader(|conn| auth::authenticate(conn, username, password));
+    if let Some(address) = address {
+        let mut failures = lock_login_failures(shared)?;
+        match result {
+            Ok(_) => {
+                failures.remove(&address);
+            }
+            Err(_) => {
+                let failed = failures.entry(address).or_insert(LoginFailures {
+                    count: 0,
+                    last: Instant::now(),
+                });
+                failed.count += 1;
+                failed.last = Instant::now();
+            }
+        }
+    }
+    result.map_err(|err| Failure::new(401, &err))
+}
+
+/// The user logged in with `token`, as the account is now, ending the
+/// session if it was left idle too long or its account was deleted.
+fn session_user(shared: &Shared, token: &str) -> Result<User, Failure> {
+    let user_id = {
+        let mut sessions = lock_sessions(shared)?;
+        let session = sessions
+            .get_mut(token)
+            .ok_or_else(|| Failure::new(401, "Please log in"))?;
+        if session.last_used.elapsed() > SESSION_IDLE_TIMEOUT {
+            sessions.remove(token);
+            return Err(Failure::new(401, "Session expired, please log in again"));
+        }
+        session.last_used = Instant::now();
+        session.user_id
+    };
+    let user = shared
+        .pool
+        .with_reader(|conn| auth::get_user(conn, user_id))?;
+    match user {
+        Some(user) => Ok(user),
+        None => {
+            lock_sessions(shared)?.remove(token);
+            Err(Failure::new(401, "Please log in"))
+        }
+    }
+}
+
+fn lock_sessions(shared: &Shared) -> Result<MutexGuard<'_, HashMap<String, Session>>, Failure> {
+    shared
+        .sessions
+        .lock()
+        .map_err(|_| Failure::new(500, "Fail to read session"))
+}
+
+fn lock_login_failures(
+    shared: &Shared,
+) -> Result<MutexGuard<'_, HashMap<IpAddr, LoginFailures>>, Failure> {
+    shared
+        .login_failures
+        .lock()
+        .map_err(|_| Failure::new(500, "Fail to read login attempts"))
+}
+
+fn bearer_token(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+}
+
+fn new_token() -> String {
+    let mut bytes = [0u8; TOKEN_SIZE];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Writes the workbook to a temporary file, since xlsxwriter only writes to
+/// files, and returns its content.
+fn export_workbook(pool: &Pool, from: i64, to: i64, notes: bool) -> Result<Vec<u8>, String> {
+    let path = env::temp_dir().join(format!("docman-export-{}.xlsx", new_token()));
+    let setting = ExportSetting::new(from, to, path.display().to_string()).with_notes(notes);
+    let result = export::write_workbook(pool, &setting, &AtomicBool::new(false), |_| {})
+        .and_then(|_| fs::read(&path).map_err(|_| String::from("Fail to read exported file")));
+    let _ = fs::remove_file(&path);
+    result
+}
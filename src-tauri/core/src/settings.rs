@@ -0,0 +1,164 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::{dates::DateSettings, db, numbering::NumberingScheme};
+
+const AGENCY_NAME_KEY: &str = "agency.name";
+const EXPORT_DIR_KEY: &str = "export.dir";
+const HANDLING_DAYS_KEY: &str = "deadline.handling_days";
+const EXTENDED_HANDLING_DAYS_KEY: &str = "deadline.extended_handling_days";
+const AGENCY_NAME_LENGTH: usize = 200;
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+/// Longest time allowed to handle a report, ten years, which keeps deadlines
+/// far from overflowing once counted in milliseconds.
+const MAX_DEADLINE_DAYS: i64 = 3650;
+
+/// Time allowed to handle a report, counted in days from its acceptance.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct DeadlineSettings {
+    handling_days: i64,
+    /// Total time allowed once the handling has been extended.
+    extended_handling_days: i64,
+}
+
+impl Default for DeadlineSettings {
+    fn default() -> Self {
+        DeadlineSettings {
+            handling_days: 20,
+            extended_handling_days: 60,
+        }
+    }
+}
+
+impl DeadlineSettings {
+    pub fn load(conn: &Connection) -> rusqlite::Result<Self> {
+        let default = DeadlineSettings::default();
+        let days = |key, default| -> rusqlite::Result<i64> {
+            Ok(db::get_setting(conn, key)?
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default))
+        };
+        Ok(DeadlineSettings {
+            handling_days: days(HANDLING_DAYS_KEY, default.handling_days)?,
+            extended_handling_days: days(
+                EXTENDED_HANDLING_DAYS_KEY,
+                default.extended_handling_days,
+            )?,
+        })
+    }
+
+    fn save(&self, conn: &Connection) -> rusqlite::Result<()> {
+        db::set_setting(conn, HANDLING_DAYS_KEY, &self.handling_days.to_string())?;
+        db::set_setting(
+            conn,
+            EXTENDED_HANDLING_DAYS_KEY,
+            &self.extended_handling_days.to_string(),
+        )
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.handling_days <= 0 {
+            return Err("Handling time must be at least one day".into());
+        }
+        if self.extended_handling_days < self.handling_days {
+            return Err("Extended handling time must not be shorter than the handling time".into());
+        }
+        if self.extended_handling_days > MAX_DEADLINE_DAYS {
+            return Err(format!(
+                "Handling time must be at most {} days",
+                MAX_DEADLINE_DAYS
+            ));
+        }
+        Ok(())
+    }
+
+    /// SQL condition on `information` matching the reports still open after
+    /// their deadline, on the day starting at `today`: neither handled,
+    /// transferred nor canceled, and accepted longer ago than the time
+    /// allowed, or the extended time once the handling has been extended.
+    pub fn overdue_condition(&self, today: i64) -> String {
+        format!(
+            "
+            inv_handled_at IS NULL AND
+            inv_transferred_at IS NULL AND
+            inv_canceled_at IS NULL AND
+            accepted_at + {day} * CASE WHEN inv_extended_at IS NULL THEN {handling}
+                ELSE {extended} END < {today}
+            ",
+            day = DAY_MILLIS,
+            handling = self.handling_days,
+            extended = self.extended_handling_days,
+            today = today
+        )
+    }
+}
+
+/// Every setting of the app, stored as key/value rows of the `setting` table.
+/// Missing rows take their default, so new settings need no migration.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Settings {
+    /// Printed in the title of exports.
+    agency_name: String,
+    /// Folder the export dialog opens in; the home folder when unset.
+    export_dir: Option<String>,
+    dates: DateSettings,
+    numbering: NumberingScheme,
+    deadlines: DeadlineSettings,
+}
+
+impl Settings {
+    pub fn load(conn: &Connection) -> rusqlite::Result<Self> {
+        Ok(Settings {
+            agency_name: db::get_setting(conn, AGENCY_NAME_KEY)?.unwrap_or_default(),
+            export_dir: db::get_setting(conn, EXPORT_DIR_KEY)?.filter(|dir| !dir.is_empty()),
+            dates: DateSettings::load(conn)?,
+            numbering: NumberingScheme::load(conn)?,
+            deadlines: DeadlineSettings::load(conn)?,
+        })
+    }
+
+    fn save(&self, conn: &Connection) -> rusqlite::Result<()> {
+        db::set_setting(conn, AGENCY_NAME_KEY, self.agency_name.trim())?;
+        db::set_setting(
+            conn,
+            EXPORT_DIR_KEY,
+            self.export_dir.as_deref().unwrap_or_default(),
+        )?;
+        self.dates.save(conn)?;
+        self.numbering.save(conn)?;
+        self.deadlines.save(conn)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.agency_name.chars().count() > AGENCY_NAME_LENGTH {
+            return Err(format!(
+                "Agency name must have at most {} characters",
+                AGENCY_NAME_LENGTH
+            ));
+        }
+        self.dates.validate()?;
+        self.numbering.validate()?;
+        self.deadlines.validate()
+    }
+
+    pub fn agency_name(&self) -> &str {
+        &self.agency_name
+    }
+
+    pub fn dates(&self) -> &DateSettings {
+        &self.dates
+    }
+}
+
+/// Saves every setting at once, returning them as stored. Settings are read
+/// when they are needed, so changes apply without a restart.
+pub fn update(conn: &Connection, settings: &Settings) -> Result<Settings, String> {
+    settings.validate()?;
+    conn.unchecked_transaction()
+        .and_then(|tx| {
+            settings.save(&tx)?;
+            tx.commit()
+        })
+        .map_err(|_| "Fail to save settings")?;
+    Settings::load(conn).map_err(|_| String::from("Fail to read settings"))
+}
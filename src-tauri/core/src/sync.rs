@@ -0,0 +1,534 @@
+//! Changesets carrying reports between installations that share no network,
+//! on files such as USB sticks. Reports are matched by their `uid`, since each
+//! installation numbers `id`s on its own; deletions travel as tombstones.
+//! Attachments and links between reports stay on the installation they were
+//! added on.
+
+use std::{fs, path::Path};
+
+use rusqlite::{named_params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attachment, db,
+    event::{self, EventDetails},
+    model::read_from_row,
+    numbering,
+    party::{self, PartyDetails},
+    repository, Information,
+};
+
+/// Version of the changeset file format, raised on incompatible changes.
+const FORMAT_VERSION: u32 = 1;
+const INSTALLATION_ID_KEY: &str = "sync.installation_id";
+/// End of the last exported changeset, where the next one starts by default.
+const EXPORTED_UNTIL_KEY: &str = "sync.exported_until";
+/// Followed by the installation id of the source: end of the last changeset
+/// imported from it, by the source's clock. Only read for databases that
+/// imported before `IMPORTED_AT_KEY_PREFIX` was kept.
+const IMPORTED_UNTIL_KEY_PREFIX: &str = "sync.imported_until.";
+/// Followed by the installation id of the source: when the last changeset
+/// from it was imported, by this installation's clock.
+const IMPORTED_AT_KEY_PREFIX: &str = "sync.imported_at.";
+
+/// A report created or changed at the source, with its parties and events.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ChangedRecord {
+    pub uid: String,
+    /// `updated_at` of the report at the source, or `created_at` if never
+    /// updated.
+    pub modified_at: i64,
+    pub information: Information,
+    pub parties: Vec<PartyDetails>,
+    /// Missing from changesets written before events were carried; the dates
+    /// of the report are then recorded as its events.
+    #[serde(default)]
+    pub events: Option<Vec<EventDetails>>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DeletedRecord {
+    pub uid: String,
+    pub deleted_at: i64,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Changeset {
+    pub format: u32,
+    /// Installation the changes come from.
+    pub source: String,
+    /// Changes made after `since`, up to and including `until`.
+    pub since: i64,
+    pub until: i64,
+    pub records: Vec<ChangedRecord>,
+    pub deletions: Vec<DeletedRecord>,
+}
+
+/// Result of `export_to`.
+#[derive(Serialize, Debug)]
+pub struct ExportSummary {
+    pub records: usize,
+    pub deletions: usize,
+    pub since: i64,
+    pub until: i64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// Changed here since the last import from the source, and there too.
+    BothChanged,
+    /// Changed here since the last import from the source, deleted there.
+    DeletedThere,
+    /// Deleted here, changed at the source.
+    DeletedHere,
+    /// Another report here has the acceptance number of the source's one.
+    AcceptanceNoTaken,
+}
+
+/// A change that was not applied and has to be resolved by hand.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Conflict {
+    pub uid: String,
+    pub kind: ConflictKind,
+    /// The report as stored here, if it still is.
+    pub local: Option<Information>,
+    pub local_modified_at: Option<i64>,
+    /// The report as changed at the source, unless deleted there.
+    pub remote: Option<Information>,
+    pub remote_modified_at: i64,
+}
+
+/// Result of `apply`: counts of the changes made and the conflicts left.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct ImportReport {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Changes already applied, or older than the report stored here.
+    pub unchanged: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A report stored here, looked up by `uid`.
+struct LocalRecord {
+    information: Information,
+    /// As in `ChangedRecord`, by the clock of the installation the last
+    /// change was made on.
+    modified_at: i64,
+    /// When it was last created, changed or imported here, by this
+    /// installation's clock.
+    changed_at: i64,
+}
+
+/// Id of this installation in changesets, generated on first use.
+pub fn installation_id(conn: &Connection) -> rusqlite::Result<String> {
+    if let Some(id) = db::get_setting(conn, INSTALLATION_ID_KEY)? {
+        return Ok(id);
+    }
+    let id: String = conn.query_row("SELECT lower(hex(randomblob(16)))", [], |row| row.get(0))?;
+    db::set_setting(conn, INSTALLATION_ID_KEY, &id)?;
+    Ok(id)
+}
+
+fn get_watermark(conn: &Connection, key: &str) -> rusqlite::Result<i64> {
+    Ok(db::get_setting(conn, key)?
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default())
+}
+
+/// The reports changed here and deleted after `since`, by this installation's
+/// clock. Reports imported from other installations count as changed when
+/// they were imported, and are carried on with their timestamps.
+pub fn collect(conn: &Connection, since: i64) -> Result<Changeset, String> {
+    // Changes made in the current millisecond go into the next changeset, as
+    // more may follow within it.
+    let until = db::now() - 1;
+    let read = || -> rusqlite::Result<Changeset> {
+        let mut stmt = conn.prepare(
+            "
+            SELECT *,
+                coalesce(updated_at, created_at) AS modified_at,
+                coalesce(local_updated_at, created_at) AS changed_at
+            FROM information
+            WHERE coalesce(local_updated_at, created_at) > :since
+                AND coalesce(local_updated_at, created_at) <= :until
+            ORDER BY changed_at, id
+            ",
+        )?;
+        let mut rows = stmt.query(named_params! { ":since": since, ":until": until })?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            records.push(ChangedRecord {
+                uid: row.get("uid")?,
+                modified_at: row.get("modified_at")?,
+                information: read_from_row(row),
+                parties: Vec::new(),
+                events: None,
+            });
+        }
+
+        let mut stmt = conn.prepare(
+            "
+            SELECT uid, deleted_at FROM information_tombstone
+            WHERE deleted_at > :since AND deleted_at <= :until
+            ORDER BY deleted_at
+            ",
+        )?;
+        let deletions = stmt
+            .query_map(named_params! { ":since": since, ":until": until }, |row| {
+                Ok(DeletedRecord {
+                    uid: row.get(0)?,
+                    deleted_at: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(Changeset {
+            format: FORMAT_VERSION,
+            source: installation_id(conn)?,
+            since,
+            until,
+            records,
+            deletions,
+        })
+    };
+    let mut changeset = read().map_err(|_| "Fail to read changes")?;
+    for record in &mut changeset.records {
+        record.parties = party::details_of(conn, record.information.id)?;
+        record.events = Some(event::details_of(conn, record.information.id)?);
+    }
+    Ok(changeset)
+}
+
+/// Writes the changes made after `since` to `path`, or those made since the
+/// previous export when `since` is `None`, and remembers where this changeset
+/// ends for the next export.
+pub fn export_to(
+    conn: &Connection,
+    path: &Path,
+    since: Option<i64>,
+) -> Result<ExportSummary, String> {
+    let since = match since {
+        Some(since) => since,
+        None => get_watermark(conn, EXPORTED_UNTIL_KEY).map_err(|_| "Fail to read changes")?,
+    };
+    let changeset = collect(conn, since)?;
+    let json = serde_json::to_vec(&changeset).map_err(|_| "Fail to write changeset")?;
+    fs::write(path, json).map_err(|_| format!("Cannot write {}", path.display()))?;
+    db::set_setting(conn, EXPORTED_UNTIL_KEY, &changeset.until.to_string())
+        .map_err(|_| "Fail to save export watermark")?;
+    Ok(ExportSummary {
+        records: changeset.records.len(),
+        deletions: changeset.deletions.len(),
+        since: changeset.since,
+        until: changeset.until,
+    })
+}
+
+pub fn read_changeset(path: &Path) -> Result<Changeset, String> {
+    let content = fs::read(path).map_err(|_| format!("Cannot read {}", path.display()))?;
+    let changeset: Changeset = serde_json::from_slice(&content)
+        .map_err(|_| format!("{} is not a changeset", path.display()))?;
+    if changeset.format != FORMAT_VERSION {
+        return Err("Changeset was written by an incompatible version".into());
+    }
+    Ok(changeset)
+}
+
+/// Applies the changeset at `path`, see `apply`.
+pub fn import_from(conn: &Connection, path: &Path) -> Result<ImportReport, String> {
+    apply(conn, &read_changeset(path)?)
+}
+
+fn find_local(conn: &Connection, uid: &str) -> rusqlite::Result<Option<LocalRecord>> {
+    conn.query_row(
+        "
+        SELECT *,
+            coalesce(updated_at, created_at) AS modified_at,
+            coalesce(local_updated_at, created_at) AS changed_at
+        FROM information WHERE uid = :uid
+        ",
+        named_params! { ":uid": uid },
+        |row| {
+            Ok(LocalRecord {
+                information: read_from_row(row),
+                modified_at: row.get("modified_at")?,
+                changed_at: row.get("changed_at")?,
+            })
+        },
+    )
+    .optional()
+}
+
+fn is_tombstoned(conn: &Connection, uid: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT count(*) FROM information_tombstone WHERE uid = :uid",
+        named_params! { ":uid": uid },
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+/// Whether a report other than `id` has the acceptance number.
+fn acceptance_no_taken(conn: &Connection, acceptance_no: &str, id: i64) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT count(*) FROM information WHERE acceptance_no = :acceptance_no AND id <> :id",
+        named_params! { ":acceptance_no": acceptance_no, ":id": id },
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+/// Inserts the report of `record`, or overwrites the one with its `uid`,
+/// keeping the timestamps of the source and marking it changed here at
+/// `imported_at`. Returns its id here.
+fn write_record(
+    conn: &Connection,
+    record: &ChangedRecord,
+    existing: Option<i64>,
+    imported_at: i64,
+) -> rusqlite::Result<i64> {
+    let query = if existing.is_some() {
+        "
+        UPDATE information
+        SET
+            acceptance_no = :acceptance_no,
+            accepted_at = :accepted_at,
+            plaintiff = :plaintiff,
+            defendant = :defendant,
+            description = :description,
+            law = :law,
+            inv_investigator = :inv_investigator,
+            inv_designation_no = :inv_designation_no,
+            inv_designated_at = :inv_designated_at,
+            inv_status = :inv_status,
+            inv_handling_no = :inv_handling_no,
+            inv_handled_at = :inv_handled_at,
+            inv_transferred_at = :inv_transferred_at,
+            inv_extended_at = :inv_extended_at,
+            inv_recovered_at = :inv_recovered_at,
+            inv_canceled_at = :inv_canceled_at,
+            pro_procurator = :pro_procurator,
+            pro_designation_no = :pro_designation_no,
+            pro_designated_at = :pro_designated_at,
+            pro_additional_evidence_requirement = :pro_additional_evidence_requirement,
+            pro_non_prosecution_decision = :pro_non_prosecution_decision,
+            pro_cessation_decision = :pro_cessation_decision,
+            created_at = :created_at,
+            deleted_at = :deleted_at,
+            updated_at = :updated_at,
+            local_updated_at = :local_updated_at
+        WHERE uid = :uid
+        "
+    } else {
+        "
+        INSERT INTO information (
+            uid,
+            acceptance_no,
+            accepted_at,
+            plaintiff,
+            defendant,
+            description,
+            law,
+            inv_investigator,
+            inv_designation_no,
+            inv_designated_at,
+            inv_status,
+            inv_handling_no,
+            inv_handled_at,
+            inv_transferred_at,
+            inv_extended_at,
+            inv_recovered_at,
+            inv_canceled_at,
+            pro_procurator,
+            pro_designation_no,
+            pro_designated_at,
+            pro_additional_evidence_requirement,
+            pro_non_prosecution_decision,
+            pro_cessation_decision,
+            created_at,
+            deleted_at,
+            updated_at,
+            local_updated_at
+        )
+        VALUES (
+            :uid,
+            :acceptance_no,
+            :accepted_at,
+            :plaintiff,
+            :defendant,
+            :description,
+            :law,
+            :inv_investigator,
+            :inv_designation_no,
+            :inv_designated_at,
+            :inv_status,
+            :inv_handling_no,
+            :inv_handled_at,
+            :inv_transferred_at,
+            :inv_extended_at,
+            :inv_recovered_at,
+            :inv_canceled_at,
+            :pro_procurator,
+            :pro_designation_no,
+            :pro_designated_at,
+            :pro_additional_evidence_requirement,
+            :pro_non_prosecution_decision,
+            :pro_cessation_decision,
+            :created_at,
+            :deleted_at,
+            :updated_at,
+            :local_updated_at
+        )
+        "
+    };
+    let information = &record.information;
+    conn.execute(
+        query,
+        named_params! {
+            ":uid": record.uid,
+            ":acceptance_no": information.acceptance_no,
+            ":accepted_at": information.accepted_at,
+            ":plaintiff": information.plaintiff,
+            ":defendant": information.defendant,
+            ":description": information.description,
+            ":law": information.law,
+            ":inv_investigator": information.inv_investigator,
+            ":inv_designation_no": information.inv_designation_no,
+            ":inv_designated_at": information.inv_designated_at,
+            ":inv_status": information.inv_status,
+            ":inv_handling_no": information.inv_handling_no,
+            ":inv_handled_at": information.inv_handled_at,
+            ":inv_transferred_at": information.inv_transferred_at,
+            ":inv_extended_at": information.inv_extended_at,
+            ":inv_recovered_at": information.inv_recovered_at,
+            ":inv_canceled_at": information.inv_canceled_at,
+            ":pro_procurator": information.pro_procurator,
+            ":pro_designation_no": information.pro_designation_no,
+            ":pro_designated_at": information.pro_designated_at,
+            ":pro_additional_evidence_requirement": information.pro_additional_evidence_requirement,
+            ":pro_non_prosecution_decision": information.pro_non_prosecution_decision,
+            ":pro_cessation_decision": information.pro_cessation_decision,
+            ":created_at": information.created_at.unwrap_or(record.modified_at),
+            ":deleted_at": information.deleted_at,
+            ":updated_at": information.updated_at,
+            ":local_updated_at": imported_at,
+        },
+    )?;
+    let id = existing.unwrap_or_else(|| conn.last_insert_rowid());
+    if record.parties.is_empty() {
+        party::create_from_names(conn, id, &information.plaintiff, &information.defendant)?;
+    } else {
+        party::replace_all(conn, id, &record.parties)?;
+    }
+    if let Some(events) = &record.events {
+        event::replace_all(conn, id, events)?;
+    }
+    event::record_columns(conn, id, None)?;
+    numbering::advance_past(conn, &information.acceptance_no, information.accepted_at)?;
+    Ok(id)
+}
+
+/// Applies the changes of another installation in one transaction.
+///
+/// A report changed at the source overwrites the one here when that one was
+/// not changed since the previous import from the same source, whichever
+/// change is newer; otherwise both versions are reported as a conflict and the
+/// one here is kept. The same goes for deletions. Changes here are told by
+/// when they were made or imported by this installation's clock, so the clock
+/// of the source does not matter.
+pub fn apply(conn: &Connection, changeset: &Changeset) -> Result<ImportReport, String> {
+    let own_id = installation_id(conn).map_err(|_| "Fail to read changes")?;
+    if changeset.source == own_id {
+        return Err("Changeset was exported from this installation".into());
+    }
+    let imported_at_key = format!("{}{}", IMPORTED_AT_KEY_PREFIX, changeset.source);
+    let run = || -> rusqlite::Result<(ImportReport, Vec<String>)> {
+        let last_import = match db::get_setting(conn, &imported_at_key)? {
+            Some(value) => value.parse().unwrap_or_default(),
+            None => get_watermark(
+                conn,
+                &format!("{}{}", IMPORTED_UNTIL_KEY_PREFIX, changeset.source),
+            )?,
+        };
+        let imported_at = db::now();
+        let mut report = ImportReport::default();
+        let mut removed_hashes = Vec::new();
+        let tx = conn.unchecked_transaction()?;
+
+        for record in &changeset.records {
+            let conflict = |kind, local: Option<LocalRecord>| Conflict {
+                uid: record.uid.clone(),
+                kind,
+                local_modified_at: local.as_ref().map(|local| local.modified_at),
+                local: local.map(|local| local.information),
+                remote: Some(record.information.clone()),
+                remote_modified_at: record.modified_at,
+            };
+            match find_local(&tx, &record.uid)? {
+                None if is_tombstoned(&tx, &record.uid)? => {
+                    report
+                        .conflicts
+                        .push(conflict(ConflictKind::DeletedHere, None));
+                }
+                None => {
+                    if acceptance_no_taken(&tx, &record.information.acceptance_no, 0)? {
+                        report
+                            .conflicts
+                            .push(conflict(ConflictKind::AcceptanceNoTaken, None));
+                    } else {
+                        write_record(&tx, record, None, imported_at)?;
+                        report.created += 1;
+                    }
+                }
+                Some(local) if local.modified_at == record.modified_at => report.unchanged += 1,
+                Some(local) if local.changed_at > last_import => {
+                    report
+                        .conflicts
+                        .push(conflict(ConflictKind::BothChanged, Some(local)));
+                }
+                Some(local) if local.modified_at > record.modified_at => report.unchanged += 1,
+                Some(local) => {
+                    let id = local.information.id;
+                    if acceptance_no_taken(&tx, &record.information.acceptance_no, id)? {
+                        report
+                            .conflicts
+                            .push(conflict(ConflictKind::AcceptanceNoTaken, Some(local)));
+                    } else {
+                        write_record(&tx, record, Some(id), imported_at)?;
+                        report.updated += 1;
+                    }
+                }
+            }
+        }
+
+        for deletion in &changeset.deletions {
+            match find_local(&tx, &deletion.uid)? {
+                None => report.unchanged += 1,
+                Some(local) if local.changed_at > last_import => {
+                    report.conflicts.push(Conflict {
+                        uid: deletion.uid.clone(),
+                        kind: ConflictKind::DeletedThere,
+                        local_modified_at: Some(local.modified_at),
+                        local: Some(local.information),
+                        remote: None,
+                        remote_modified_at: deletion.deleted_at,
+                    });
+                }
+                Some(local) => {
+                    let hashes = repository::delete_information(&tx, &[local.information.id])?;
+                    removed_hashes.extend(hashes);
+                    report.deleted += 1;
+                }
+            }
+        }
+
+        db::set_setting(&tx, &imported_at_key, &imported_at.to_string())?;
+        tx.commit()?;
+        Ok((report, removed_hashes))
+    };
+    let (report, removed_hashes) = run().map_err(|_| "Fail to apply changeset")?;
+    attachment::remove_unused_files(conn, &removed_hashes);
+    Ok(report)
+}
@@ -0,0 +1,250 @@
+//! Labels such as "urgent" or "juvenile involved" put on reports, and the
+//! list filter on them.
+
+use std::collections::HashMap;
+
+use rusqlite::{named_params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+
+/// Maximum length, in characters, of a tag name.
+const NAME_LENGTH: usize = 50;
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    /// Number of reports carrying the tag.
+    pub count: i64,
+    pub created_at: i64,
+}
+
+fn read_tag(row: &Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        count: row.get("count")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+const SELECT_TAGS: &str = "
+    SELECT
+        tag.id, tag.name, tag.created_at,
+        (SELECT count(*) FROM information_tag WHERE tag_id = tag.id) AS count
+    FROM tag
+";
+
+fn id_list(ids: &[i64]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Condition on `information` matching the reports that carry every tag of
+/// `tag_ids`, for `InformationRepository::list`. An id given twice counts once.
+pub fn filter_condition(tag_ids: &[i64]) -> String {
+    let mut tag_ids = tag_ids.to_vec();
+    tag_ids.sort_unstable();
+    tag_ids.dedup();
+    format!(
+        "
+        id IN (
+            SELECT information_id FROM information_tag
+            WHERE tag_id IN ({})
+            GROUP BY information_id
+            HAVING count(*) = {}
+        )
+        ",
+        id_list(&tag_ids),
+        tag_ids.len()
+    )
+}
+
+fn checked_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Tag name is required".into());
+    }
+    if name.chars().count() > NAME_LENGTH {
+        return Err(format!(
+            "Tag name must have at most {} characters",
+            NAME_LENGTH
+        ));
+    }
+    Ok(name.to_string())
+}
+
+fn ensure_unused(conn: &Connection, name: &str, id: Option<i64>) -> Result<(), String> {
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM tag WHERE name = :name",
+            named_params! { ":name": name },
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|_| "Fail to read tags")?;
+    match existing {
+        Some(existing) if Some(existing) != id => Err(format!("Tag {} already exists", name)),
+        _ => Ok(()),
+    }
+}
+
+fn get(conn: &Connection, id: i64) -> Result<Tag, String> {
+    conn.query_row(
+        &format!("{} WHERE tag.id = :id", SELECT_TAGS),
+        named_params! { ":id": id },
+        read_tag,
+    )
+    .optional()
+    .map_err(|_| "Fail to read tags")?
+    .ok_or_else(|| "Tag not found".into())
+}
+
+/// Every tag, by name.
+pub fn list(conn: &Connection) -> Result<Vec<Tag>, String> {
+    let read = || -> rusqlite::Result<Vec<Tag>> {
+        let mut stmt = conn.prepare(&format!("{} ORDER BY tag.name", SELECT_TAGS))?;
+        let tags = stmt.query_map([], read_tag)?.collect();
+        tags
+    };
+    read().map_err(|_| "Fail to read tags".into())
+}
+
+pub fn create(conn: &Connection, name: &str) -> Result<Tag, String> {
+    let name = checked_name(name)?;
+    ensure_unused(conn, &name, None)?;
+    conn.execute(
+        "INSERT INTO tag (name, created_at) VALUES (:name, :created_at)",
+        named_params! { ":name": name, ":created_at": db::now() },
+    )
+    .map_err(|_| "Fail to save tag")?;
+    get(conn, conn.last_insert_rowid())
+}
+
+pub fn rename(conn: &Connection, id: i64, name: &str) -> Result<Tag, String> {
+    let name = checked_name(name)?;
+    ensure_unused(conn, &name, Some(id))?;
+    let updated = conn
+        .execute(
+            "UPDATE tag SET name = :name WHERE id = :id",
+            named_params! { ":name": name, ":id": id },
+        )
+        .map_err(|_| "Fail to save tag")?;
+    if updated == 0 {
+        return Err("Tag not found".into());
+    }
+    get(conn, id)
+}
+
+/// Deletes a tag, taking it off every report.
+pub fn delete(conn: &Connection, id: i64) -> Result<(), String> {
+    let result = conn.unchecked_transaction().and_then(|tx| {
+        tx.execute(
+            "DELETE FROM information_tag WHERE tag_id = :id",
+            named_params! { ":id": id },
+        )?;
+        tx.execute(
+            "DELETE FROM tag WHERE id = :id",
+            named_params! { ":id": id },
+        )?;
+        tx.commit()
+    });
+    result.map_err(|_| "Fail to delete tag".into())
+}
+
+/// The tags of a report, by name.
+pub fn tags_of(conn: &Connection, information_id: i64) -> Result<Vec<Tag>, String> {
+    let read = || -> rusqlite::Result<Vec<Tag>> {
+        let mut stmt = conn.prepare(&format!(
+            "
+            {}
+            JOIN information_tag ON information_tag.tag_id = tag.id
+            WHERE information_tag.information_id = :information_id
+            ORDER BY tag.name
+            ",
+            SELECT_TAGS
+        ))?;
+        let tags = stmt
+            .query_map(
+                named_params! { ":information_id": information_id },
+                read_tag,
+            )?
+            .collect();
+        tags
+    };
+    read().map_err(|_| "Fail to read tags".into())
+}
+
+/// Puts every tag of `tag_ids` on every report of `ids`, in one transaction.
+/// Reports already carrying a tag keep it.
+pub fn add(conn: &Connection, ids: &[i64], tag_ids: &[i64]) -> Result<(), String> {
+    let result = conn.unchecked_transaction().and_then(|tx| {
+        {
+            let mut stmt = tx.prepare(
+                "
+                INSERT OR IGNORE INTO information_tag (information_id, tag_id, created_at)
+                SELECT information.id, tag.id, :created_at FROM information, tag
+                WHERE information.id = :information_id AND tag.id = :tag_id
+                ",
+            )?;
+            let now = db::now();
+            for id in ids {
+                for tag_id in tag_ids {
+                    stmt.execute(named_params! {
+                        ":information_id": id,
+                        ":tag_id": tag_id,
+                        ":created_at": now,
+                    })?;
+                }
+            }
+        }
+        tx.commit()
+    });
+    result.map_err(|_| "Fail to tag information".into())
+}
+
+/// Takes every tag of `tag_ids` off every report of `ids`.
+pub fn remove(conn: &Connection, ids: &[i64], tag_ids: &[i64]) -> Result<(), String> {
+    conn.execute(
+        &format!(
+            "DELETE FROM information_tag WHERE information_id IN ({}) AND tag_id IN ({})",
+            id_list(ids),
+            id_list(tag_ids)
+        ),
+        [],
+    )
+    .map_err(|_| "Fail to untag information")?;
+    Ok(())
+}
+
+/// Tag names of each report of `ids` that has any, joined with commas, for the
+/// Excel export.
+pub fn names_for(conn: &Connection, ids: &[i64]) -> rusqlite::Result<HashMap<i64, String>> {
+    let mut stmt = conn.prepare(&format!(
+        "
+        SELECT information_id, group_concat(name, ', ') FROM (
+            SELECT information_tag.information_id, tag.name FROM information_tag
+            JOIN tag ON tag.id = information_tag.tag_id
+            WHERE information_tag.information_id IN ({})
+            ORDER BY tag.name
+        )
+        GROUP BY information_id
+        ",
+        id_list(ids)
+    ))?;
+    let names = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect();
+    names
+}
+
+pub fn delete_for_information(conn: &Connection, ids: &[i64]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("DELETE FROM information_tag WHERE information_id = :id")?;
+    for id in ids {
+        stmt.execute(named_params! { ":id": id })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,314 @@
+use chrono_tz::Tz;
+
+use crate::{
+    dates::{self, ColumnKind},
+    error::FieldError,
+    Information,
+};
+
+/// Maximum lengths, in characters, matching the column definitions.
+const ACCEPTANCE_NO_LENGTH: usize = 50;
+const NAME_LENGTH: usize = 100;
+const LAW_LENGTH: usize = 200;
+const NUMBER_LENGTH: usize = 50;
+
+fn is_blank(value: &Option<String>) -> bool {
+    value
+        .as_deref()
+        .map_or(true, |value| value.trim().is_empty())
+}
+
+/// Fields of `information` that differ from `existing`.
+fn changed_fields(information: &Information, existing: &Information) -> Vec<&'static str> {
+    let (info, old) = (information, existing);
+    [
+        ("acceptance_no", info.acceptance_no != old.acceptance_no),
+        ("accepted_at", info.accepted_at != old.accepted_at),
+        ("plaintiff", info.plaintiff != old.plaintiff),
+        ("defendant", info.defendant != old.defendant),
+        ("law", info.law != old.law),
+        (
+            "inv_investigator",
+            info.inv_investigator != old.inv_investigator,
+        ),
+        (
+            "inv_designation_no",
+            info.inv_designation_no != old.inv_designation_no,
+        ),
+        (
+            "inv_designated_at",
+            info.inv_designated_at != old.inv_designated_at,
+        ),
+        ("inv_status", info.inv_status != old.inv_status),
+        (
+            "inv_handling_no",
+            info.inv_handling_no != old.inv_handling_no,
+        ),
+        ("inv_handled_at", info.inv_handled_at != old.inv_handled_at),
+        (
+            "inv_transferred_at",
+            info.inv_transferred_at != old.inv_transferred_at,
+        ),
+        (
+            "inv_extended_at",
+            info.inv_extended_at != old.inv_extended_at,
+        ),
+        (
+            "inv_recovered_at",
+            info.inv_recovered_at != old.inv_recovered_at,
+        ),
+        (
+            "inv_canceled_at",
+            info.inv_canceled_at != old.inv_canceled_at,
+        ),
+        ("pro_procurator", info.pro_procurator != old.pro_procurator),
+        (
+            "pro_designation_no",
+            info.pro_designation_no != old.pro_designation_no,
+        ),
+        (
+            "pro_designated_at",
+            info.pro_designated_at != old.pro_designated_at,
+        ),
+        (
+            "pro_additional_evidence_requirement",
+            info.pro_additional_evidence_requirement != old.pro_additional_evidence_requirement,
+        ),
+        (
+            "pro_non_prosecution_decision",
+            info.pro_non_prosecution_decision != old.pro_non_prosecution_decision,
+        ),
+        (
+            "pro_cessation_decision",
+            info.pro_cessation_decision != old.pro_cessation_decision,
+        ),
+    ]
+    .iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(field, _)| *field)
+    .collect()
+}
+
+struct Validator<'a> {
+    errors: Vec<FieldError>,
+    tz: &'a Tz,
+    /// Fields changed by an update, `None` for a new report. Rules only on
+    /// unchanged fields are skipped, so a stored report that breaks a newer
+    /// rule, or fields the user may not edit, don't block other changes.
+    changed: Option<Vec<&'static str>>,
+}
+
+impl Validator<'_> {
+    fn applies(&self, fields: &[&str]) -> bool {
+        self.changed.as_ref().map_or(true, |changed| {
+            fields.iter().any(|field| changed.contains(field))
+        })
+    }
+
+    fn error(&mut self, field: &'static str, message: impl Into<String>) {
+        // One message per field is enough for the form.
+        if !self.errors.iter().any(|error| error.field() == field) {
+            self.errors.push(FieldError::new(field, message));
+        }
+    }
+
+    fn required(&mut self, field: &'static str, value: &str) {
+        if self.applies(&[field]) && value.trim().is_empty() {
+            self.error(field, "This field is required");
+        }
+    }
+
+    fn max_length(&mut self, field: &'static str, value: Option<&str>, max: usize) {
+        if self.applies(&[field]) && value.map_or(false, |value| value.chars().count() > max) {
+            self.error(field, format!("Must have at most {} characters", max));
+        }
+    }
+
+    /// `later` must not be before `earlier`, both being optional dates taken
+    /// from `fields`. Dates are picked as days, so they are compared as days.
+    fn not_before(
+        &mut self,
+        fields: &[&'static str],
+        later: Option<i64>,
+        earlier: Option<i64>,
+        earlier_label: &str,
+    ) {
+        if !self.applies(fields) {
+            return;
+        }
+        let field = fields[0];
+        let day_of = |millis| dates::day(millis, ColumnKind::Date, self.tz);
+        if let (Some(later), Some(earlier)) = (later.and_then(day_of), earlier.and_then(day_of)) {
+            if later < earlier {
+                self.error(field, format!("Must not be before {}", earlier_label));
+            }
+        }
+    }
+
+    /// `field` can only be filled in together with `dependency`. Both are a
+    /// field name and whether it is filled in.
+    fn requires(&mut self, field: (&'static str, bool), dependency: (&str, bool), label: &str) {
+        if self.applies(&[field.0, dependency.0]) && field.1 && !dependency.1 {
+            self.error(field.0, format!("Requires {}", label));
+        }
+    }
+
+    /// `field` has to be filled in once `dependant` is.
+    fn required_with(&mut self, field: (&'static str, bool), dependant: (&str, bool), label: &str) {
+        if self.applies(&[field.0, dependant.0]) && dependant.1 && !field.1 {
+            self.error(field.0, format!("Requires {}", label));
+        }
+    }
+}
+
+/// Checks a report before it is saved. `existing` is the stored report when
+/// updating, only the rules on fields changed from it are checked; names it
+/// already had may have been joined from several parties and exceed the
+/// length limit.
+pub fn validate_information(
+    information: &Information,
+    existing: Option<&Information>,
+    tz: &Tz,
+) -> Result<(), Vec<FieldError>> {
+    let mut v = Validator {
+        errors: Vec::new(),
+        tz,
+        changed: existing.map(|existing| changed_fields(information, existing)),
+    };
+    let info = information;
+
+    // Required fields. New reports may leave the number blank to have it
+    // generated.
+    if existing.is_some() {
+        v.required("acceptance_no", &info.acceptance_no);
+    }
+    v.required("plaintiff", &info.plaintiff);
+    v.required("defendant", &info.defendant);
+    if v.applies(&["accepted_at"]) {
+        if info.accepted_at <= 0 {
+            v.error("accepted_at", "This field is required");
+        } else if dates::day(info.accepted_at, ColumnKind::Date, tz) > Some(dates::today(tz)) {
+            v.error("accepted_at", "Must not be in the future");
+        }
+    }
+
+    // Length limits.
+    v.max_length(
+        "acceptance_no",
+        Some(info.acceptance_no.trim()),
+        ACCEPTANCE_NO_LENGTH,
+    );
+    v.max_length("plaintiff", Some(&info.plaintiff), NAME_LENGTH);
+    v.max_length("defendant", Some(&info.defendant), NAME_LENGTH);
+    v.max_length("law", info.law.as_deref(), LAW_LENGTH);
+    v.max_length(
+        "inv_investigator",
+        info.inv_investigator.as_deref(),
+        NAME_LENGTH,
+    );
+    v.max_length(
+        "inv_designation_no",
+        info.inv_designation_no.as_deref(),
+        NUMBER_LENGTH,
+    );
+    v.max_length(
+        "inv_handling_no",
+        info.inv_handling_no.as_deref(),
+        NUMBER_LENGTH,
+    );
+    v.max_length(
+        "pro_procurator",
+        info.pro_procurator.as_deref(),
+        NAME_LENGTH,
+    );
+    v.max_length(
+        "pro_designation_no",
+        info.pro_designation_no.as_deref(),
+        NUMBER_LENGTH,
+    );
+
+    // Consistency: a designation names who is designated, by which decision
+    // and when.
+    let investigator = ("inv_investigator", !is_blank(&info.inv_investigator));
+    let inv_designation_no = ("inv_designation_no", !is_blank(&info.inv_designation_no));
+    let inv_designated_at = ("inv_designated_at", info.inv_designated_at.is_some());
+    let inv_handling_no = ("inv_handling_no", !is_blank(&info.inv_handling_no));
+    let inv_handled_at = ("inv_handled_at", info.inv_handled_at.is_some());
+    v.requires(inv_designation_no, investigator, "an investigator");
+    v.requires(inv_designated_at, investigator, "an investigator");
+    v.required_with(inv_designation_no, investigator, "a designation number");
+    v.required_with(inv_designated_at, investigator, "a designation date");
+    v.requires(inv_handled_at, inv_handling_no, "a handling number");
+    v.requires(inv_handling_no, inv_handled_at, "a handling date");
+    for filled in [
+        ("inv_status", info.inv_status.is_some()),
+        inv_handling_no,
+        ("inv_transferred_at", info.inv_transferred_at.is_some()),
+        ("inv_extended_at", info.inv_extended_at.is_some()),
+        ("inv_recovered_at", info.inv_recovered_at.is_some()),
+        ("inv_canceled_at", info.inv_canceled_at.is_some()),
+    ] {
+        v.requires(filled, investigator, "an investigator");
+    }
+
+    let procurator = ("pro_procurator", !is_blank(&info.pro_procurator));
+    let pro_designation_no = ("pro_designation_no", !is_blank(&info.pro_designation_no));
+    let pro_designated_at = ("pro_designated_at", info.pro_designated_at.is_some());
+    v.requires(pro_designation_no, procurator, "a procurator");
+    v.requires(pro_designated_at, procurator, "a procurator");
+    v.required_with(pro_designation_no, procurator, "a designation number");
+    v.required_with(pro_designated_at, procurator, "a designation date");
+    for filled in [
+        (
+            "pro_additional_evidence_requirement",
+            !is_blank(&info.pro_additional_evidence_requirement),
+        ),
+        (
+            "pro_non_prosecution_decision",
+            !is_blank(&info.pro_non_prosecution_decision),
+        ),
+        (
+            "pro_cessation_decision",
+            !is_blank(&info.pro_cessation_decision),
+        ),
+    ] {
+        v.requires(filled, procurator, "a procurator");
+    }
+
+    // Chronology: everything happens after acceptance, and the investigation
+    // steps after the investigator's designation.
+    let accepted_at = Some(info.accepted_at);
+    v.not_before(
+        &["inv_designated_at", "accepted_at"],
+        info.inv_designated_at,
+        accepted_at,
+        "the acceptance date",
+    );
+    v.not_before(
+        &["pro_designated_at", "accepted_at"],
+        info.pro_designated_at,
+        accepted_at,
+        "the acceptance date",
+    );
+    let investigation_start = info.inv_designated_at.or(accepted_at);
+    for (field, date) in [
+        ("inv_handled_at", info.inv_handled_at),
+        ("inv_transferred_at", info.inv_transferred_at),
+        ("inv_extended_at", info.inv_extended_at),
+        ("inv_recovered_at", info.inv_recovered_at),
+        ("inv_canceled_at", info.inv_canceled_at),
+    ] {
+        v.not_before(
+            &[field, "inv_designated_at", "accepted_at"],
+            date,
+            investigation_start,
+            "the designation date",
+        );
+    }
+
+    if v.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(v.errors)
+    }
+}
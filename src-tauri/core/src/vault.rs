@@ -0,0 +1,219 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use rusqlite::{params, Connection, DatabaseName};
+use serde::Serialize;
+
+use crate::{attachment, backup, db, pool::Pool};
+
+/// Every plaintext SQLite file starts with this header; SQLCipher files start
+/// with a random salt instead.
+const PLAINTEXT_HEADER: &[u8; 16] = b"SQLite format 3\0";
+const MIN_PASSWORD_LENGTH: usize = 8;
+const DATABASE_FILE: &str = "docman.db";
+
+/// Location of the encrypted database and the key it was unlocked with. The key
+/// is kept so that connections other than the pool's (backups) can be opened
+/// with it.
+pub struct Vault {
+    path: PathBuf,
+    key: Mutex<Option<String>>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DatabaseStatus {
+    /// No database yet, a password has to be chosen.
+    Uninitialized,
+    /// A database from before encryption exists; choosing a password encrypts it.
+    Plaintext,
+    Locked,
+    Unlocked,
+}
+
+impl Vault {
+    pub fn new(path: PathBuf) -> Self {
+        Vault {
+            path,
+            key: Mutex::new(None),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn key(&self) -> Result<String, String> {
+        self.key
+            .lock()
+            .map_err(|_| "Fail to read database key")?
+            .clone()
+            .ok_or_else(|| "Database is locked".into())
+    }
+
+    pub fn status(&self) -> Result<DatabaseStatus, String> {
+        if self
+            .key
+            .lock()
+            .map_err(|_| "Fail to read database key")?
+            .is_some()
+        {
+            return Ok(DatabaseStatus::Unlocked);
+        }
+        if !self.path.exists() {
+            return Ok(DatabaseStatus::Uninitialized);
+        }
+        if is_plaintext(&self.path).map_err(|_| "Cannot read database file")? {
+            return Ok(DatabaseStatus::Plaintext);
+        }
+        Ok(DatabaseStatus::Locked)
+    }
+
+    /// Opens the database with `key`, remembering the key once it is right.
+    pub fn unlock(&self, key: &str) -> Result<Pool, String> {
+        let pool = open_pool(&self.path, key)?;
+        *self.key.lock().map_err(|_| "Fail to store database key")? = Some(key.to_string());
+        Ok(pool)
+    }
+
+    /// First run: creates the encrypted database, or encrypts the existing
+    /// plaintext one (and the backups taken before encryption) with
+    /// `password`, then unlocks it.
+    pub fn setup_password(&self, password: &str) -> Result<Pool, String> {
+        validate_password(password)?;
+        match self.status()? {
+            DatabaseStatus::Uninitialized => {}
+            DatabaseStatus::Plaintext => {
+                encrypt_file(&self.path, password)?;
+                for old_backup in backup::plaintext_backups(&self.path)? {
+                    encrypt_file(&old_backup, password)?;
+                }
+            }
+            DatabaseStatus::Locked | DatabaseStatus::Unlocked => {
+                return Err("Password is already set".into());
+            }
+        }
+        self.unlock(password)
+    }
+
+    /// Re-keys the database open in `pool`. Backups keep the password they
+    /// were taken with.
+    pub fn change_password(
+        &self,
+        pool: &Pool,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), String> {
+        if self.key()? != old_password {
+            return Err("Wrong password".into());
+        }
+        validate_password(new_password)?;
+
+        let mut key = self.key.lock().map_err(|_| "Fail to store database key")?;
+        pool.rekey(new_password)
+            .map_err(|_| "Fail to change password")?;
+        *key = Some(new_password.to_string());
+        Ok(())
+    }
+
+    /// Records the server the app works against in client mode, `None` when
+    /// it works on this database again; see `connected_server`.
+    pub fn set_connected_server(&self, address: Option<&str>) -> Result<(), String> {
+        let marker = server_marker(&self.path);
+        let result = match address {
+            Some(address) => fs::write(&marker, address),
+            None if marker.exists() => fs::remove_file(&marker),
+            None => Ok(()),
+        };
+        result.map_err(|_| "Fail to record server connection".into())
+    }
+}
+
+/// File next to the database holding the address of the server the app works
+/// against, while it does.
+fn server_marker(path: &Path) -> PathBuf {
+    path.with_extension("server")
+}
+
+/// Address of the server the app using the database at `path` is connected
+/// to, so other processes don't change the database while the app shows the
+/// server's reports instead.
+pub fn connected_server(path: &Path) -> Option<String> {
+    fs::read_to_string(server_marker(path)).ok()
+}
+
+/// The database used by the app, `docman.db` in the user's data folder.
+pub fn default_path() -> Option<PathBuf> {
+    dirs_next::data_dir().map(|dir| dir.join(DATABASE_FILE))
+}
+
+pub fn is_plaintext(path: &Path) -> std::io::Result<bool> {
+    let mut header = [0u8; 16];
+    let mut file = fs::File::open(path)?;
+    match file.read_exact(&mut header) {
+        Ok(()) => Ok(&header == PLAINTEXT_HEADER),
+        // An empty file is what SQLite leaves behind before the first write.
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(true),
+        Err(err) => Err(err),
+    }
+}
+
+/// Opens an SQLCipher database, failing with `NotADatabase` when the key is
+/// wrong.
+pub fn open_encrypted(path: &Path, key: &str) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.pragma_update(None, "key", key)?;
+    // The key is only checked when the first page is read.
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))?;
+    Ok(conn)
+}
+
+/// Rewrites a plaintext database file as an encrypted one in place.
+fn encrypt_file(path: &Path, key: &str) -> Result<(), String> {
+    let encrypted_path = path.with_extension("encrypting");
+    let _ = fs::remove_file(&encrypted_path);
+    {
+        let conn = Connection::open(path).map_err(|_| "Cannot open database")?;
+        let version = db::schema_version(&conn).map_err(|_| "Cannot read database")?;
+        conn.execute(
+            "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+            params![encrypted_path.display().to_string(), key],
+        )
+        .map_err(|_| "Cannot create encrypted database")?;
+        conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))
+            .map_err(|_| "Fail to encrypt database")?;
+        // sqlcipher_export copies the schema and data but not the pragmas.
+        conn.pragma_update(
+            Some(DatabaseName::Attached("encrypted")),
+            "user_version",
+            version,
+        )
+        .map_err(|_| "Fail to encrypt database")?;
+        conn.execute("DETACH DATABASE encrypted", [])
+            .map_err(|_| "Fail to encrypt database")?;
+    }
+    fs::rename(&encrypted_path, path).map_err(|_| "Cannot replace database file")?;
+    Ok(())
+}
+
+fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!(
+            "Password must have at least {} characters",
+            MIN_PASSWORD_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+/// Opens the encrypted database at `path` with `key`, brings its schema up
+/// to date and makes sure it has an attachment key.
+pub fn open_pool(path: &Path, key: &str) -> Result<Pool, String> {
+    let conn = open_encrypted(path, key).map_err(|_| "Wrong password")?;
+    db::migrate(&conn).map_err(|_| "Failed to initialize db")?;
+    attachment::ensure_key(&conn)?;
+    Pool::new(path.to_path_buf(), key, conn).map_err(|_| "Failed to initialize db".into())
+}
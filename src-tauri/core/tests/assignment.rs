@@ -0,0 +1,267 @@
+mod common;
+
+use common::{create, day, report, supervisor};
+use docman_core::{
+    assignment::{self, Assignment, AssignmentKind},
+    auth::{self, NewUser, Role},
+    db,
+    repository::{InformationRepository, ListFilter, SqliteRepository},
+    Information, InformationPageQueryOption,
+};
+use rusqlite::Connection;
+
+fn unassigned(conn: &Connection) -> Vec<i64> {
+    let query = InformationPageQueryOption {
+        limit: 10,
+        ..Default::default()
+    };
+    let (found, _) = SqliteRepository::new(conn)
+        .list(ListFilter::Unassigned, &query)
+        .unwrap();
+    let mut ids: Vec<i64> = found.iter().map(|info| info.id).collect();
+    ids.sort_unstable();
+    ids
+}
+
+fn designation(kind: AssignmentKind, assignee: &str, designated_at: i64) -> Assignment {
+    Assignment {
+        kind,
+        assignee: assignee.to_string(),
+        designation_no: "12/QĐ".to_string(),
+        designated_at,
+    }
+}
+
+#[test]
+fn assign_designates_every_report_or_none() {
+    let conn = db::open_in_memory().unwrap();
+    let first = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let second = create(&conn, report(day(2023, 3, 2), "Lê Văn C", "Phạm Văn D"));
+    let late = create(&conn, report(day(2023, 3, 9), "Lê Văn C", "Trần Thị B"));
+    let supervisor = supervisor();
+    let assignment = designation(
+        AssignmentKind::Investigator,
+        "Điều tra viên E",
+        day(2023, 3, 5),
+    );
+
+    let repo = SqliteRepository::new(&conn);
+    // Designated before the last report was accepted: nothing is assigned.
+    assert!(repo
+        .assign(&supervisor, &[first, second, late], &assignment)
+        .is_err());
+    assert_eq!(unassigned(&conn), vec![first, second, late]);
+
+    repo.assign(&supervisor, &[first, second], &assignment)
+        .unwrap();
+    assert_eq!(unassigned(&conn), vec![late]);
+    let assigned = repo.get(second).unwrap().unwrap();
+    assert_eq!(
+        assigned.inv_investigator.as_deref(),
+        Some("Điều tra viên E")
+    );
+    assert_eq!(assigned.inv_designation_no.as_deref(), Some("12/QĐ"));
+    assert_eq!(assigned.plaintiff, "Lê Văn C");
+
+    let history = assignment::history(&conn, second).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].kind, AssignmentKind::Investigator);
+    assert_eq!(history[0].assigned_by, Some(supervisor.id));
+}
+
+#[test]
+fn assign_needs_reports_that_exist() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let assignment = designation(
+        AssignmentKind::Investigator,
+        "Điều tra viên E",
+        day(2023, 3, 5),
+    );
+    let repo = SqliteRepository::new(&conn);
+
+    let error = repo.assign(&supervisor(), &[], &assignment).unwrap_err();
+    assert_eq!(error.to_string(), "No report to assign");
+    let error = repo
+        .assign(&supervisor(), &[id, id + 1], &assignment)
+        .unwrap_err();
+    assert_eq!(error.to_string(), "Information not found");
+    assert_eq!(unassigned(&conn), vec![id]);
+    assert!(assignment::history(&conn, id).unwrap().is_empty());
+}
+
+#[test]
+fn reassigning_keeps_every_designation_in_history() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let repo = SqliteRepository::new(&conn);
+    for assignment in [
+        designation(
+            AssignmentKind::Investigator,
+            "Điều tra viên E",
+            day(2023, 3, 5),
+        ),
+        designation(
+            AssignmentKind::Procurator,
+            "Kiểm sát viên G",
+            day(2023, 3, 6),
+        ),
+        designation(
+            AssignmentKind::Investigator,
+            "Điều tra viên F",
+            day(2023, 3, 7),
+        ),
+    ] {
+        repo.assign(&supervisor(), &[id], &assignment).unwrap();
+    }
+
+    let information = repo.get(id).unwrap().unwrap();
+    assert_eq!(
+        information.inv_investigator.as_deref(),
+        Some("Điều tra viên F")
+    );
+    assert_eq!(information.inv_designated_at, Some(day(2023, 3, 7)));
+    assert_eq!(
+        information.pro_procurator.as_deref(),
+        Some("Kiểm sát viên G")
+    );
+    let history = assignment::history(&conn, id).unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|record| (record.kind, record.assignee.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (AssignmentKind::Investigator, "Điều tra viên E"),
+            (AssignmentKind::Procurator, "Kiểm sát viên G"),
+            (AssignmentKind::Investigator, "Điều tra viên F"),
+        ]
+    );
+
+    // The history goes with the report.
+    repo.delete(&[id]).unwrap();
+    assert!(assignment::history(&conn, id).unwrap().is_empty());
+}
+
+#[test]
+fn assignees_are_matched_by_the_same_name_everywhere() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(
+        &conn,
+        Information {
+            inv_investigator: Some(" Điều tra viên E ".into()),
+            inv_designation_no: Some("12/QĐ".into()),
+            inv_designated_at: Some(day(2023, 3, 2)),
+            ..report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B")
+        },
+    );
+    let investigator = auth::create_user(
+        &conn,
+        &NewUser {
+            username: "dtv".into(),
+            full_name: "Điều tra viên E".into(),
+            password: "mat-khau-dtv".into(),
+            role: Role::Investigator,
+        },
+    )
+    .unwrap();
+    let repo = SqliteRepository::new(&conn);
+    let information = repo.get(id).unwrap().unwrap();
+    assert_eq!(
+        information.inv_investigator.as_deref(),
+        Some("Điều tra viên E")
+    );
+    assert!(auth::ensure_assigned(&investigator, &information).is_ok());
+    repo.update(
+        &investigator,
+        Information {
+            inv_status: Some(1),
+            ..information
+        },
+    )
+    .unwrap();
+
+    // A renamed account keeps its reports.
+    auth::update_user(
+        &conn,
+        investigator.id,
+        "Điều tra viên Ê",
+        Role::Investigator,
+    )
+    .unwrap();
+    let renamed = auth::list_users(&conn)
+        .unwrap()
+        .into_iter()
+        .find(|user| user.id == investigator.id)
+        .unwrap();
+    let information = repo.get(id).unwrap().unwrap();
+    assert_eq!(
+        information.inv_investigator.as_deref(),
+        Some("Điều tra viên Ê")
+    );
+    assert!(auth::ensure_assigned(&investigator, &information).is_err());
+    assert!(auth::ensure_assigned(&renamed, &information).is_ok());
+    repo.update(&renamed, information).unwrap();
+}
+
+#[test]
+fn accounts_cannot_take_a_blank_or_taken_name() {
+    let conn = db::open_in_memory().unwrap();
+    let mut accounts = Vec::new();
+    for (username, full_name) in [("dtv1", "Điều tra viên F"), ("dtv2", "Điều tra viên G")]
+    {
+        let account = auth::create_user(
+            &conn,
+            &NewUser {
+                username: username.into(),
+                full_name: full_name.into(),
+                password: "mat-khau-dtv".into(),
+                role: Role::Investigator,
+            },
+        )
+        .unwrap();
+        accounts.push(account);
+    }
+    let id = create(
+        &conn,
+        Information {
+            inv_investigator: Some("Điều tra viên F".into()),
+            ..report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B")
+        },
+    );
+
+    assert_eq!(
+        auth::update_user(&conn, accounts[0].id, "  ", Role::Investigator).unwrap_err(),
+        "Full name is required"
+    );
+    assert_eq!(
+        auth::update_user(
+            &conn,
+            accounts[0].id,
+            " Điều tra viên G ",
+            Role::Investigator
+        )
+        .unwrap_err(),
+        "Another account already has this full name"
+    );
+    let information = SqliteRepository::new(&conn).get(id).unwrap().unwrap();
+    assert_eq!(
+        information.inv_investigator.as_deref(),
+        Some("Điều tra viên F")
+    );
+
+    let namesake = NewUser {
+        username: "dtv3".into(),
+        full_name: " Điều tra viên F".into(),
+        password: "mat-khau-dtv".into(),
+        role: Role::Investigator,
+    };
+    assert_eq!(
+        auth::create_user(&conn, &namesake).unwrap_err(),
+        "Another account already has this full name"
+    );
+    assert_eq!(auth::list_users(&conn).unwrap().len(), 2);
+
+    // Keeping its own name is fine.
+    auth::update_user(&conn, accounts[0].id, "Điều tra viên F", Role::Procurator).unwrap();
+}
@@ -0,0 +1,81 @@
+use std::{env, fs};
+
+mod common;
+
+use common::{create, day, report, user};
+use docman_core::{attachment, auth::Role, db, vault, Information};
+
+#[test]
+fn files_are_attached_by_registrars_and_assignees() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(
+        &conn,
+        Information {
+            inv_investigator: Some("Điều tra viên E".into()),
+            ..report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B")
+        },
+    );
+
+    for allowed in [
+        user(1, "Văn thư", Role::Clerk),
+        user(2, "Điều tra viên E", Role::Investigator),
+        user(3, "Trưởng phòng", Role::Supervisor),
+    ] {
+        attachment::check_information(&conn, &allowed, id).unwrap();
+    }
+    for refused in [
+        user(4, "Điều tra viên F", Role::Investigator),
+        user(5, "Kiểm sát viên G", Role::Procurator),
+    ] {
+        assert!(attachment::check_information(&conn, &refused, id).is_err());
+    }
+    assert_eq!(
+        attachment::check_information(&conn, &user(1, "Văn thư", Role::Clerk), id + 1).unwrap_err(),
+        "Information not found"
+    );
+}
+
+#[test]
+fn only_files_the_app_can_open_are_attached() {
+    for file_name in ["to-trinh.PDF", "bien-ban.docx", "anh.jpeg", "scan.tif"] {
+        attachment::check_file_name(file_name).unwrap();
+    }
+    for file_name in ["ghi-chu.txt", "setup.exe", "khong-duoi"] {
+        assert!(attachment::check_file_name(file_name).is_err());
+    }
+}
+
+#[test]
+fn files_are_stored_next_to_their_database() {
+    let name = format!("docman-attachment-store-{}", std::process::id());
+    let path = env::temp_dir().join(format!("{}.db", name));
+    let source = env::temp_dir().join(format!("{}.pdf", name));
+    fs::write(&source, b"scanned report").unwrap();
+    let pool = vault::open_pool(&path, "attachment test key").unwrap();
+
+    let (hash, size) = pool
+        .with_reader(|conn| {
+            let key = attachment::store_key(conn)?;
+            attachment::store_file(&key, &source)
+        })
+        .unwrap();
+    assert_eq!(size, 14);
+    let store = attachment::store_path(&path);
+    assert_eq!(
+        store,
+        env::temp_dir().join(format!("{}.db-attachments", name))
+    );
+    assert!(store.join(&hash[..2]).join(&hash).is_file());
+
+    // A database in memory has no folder of its own to put files in.
+    let conn = db::open_in_memory().unwrap();
+    attachment::ensure_key(&conn).unwrap();
+    assert!(attachment::store_key(&conn).is_err());
+
+    drop(pool);
+    let _ = fs::remove_dir_all(&store);
+    let _ = fs::remove_file(&source);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+mod common;
+
+use chrono::Local;
+use common::{create, day, report};
+use docman_core::{backup, db, pool::Pool, vault};
+use rusqlite::Connection;
+
+const KEY: &str = "backup test key";
+
+/// A database of its own folder, so each test has its own backup folder.
+fn database(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("docman-backup-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir.join("docman.db")
+}
+
+fn remove(database: &Path) {
+    let _ = fs::remove_dir_all(database.parent().unwrap());
+}
+
+fn count(conn: &Connection) -> i64 {
+    conn.query_row("SELECT count(*) FROM information", [], |row| row.get(0))
+        .unwrap()
+}
+
+fn add_report(pool: &Pool, plaintiff: &str) {
+    pool.with_writer(|conn| -> Result<(), String> {
+        create(conn, report(day(2023, 3, 1), plaintiff, "Trần Thị B"));
+        Ok(())
+    })
+    .unwrap();
+}
+
+fn take_backup(pool: &Pool, path: &Path, key: &str) {
+    pool.with_reader(|conn| backup::backup_to(conn, path, key).map_err(|err| err.to_string()))
+        .unwrap();
+}
+
+/// Names of the backups in the folder of `database`, sorted.
+fn backups(database: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(backup::backup_path(database))
+        .into_iter()
+        .flatten()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".db"))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn backups_are_encrypted_with_the_key_of_the_database() {
+    let path = database("copy");
+    let pool = vault::open_pool(&path, KEY).unwrap();
+    add_report(&pool, "Nguyễn Văn A");
+    let copy = path.with_file_name("copy.db");
+    take_backup(&pool, &copy, KEY);
+
+    assert!(!vault::is_plaintext(&copy).unwrap());
+    assert_eq!(count(&vault::open_encrypted(&copy, KEY).unwrap()), 1);
+    assert!(vault::open_encrypted(&copy, "another key").is_err());
+    // A single file, whatever the journal mode of the database.
+    assert!(!copy.with_file_name("copy.db-wal").exists());
+
+    drop(pool);
+    remove(&path);
+}
+
+#[test]
+fn only_the_newest_daily_backups_are_kept() {
+    let path = database("daily");
+    let pool = vault::open_pool(&path, KEY).unwrap();
+    let dir = backup::backup_path(&path);
+    fs::create_dir_all(&dir).unwrap();
+    for date in 1..=9 {
+        fs::write(dir.join(format!("docman-2020-01-0{}.db", date)), b"old").unwrap();
+    }
+    fs::write(dir.join("pre-restore-2020-01-01-080000.db"), b"old").unwrap();
+    fs::write(dir.join("before-merge.db"), b"mine").unwrap();
+
+    backup::run_daily_backup(&pool, KEY).unwrap();
+    let today = format!("docman-{}.db", Local::now().format("%Y-%m-%d"));
+    let mut expected: Vec<String> = (4..=9)
+        .map(|date| format!("docman-2020-01-0{}.db", date))
+        .chain([
+            today.clone(),
+            "pre-restore-2020-01-01-080000.db".to_string(),
+            "before-merge.db".to_string(),
+        ])
+        .collect();
+    expected.sort();
+    assert_eq!(backups(&path), expected);
+    assert_eq!(
+        count(&vault::open_encrypted(&dir.join(&today), KEY).unwrap()),
+        0
+    );
+
+    // Today's backup is taken once.
+    add_report(&pool, "Nguyễn Văn A");
+    backup::run_daily_backup(&pool, KEY).unwrap();
+    assert_eq!(backups(&path), expected);
+    assert_eq!(
+        count(&vault::open_encrypted(&dir.join(&today), KEY).unwrap()),
+        0
+    );
+
+    drop(pool);
+    remove(&path);
+}
+
+#[test]
+fn restoring_keeps_a_copy_of_the_replaced_data() {
+    let path = database("restore");
+    let pool = vault::open_pool(&path, KEY).unwrap();
+    add_report(&pool, "Nguyễn Văn A");
+    let copy = path.with_file_name("copy.db");
+    take_backup(&pool, &copy, KEY);
+    add_report(&pool, "Lê Văn C");
+    let dir = backup::backup_path(&path);
+    fs::create_dir_all(&dir).unwrap();
+    for date in 1..=7 {
+        fs::write(
+            dir.join(format!("pre-restore-2020-01-0{}-080000.db", date)),
+            b"old",
+        )
+        .unwrap();
+    }
+
+    pool.with_writer(|conn| backup::restore(conn, &copy, None, KEY))
+        .unwrap();
+    pool.with_writer(|conn| -> Result<(), String> {
+        assert_eq!(count(conn), 1);
+        Ok(())
+    })
+    .unwrap();
+
+    let pre_restore: Vec<String> = backups(&path)
+        .into_iter()
+        .filter(|name| name.starts_with("pre-restore-"))
+        .collect();
+    assert_eq!(pre_restore.len(), 7);
+    assert_eq!(pre_restore[0], "pre-restore-2020-01-02-080000.db");
+    let saved = dir.join(pre_restore.last().unwrap());
+    assert_eq!(count(&vault::open_encrypted(&saved, KEY).unwrap()), 2);
+
+    drop(pool);
+    remove(&path);
+}
+
+#[test]
+fn restoring_refuses_files_it_cannot_use() {
+    let path = database("refused");
+    let pool = vault::open_pool(&path, KEY).unwrap();
+    add_report(&pool, "Nguyễn Văn A");
+    let restore = |source: &Path, password: Option<&str>| {
+        pool.with_writer(|conn| backup::restore(conn, source, password, KEY))
+    };
+
+    let other = path.with_file_name("other.db");
+    vault::open_encrypted(&other, KEY)
+        .unwrap()
+        .execute_batch("CREATE TABLE note (text TEXT);")
+        .unwrap();
+    assert_eq!(
+        restore(&other, None).unwrap_err(),
+        "Backup file is not a docman database"
+    );
+
+    let newer = path.with_file_name("newer.db");
+    take_backup(&pool, &newer, KEY);
+    vault::open_encrypted(&newer, KEY)
+        .unwrap()
+        .pragma_update(None, "user_version", db::SCHEMA_VERSION + 1)
+        .unwrap();
+    assert_eq!(
+        restore(&newer, None).unwrap_err(),
+        "Backup was made by a newer version of the app"
+    );
+
+    // Taken under a former password, which has to be given.
+    let former = path.with_file_name("former.db");
+    let conn = vault::open_encrypted(&former, "former key").unwrap();
+    db::migrate(&conn).unwrap();
+    create(&conn, report(day(2023, 3, 1), "Phạm Văn D", "Trần Thị B"));
+    drop(conn);
+    for password in [None, Some("not the former key")] {
+        assert_eq!(
+            restore(&former, password).unwrap_err(),
+            "Wrong password or backup file is not a valid database"
+        );
+    }
+    assert!(backups(&path).is_empty());
+
+    add_report(&pool, "Lê Văn C");
+    restore(&former, Some("former key")).unwrap();
+    pool.with_writer(|conn| -> Result<(), String> {
+        assert_eq!(count(conn), 1);
+        Ok(())
+    })
+    .unwrap();
+
+    drop(pool);
+    remove(&path);
+}
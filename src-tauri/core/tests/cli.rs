@@ -0,0 +1,191 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+mod common;
+
+use chrono::Local;
+use common::{create, day, report};
+use docman_core::{backup, vault, Information};
+use serde_json::Value;
+
+const KEY: &str = "cli test key";
+
+/// A database with two reports, in a folder of its own for its backups.
+fn database(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("docman-cli-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("docman.db");
+    let pool = vault::open_pool(&path, KEY).unwrap();
+    pool.with_writer(|conn| -> Result<(), String> {
+        create(conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+        create(conn, report(day(2023, 4, 1), "Lê Văn C", "Phạm Văn D"));
+        Ok(())
+    })
+    .unwrap();
+    path
+}
+
+fn remove(database: &Path) {
+    let _ = fs::remove_dir_all(database.parent().unwrap());
+}
+
+fn cli(database: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docman-cli"))
+        .arg("--db")
+        .arg(database)
+        .args(args)
+        .env("DOCMAN_PASSWORD", KEY)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// The error the command failed with.
+fn error(output: &Output) -> String {
+    assert!(!output.status.success());
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+fn json(output: &Output) -> Value {
+    serde_json::from_str(&stdout(output)).unwrap()
+}
+
+#[test]
+fn reports_are_listed_and_counted() {
+    let path = database("list");
+
+    let listed = json(&cli(&path, &["list", "--json"]));
+    assert_eq!(listed.as_array().unwrap().len(), 2);
+    let searched = json(&cli(&path, &["list", "--search", "Lê Văn", "--json"]));
+    assert_eq!(searched[0]["plaintiff"], "Lê Văn C");
+    assert_eq!(searched.as_array().unwrap().len(), 1);
+    let text = stdout(&cli(&path, &["list", "--limit", "1"]));
+    assert_eq!(text.lines().count(), 1);
+
+    let stats = json(&cli(&path, &["stats", "--json"]));
+    assert_eq!(stats["total"], 2);
+    assert!(stdout(&cli(&path, &["stats"])).starts_with("Total\t2\n"));
+
+    remove(&path);
+}
+
+#[test]
+fn the_database_and_its_password_are_checked() {
+    let path = database("checks");
+
+    let missing = path.with_file_name("missing.db");
+    assert!(error(&cli(&missing, &["stats"])).contains("does not exist"));
+    let wrong = Command::new(env!("CARGO_BIN_EXE_docman-cli"))
+        .args(["--db", path.to_str().unwrap(), "stats"])
+        .env("DOCMAN_PASSWORD", "another key")
+        .output()
+        .unwrap();
+    assert_eq!(error(&wrong), "Error: Wrong password\n");
+    let unset = Command::new(env!("CARGO_BIN_EXE_docman-cli"))
+        .args(["stats", "--db", path.to_str().unwrap()])
+        .env_remove("DOCMAN_PASSWORD")
+        .output()
+        .unwrap();
+    assert!(error(&unset).contains("DOCMAN_PASSWORD"));
+
+    assert!(error(&cli(&path, &["stats", "--bogus", "1"])).contains("Unknown option --bogus"));
+    assert!(error(&cli(&path, &["frobnicate"])).contains("Unknown command frobnicate"));
+    assert!(error(&cli(&path, &["list", "--limit"])).contains("Missing value for --limit"));
+    assert!(stdout(&cli(&path, &[])).starts_with("Usage: docman-cli"));
+
+    remove(&path);
+}
+
+#[test]
+fn reports_are_imported_from_json() {
+    let path = database("import");
+    let file = path.with_file_name("reports.json");
+    let reports: Vec<Information> = vec![
+        report(day(2023, 5, 1), "Hoàng Văn E", "Đặng Thị F"),
+        report(day(2023, 5, 2), "", "Đặng Thị F"),
+    ];
+    fs::write(&file, serde_json::to_string(&reports).unwrap()).unwrap();
+
+    let imported = cli(&path, &["import", file.to_str().unwrap()]);
+    // Invalid reports are skipped, the others created.
+    assert_eq!(String::from_utf8_lossy(&imported.stdout).lines().count(), 1);
+    assert!(error(&imported).contains("1 of 2 reports were not imported"));
+    assert_eq!(json(&cli(&path, &["stats", "--json"]))["total"], 3);
+
+    fs::write(&file, "not json").unwrap();
+    assert!(error(&cli(&path, &["import", file.to_str().unwrap()]))
+        .contains("is not a valid report file"));
+
+    remove(&path);
+}
+
+#[test]
+fn backups_and_exports_are_written() {
+    let path = database("files");
+
+    let copy = path.with_file_name("copy.db");
+    assert_eq!(
+        stdout(&cli(&path, &["backup", "--out", copy.to_str().unwrap()])).trim(),
+        copy.to_str().unwrap()
+    );
+    assert!(vault::open_encrypted(&copy, KEY).is_ok());
+    stdout(&cli(&path, &["backup"]));
+    let today = format!("docman-{}.db", Local::now().format("%Y-%m-%d"));
+    assert!(backup::backup_path(&path).join(today).is_file());
+
+    let workbook = path.with_file_name("reports.xlsx");
+    let out = workbook.to_str().unwrap();
+    stdout(&cli(
+        &path,
+        &[
+            "export",
+            "--from",
+            "2023-01-01",
+            "--to",
+            "2023-12-31",
+            "--out",
+            out,
+        ],
+    ));
+    assert!(fs::metadata(&workbook).unwrap().len() > 0);
+    assert!(error(&cli(
+        &path,
+        &[
+            "export",
+            "--from",
+            "2023-12-31",
+            "--to",
+            "2023-01-01",
+            "--out",
+            out
+        ],
+    ))
+    .contains("--from must not be after --to"));
+    assert!(error(&cli(
+        &path,
+        &[
+            "export",
+            "--from",
+            "31/12/2023",
+            "--to",
+            "2023-12-31",
+            "--out",
+            out
+        ],
+    ))
+    .contains("Invalid date 31/12/2023"));
+
+    remove(&path);
+}
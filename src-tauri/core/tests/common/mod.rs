@@ -0,0 +1,83 @@
+//! Fixtures shared by the integration tests. Each test file uses a part of
+//! them only.
+#![allow(dead_code)]
+
+use chrono::TimeZone;
+use chrono_tz::Asia::Ho_Chi_Minh;
+use docman_core::{
+    auth::{Role, User},
+    repository::{InformationRepository, SqliteRepository},
+    Information,
+};
+use rusqlite::Connection;
+
+/// Midnight of a day in the default timezone, as the form sends it.
+pub fn day(year: i32, month: u32, day: u32) -> i64 {
+    Ho_Chi_Minh
+        .with_ymd_and_hms(year, month, day, 0, 0, 0)
+        .unwrap()
+        .timestamp_millis()
+}
+
+pub fn report(accepted_at: i64, plaintiff: &str, defendant: &str) -> Information {
+    Information {
+        id: 0,
+        acceptance_no: String::new(),
+        accepted_at,
+        plaintiff: plaintiff.to_string(),
+        defendant: defendant.to_string(),
+        description: None,
+        law: None,
+        inv_investigator: None,
+        inv_designation_no: None,
+        inv_designated_at: None,
+        inv_status: None,
+        inv_handling_no: None,
+        inv_handled_at: None,
+        inv_transferred_at: None,
+        inv_extended_at: None,
+        inv_recovered_at: None,
+        inv_canceled_at: None,
+        pro_procurator: None,
+        pro_designation_no: None,
+        pro_designated_at: None,
+        pro_additional_evidence_requirement: None,
+        pro_non_prosecution_decision: None,
+        pro_cessation_decision: None,
+        created_at: None,
+        deleted_at: None,
+        updated_at: None,
+    }
+}
+
+pub fn create(conn: &Connection, information: Information) -> i64 {
+    SqliteRepository::new(conn)
+        .create(information)
+        .expect("report should be valid")
+        .id
+}
+
+/// A logged in user, without an account in the database.
+pub fn user(id: i64, full_name: &str, role: Role) -> User {
+    User {
+        id,
+        username: format!("user{}", id),
+        full_name: full_name.to_string(),
+        role,
+        created_at: 0,
+    }
+}
+
+pub fn admin() -> User {
+    User {
+        username: "admin".to_string(),
+        ..user(1, "Admin", Role::Admin)
+    }
+}
+
+pub fn supervisor() -> User {
+    User {
+        username: "truongphong".to_string(),
+        ..user(3, "Trưởng phòng", Role::Supervisor)
+    }
+}
@@ -0,0 +1,42 @@
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Asia::{Ho_Chi_Minh, Tokyo};
+use docman_core::dates::{self, ColumnKind, DateSettings};
+
+#[test]
+fn date_columns_keep_the_picked_day_and_timestamps_their_own() {
+    // Picked as 1 March on a machine in Tokyo, 22:00 on 28 February here.
+    let picked = Tokyo
+        .with_ymd_and_hms(2023, 3, 1, 0, 0, 0)
+        .unwrap()
+        .timestamp_millis();
+    let first_of_march = NaiveDate::from_ymd_opt(2023, 3, 1);
+    assert_eq!(
+        dates::day(picked, ColumnKind::Date, &Ho_Chi_Minh),
+        first_of_march
+    );
+    assert_eq!(
+        dates::day(picked, ColumnKind::Timestamp, &Ho_Chi_Minh),
+        NaiveDate::from_ymd_opt(2023, 2, 28)
+    );
+    assert_eq!(
+        dates::start_of_day(picked, &Ho_Chi_Minh),
+        dates::midnight(first_of_march.unwrap(), &Ho_Chi_Minh).unwrap()
+    );
+
+    // The kind of the column decides, not whether the value looks like a
+    // midnight: a moment in the evening is on its own day as a timestamp.
+    let evening = Ho_Chi_Minh
+        .with_ymd_and_hms(2023, 3, 1, 22, 0, 0)
+        .unwrap()
+        .timestamp_millis();
+    assert_eq!(
+        dates::day(evening, ColumnKind::Timestamp, &Ho_Chi_Minh),
+        first_of_march
+    );
+
+    let settings: DateSettings =
+        serde_json::from_str(r#"{ "timezone": "Asia/Ho_Chi_Minh", "format": "dd/mm/yyyy" }"#)
+            .unwrap();
+    assert_eq!(settings.format(picked, ColumnKind::Date), "01/03/2023");
+    assert_eq!(settings.format(picked, ColumnKind::Timestamp), "28/02/2023");
+}
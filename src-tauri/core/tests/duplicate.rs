@@ -0,0 +1,162 @@
+mod common;
+
+use common::{admin, create, day, report};
+use docman_core::{
+    db,
+    duplicate::{self, LinkKind},
+    Information,
+};
+use rusqlite::Connection;
+use serde_json::Value;
+
+fn described(accepted_at: i64, plaintiff: &str, defendant: &str, description: &str) -> Information {
+    Information {
+        description: Some(description.to_string()),
+        ..report(accepted_at, plaintiff, defendant)
+    }
+}
+
+/// Ids and scores of the candidates of `id`.
+fn candidates(conn: &Connection, id: i64) -> Vec<(i64, f64)> {
+    duplicate::find_candidates(conn, id)
+        .unwrap()
+        .iter()
+        .map(|candidate| {
+            let candidate = serde_json::to_value(candidate).unwrap();
+            (
+                candidate["information_id"].as_i64().unwrap(),
+                candidate["score"].as_f64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+fn links(conn: &Connection, id: i64) -> Vec<Value> {
+    duplicate::list_links(conn, id)
+        .unwrap()
+        .iter()
+        .map(|link| serde_json::to_value(link).unwrap())
+        .collect()
+}
+
+#[test]
+fn near_identical_reports_are_probable_duplicates() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(
+        &conn,
+        described(
+            day(2023, 3, 1),
+            "Nguyễn Văn An",
+            "Trần Thị Bình",
+            "Mất xe máy Honda Wave tại chợ Bến Thành",
+        ),
+    );
+    // Typed again without diacritics a few days later.
+    let again = create(
+        &conn,
+        described(
+            day(2023, 3, 4),
+            "Nguyen Van An",
+            "Tran Thi Binh",
+            "Mất xe máy Honda Wave ở chợ Bến Thành",
+        ),
+    );
+    create(
+        &conn,
+        described(
+            day(2023, 3, 2),
+            "Lê Văn Cường",
+            "Phạm Văn Dũng",
+            "Tranh chấp ranh giới đất",
+        ),
+    );
+    // The same people, too long after.
+    create(
+        &conn,
+        described(
+            day(2023, 6, 1),
+            "Nguyễn Văn An",
+            "Trần Thị Bình",
+            "Mất xe máy Honda Wave tại chợ Bến Thành",
+        ),
+    );
+
+    let found = candidates(&conn, id);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].0, again);
+    assert!(found[0].1 >= 0.75 && found[0].1 <= 1.0);
+    assert_eq!(candidates(&conn, again)[0].0, id);
+}
+
+#[test]
+fn reports_sharing_one_name_only_are_not_duplicates() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(
+        &conn,
+        described(
+            day(2023, 3, 1),
+            "Nguyễn Văn An",
+            "Trần Thị Bình",
+            "Mất xe máy Honda Wave tại chợ Bến Thành",
+        ),
+    );
+    create(
+        &conn,
+        described(
+            day(2023, 3, 1),
+            "Nguyễn Văn An",
+            "Hoàng Minh Khôi",
+            "Tranh chấp ranh giới đất",
+        ),
+    );
+
+    assert!(candidates(&conn, id).is_empty());
+}
+
+#[test]
+fn links_are_seen_from_both_reports_and_stored_once() {
+    let conn = db::open_in_memory().unwrap();
+    let first = create(
+        &conn,
+        report(day(2023, 3, 1), "Nguyễn Văn An", "Trần Thị Bình"),
+    );
+    let second = create(
+        &conn,
+        report(day(2023, 3, 2), "Nguyễn Văn An", "Trần Thị Bình"),
+    );
+    assert_eq!(candidates(&conn, first).len(), 1);
+
+    duplicate::link(&conn, &admin(), second, first, LinkKind::Duplicate).unwrap();
+    let from_first = links(&conn, first);
+    let from_second = links(&conn, second);
+    assert_eq!(from_first.len(), 1);
+    assert_eq!(from_second.len(), 1);
+    assert_eq!(from_first[0]["id"], from_second[0]["id"]);
+    assert_eq!(from_first[0]["information_id"], second);
+    assert_eq!(from_second[0]["information_id"], first);
+    assert_eq!(from_first[0]["kind"], "duplicate");
+    // Linked reports are no longer suggested.
+    assert!(candidates(&conn, first).is_empty());
+
+    // Linking the pair again, either way round, only changes the kind.
+    duplicate::link(&conn, &admin(), first, second, LinkKind::Related).unwrap();
+    duplicate::link(&conn, &admin(), first, second, LinkKind::Related).unwrap();
+    assert_eq!(links(&conn, first).len(), 1);
+    assert_eq!(links(&conn, second)[0]["kind"], "related");
+
+    let link_id = from_first[0]["id"].as_i64().unwrap();
+    duplicate::unlink(&conn, link_id).unwrap();
+    duplicate::unlink(&conn, link_id).unwrap();
+    assert!(links(&conn, first).is_empty());
+    assert!(links(&conn, second).is_empty());
+    assert_eq!(candidates(&conn, first).len(), 1);
+
+    assert_eq!(
+        duplicate::link(&conn, &admin(), first, first, LinkKind::Related).unwrap_err(),
+        "Cannot link a report to itself"
+    );
+    assert_eq!(
+        duplicate::link(&conn, &admin(), first, second + 1, LinkKind::Related).unwrap_err(),
+        "Information not found"
+    );
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use common::{create, day, report, supervisor, user};
+use docman_core::{
+    auth::Role,
+    db,
+    error::CommandError,
+    event::{self, EventKind, NewEvent},
+    repository::{InformationRepository, SqliteRepository},
+    Information,
+};
+use rusqlite::Connection;
+
+/// A report investigated by `Điều tra viên E` since March 2nd.
+fn investigated(conn: &Connection) -> i64 {
+    create(
+        conn,
+        Information {
+            inv_investigator: Some("Điều tra viên E".into()),
+            inv_designation_no: Some("12/QĐ".into()),
+            inv_designated_at: Some(day(2023, 3, 2)),
+            ..report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B")
+        },
+    )
+}
+
+fn extension(occurred_at: i64) -> NewEvent {
+    NewEvent {
+        kind: EventKind::Extended,
+        occurred_at,
+        document_no: Some("20/QĐ-GH".into()),
+        note: None,
+    }
+}
+
+#[test]
+fn events_keep_the_latest_date_of_their_kind() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(
+        &conn,
+        Information {
+            inv_investigator: Some("Điều tra viên E".into()),
+            inv_designation_no: Some("12/QĐ".into()),
+            inv_designated_at: Some(day(2023, 3, 2)),
+            inv_extended_at: Some(day(2023, 4, 1)),
+            ..report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B")
+        },
+    );
+    let investigator = |full_name: &str| user(5, full_name, Role::Investigator);
+    // The date entered with the report is its first event.
+    assert_eq!(event::list(&conn, id).unwrap().len(), 1);
+
+    let assigned = investigator("Điều tra viên E");
+    event::add(&conn, &assigned, id, extension(day(2023, 5, 1))).unwrap();
+    event::add(&conn, &assigned, id, extension(day(2023, 4, 15))).unwrap();
+    assert!(event::add(&conn, &assigned, id, extension(day(2023, 3, 1))).is_err());
+    assert!(event::add(
+        &conn,
+        &investigator("Người khác"),
+        id,
+        extension(day(2023, 6, 1))
+    )
+    .is_err());
+
+    let events = event::list(&conn, id).unwrap();
+    assert_eq!(
+        events
+            .iter()
+            .map(|event| event.occurred_at)
+            .collect::<Vec<_>>(),
+        vec![day(2023, 4, 1), day(2023, 4, 15), day(2023, 5, 1)]
+    );
+    let repo = SqliteRepository::new(&conn);
+    let information = repo.get(id).unwrap().unwrap();
+    assert_eq!(information.inv_extended_at, Some(day(2023, 5, 1)));
+
+    // A new date from the form is one more event.
+    let cancellation = Information {
+        inv_canceled_at: Some(day(2023, 6, 1)),
+        ..information
+    };
+    repo.update(&assigned, cancellation).unwrap();
+    let kinds: Vec<EventKind> = event::list(&conn, id)
+        .unwrap()
+        .iter()
+        .map(|event| event.kind)
+        .collect();
+    assert_eq!(kinds.last(), Some(&EventKind::Canceled));
+    assert_eq!(kinds.len(), 4);
+}
+
+#[test]
+fn events_are_corrected_by_their_author_or_a_supervisor() {
+    let conn = db::open_in_memory().unwrap();
+    let id = investigated(&conn);
+    let author = user(5, "Điều tra viên E", Role::Investigator);
+    let colleague = user(6, "Điều tra viên F", Role::Investigator);
+    let supervisor = supervisor();
+    let first = event::add(&conn, &author, id, extension(day(2023, 4, 1))).unwrap();
+    let second = event::add(&conn, &author, id, extension(day(2023, 5, 1))).unwrap();
+
+    assert_eq!(
+        event::edit(&conn, &colleague, second.id, extension(day(2023, 5, 2))).unwrap_err(),
+        "You can only change the events you recorded"
+    );
+    assert!(event::remove(&conn, &colleague, second.id).is_err());
+    assert!(event::edit(&conn, &author, second.id, extension(day(2023, 3, 1))).is_err());
+
+    // Entered with the wrong kind: the extension date falls back to the first.
+    let transfer = NewEvent {
+        kind: EventKind::Transferred,
+        ..extension(day(2023, 5, 3))
+    };
+    let corrected = event::edit(&conn, &author, second.id, transfer).unwrap();
+    assert_eq!(corrected.kind, EventKind::Transferred);
+    let repo = SqliteRepository::new(&conn);
+    let information = repo.get(id).unwrap().unwrap();
+    assert_eq!(information.inv_extended_at, Some(day(2023, 4, 1)));
+    assert_eq!(information.inv_transferred_at, Some(day(2023, 5, 3)));
+
+    event::remove(&conn, &supervisor, first.id).unwrap();
+    let information = repo.get(id).unwrap().unwrap();
+    assert_eq!(information.inv_extended_at, None);
+    assert_eq!(event::list(&conn, id).unwrap().len(), 1);
+    assert!(event::remove(&conn, &supervisor, first.id).is_err());
+}
+
+#[test]
+fn update_checks_the_dates_brought_back_by_events() {
+    let conn = db::open_in_memory().unwrap();
+    let id = investigated(&conn);
+    let supervisor = supervisor();
+    event::add(&conn, &supervisor, id, extension(day(2023, 4, 1))).unwrap();
+    let repo = SqliteRepository::new(&conn);
+    let saved = repo.get(id).unwrap().unwrap();
+
+    // The cleared extension comes back from its event, before the new
+    // designation date.
+    let redesignated = Information {
+        inv_designated_at: Some(day(2023, 4, 10)),
+        inv_extended_at: None,
+        ..saved.clone()
+    };
+    match repo.update(&supervisor, redesignated) {
+        Err(CommandError::Invalid { fields }) => {
+            assert_eq!(fields.len(), 1);
+            assert_eq!(fields[0].field(), "inv_extended_at");
+        }
+        other => panic!("expected field errors, got {:?}", other),
+    }
+    assert_eq!(repo.get(id).unwrap().unwrap(), saved);
+}
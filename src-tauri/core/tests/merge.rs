@@ -0,0 +1,132 @@
+use std::{env, fs, path::PathBuf};
+
+mod common;
+
+use common::{create, day};
+use docman_core::{
+    db, merge, party,
+    repository::{InformationRepository, SqliteRepository},
+    vault, Information,
+};
+use rusqlite::Connection;
+
+const KEY: &str = "live key";
+const OTHER_KEY: &str = "other unit key";
+
+fn report(acceptance_no: &str, plaintiff: &str, defendant: &str) -> Information {
+    Information {
+        acceptance_no: acceptance_no.to_string(),
+        ..common::report(day(2023, 3, 1), plaintiff, defendant)
+    }
+}
+
+fn count(conn: &Connection) -> i64 {
+    conn.query_row("SELECT count(*) FROM information", [], |row| row.get(0))
+        .unwrap()
+}
+
+/// The database of another unit, encrypted with its own password, removed on
+/// drop.
+struct OtherDatabase(PathBuf);
+
+impl OtherDatabase {
+    fn create(name: &str, reports: Vec<Information>) -> Self {
+        let path = env::temp_dir().join(format!("docman-{}-{}.db", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        let conn = vault::open_encrypted(&path, OTHER_KEY).unwrap();
+        db::migrate(&conn).unwrap();
+        for information in reports {
+            create(&conn, information);
+        }
+        OtherDatabase(path)
+    }
+}
+
+impl Drop for OtherDatabase {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn merge_adds_new_reports_and_reports_collisions() {
+    let conn = db::open_in_memory().unwrap();
+    create(&conn, report("1/TB", "Nguyễn Văn An", "Trần Thị Bình"));
+    create(&conn, report("2/TB", "Lê Văn Cường", "Phạm Văn Dũng"));
+    let other = OtherDatabase::create(
+        "merge",
+        vec![
+            report("1/TB", "Nguyễn Văn An", "Trần Thị Bình"),
+            report("2/TB", "Hoàng Văn Em", "Phạm Văn Dũng"),
+            report("1/DV", "Võ Thị Phương", "Đặng Văn Giang"),
+        ],
+    );
+
+    let preview = merge::preview(&conn, &other.0, Some(OTHER_KEY), KEY).unwrap();
+    assert_eq!(preview.identical, 1);
+    assert_eq!(preview.new.len(), 1);
+    assert_eq!(preview.new[0].acceptance_no, "1/DV");
+    assert_eq!(preview.collisions.len(), 1);
+    assert_eq!(preview.collisions[0].local.plaintiff, "Lê Văn Cường");
+    assert_eq!(preview.collisions[0].other.plaintiff, "Hoàng Văn Em");
+    assert_eq!(count(&conn), 2);
+
+    let merged = merge::merge(&conn, &other.0, Some(OTHER_KEY), KEY).unwrap();
+    assert_eq!(merged.new.len(), 1);
+    assert_eq!(count(&conn), 3);
+    let id = merged.new[0].id;
+    let information = SqliteRepository::new(&conn).get(id).unwrap().unwrap();
+    assert_eq!(information.acceptance_no, "1/DV");
+    assert_eq!(party::list(&conn, id).unwrap().len(), 2);
+
+    // Merging again finds nothing new.
+    let again = merge::merge(&conn, &other.0, Some(OTHER_KEY), KEY).unwrap();
+    assert!(again.new.is_empty());
+    assert_eq!(again.identical, 2);
+    assert_eq!(count(&conn), 3);
+}
+
+#[test]
+fn reports_with_other_parties_or_events_collide() {
+    let conn = db::open_in_memory().unwrap();
+    let mut extended = report("2/TB", "Lê Văn Cường", "Phạm Văn Dũng");
+    extended.inv_extended_at = Some(day(2023, 4, 1));
+    create(&conn, report("1/TB", "Nguyễn Văn An", "Trần Thị Bình"));
+    create(&conn, extended.clone());
+    let other = OtherDatabase::create(
+        "merge-related",
+        vec![report("1/TB", "Nguyễn Văn An", "Trần Thị Bình"), extended],
+    );
+    let other_conn = vault::open_encrypted(&other.0, OTHER_KEY).unwrap();
+    other_conn
+        .execute_batch(
+            "
+            UPDATE party SET address = 'Hà Nội' WHERE role = 'plaintiff';
+            UPDATE information_event SET document_no = '5/QĐ';
+            ",
+        )
+        .unwrap();
+    drop(other_conn);
+
+    let preview = merge::preview(&conn, &other.0, Some(OTHER_KEY), KEY).unwrap();
+    assert_eq!(preview.identical, 0);
+    let mut collisions: Vec<&str> = preview
+        .collisions
+        .iter()
+        .map(|collision| collision.acceptance_no.as_str())
+        .collect();
+    collisions.sort();
+    assert_eq!(collisions, ["1/TB", "2/TB"]);
+}
+
+#[test]
+fn merge_needs_the_other_password() {
+    let conn = db::open_in_memory().unwrap();
+    let other = OtherDatabase::create(
+        "merge-password",
+        vec![report("1/DV", "Võ Thị Phương", "Đặng Văn Giang")],
+    );
+    assert!(merge::preview(&conn, &other.0, None, KEY).is_err());
+    assert!(merge::merge(&conn, &other.0, Some("wrong password"), KEY).is_err());
+    assert_eq!(count(&conn), 0);
+}
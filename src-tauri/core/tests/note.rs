@@ -0,0 +1,121 @@
+mod common;
+
+use common::{create, day, report, user};
+use docman_core::{
+    auth::Role,
+    db, note,
+    repository::{InformationRepository, ListFilter, SqliteRepository},
+    Information, InformationPageQueryOption,
+};
+use rusqlite::Connection;
+
+/// A report assigned to the investigator `User 1`.
+fn assigned_report(conn: &Connection, accepted_at: i64) -> i64 {
+    create(
+        conn,
+        Information {
+            inv_investigator: Some("User 1".into()),
+            inv_designation_no: Some("12/QĐ".into()),
+            inv_designated_at: Some(accepted_at),
+            ..report(accepted_at, "Nguyễn Văn A", "Trần Thị B")
+        },
+    )
+}
+
+#[test]
+fn notes_are_searchable_and_only_edited_by_their_author() {
+    let conn = db::open_in_memory().unwrap();
+    let id = assigned_report(&conn, day(2023, 3, 1));
+    assigned_report(&conn, day(2023, 3, 2));
+    let investigator = user(1, "User 1", Role::Investigator);
+    let supervisor = user(2, "User 2", Role::Supervisor);
+
+    let first = note::add(&conn, &investigator, id, "Đã mời bị hại lên làm việc", None).unwrap();
+    note::add(&conn, &supervisor, id, "Chờ kết quả giám định", None).unwrap();
+    assert!(note::add(&conn, &investigator, id, "  ", None).is_err());
+    assert!(note::add(&conn, &investigator, id, "Ảnh hiện trường", Some(99)).is_err());
+
+    let edited = note::edit(&conn, &investigator, first.id, "Đã lấy lời khai bị hại").unwrap();
+    assert!(edited.updated_at.is_some());
+    let notes = note::list(&conn, id).unwrap();
+    assert_eq!(notes.len(), 2);
+    assert_eq!(notes[0].text, "Đã lấy lời khai bị hại");
+
+    let query = InformationPageQueryOption {
+        limit: 10,
+        search: Some("giám định".into()),
+        ..Default::default()
+    };
+    let (found, _) = SqliteRepository::new(&conn)
+        .list(ListFilter::All, &query)
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, id);
+}
+
+#[test]
+fn notes_of_others_cannot_be_edited() {
+    let conn = db::open_in_memory().unwrap();
+    let id = assigned_report(&conn, day(2023, 3, 1));
+    let investigator = user(1, "User 1", Role::Investigator);
+    let written = note::add(&conn, &investigator, id, "Đã mời bị hại lên làm việc", None).unwrap();
+
+    // Not even a supervisor or an admin may put words in someone else's note.
+    for other in [
+        user(2, "User 2", Role::Supervisor),
+        user(3, "User 3", Role::Admin),
+    ] {
+        assert_eq!(
+            note::edit(&conn, &other, written.id, "Sửa").unwrap_err(),
+            "You can only edit your own notes"
+        );
+    }
+    assert_eq!(
+        note::list(&conn, id).unwrap()[0].text,
+        "Đã mời bị hại lên làm việc"
+    );
+    assert!(note::edit(&conn, &investigator, 99, "Sửa").is_err());
+}
+
+#[test]
+fn only_assignees_and_supervisors_add_notes() {
+    let conn = db::open_in_memory().unwrap();
+    let id = assigned_report(&conn, day(2023, 3, 1));
+
+    assert_eq!(
+        note::add(
+            &conn,
+            &user(4, "User 4", Role::Investigator),
+            id,
+            "Ghi chú",
+            None
+        )
+        .unwrap_err(),
+        "You can only edit reports assigned to you"
+    );
+    assert_eq!(
+        note::add(
+            &conn,
+            &user(5, "User 5", Role::Procurator),
+            id,
+            "Ghi chú",
+            None
+        )
+        .unwrap_err(),
+        "You can only edit reports assigned to you"
+    );
+    assert_eq!(
+        note::add(&conn, &user(6, "User 6", Role::Clerk), id, "Ghi chú", None).unwrap_err(),
+        "You don't have permission to do this"
+    );
+    note::add(
+        &conn,
+        &user(1, "User 1", Role::Investigator),
+        id,
+        "Ghi chú",
+        None,
+    )
+    .unwrap();
+    note::add(&conn, &user(3, "User 3", Role::Admin), id, "Ghi chú", None).unwrap();
+    assert_eq!(note::list(&conn, id).unwrap().len(), 2);
+}
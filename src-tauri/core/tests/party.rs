@@ -0,0 +1,136 @@
+mod common;
+
+use common::{create, day, report};
+use docman_core::{
+    db,
+    party::{self, PartyDetails},
+    repository::{InformationRepository, SqliteRepository},
+};
+use rusqlite::{named_params, Connection};
+use serde_json::json;
+
+fn details(role: &str, full_name: &str) -> PartyDetails {
+    serde_json::from_value(json!({ "role": role, "full_name": full_name })).unwrap()
+}
+
+fn party_id(conn: &Connection, information_id: i64, full_name: &str) -> i64 {
+    party::list(conn, information_id)
+        .unwrap()
+        .iter()
+        .map(|party| serde_json::to_value(party).unwrap())
+        .find(|party| party["full_name"] == full_name)
+        .unwrap()["id"]
+        .as_i64()
+        .unwrap()
+}
+
+/// Plaintiff and defendant names of the report.
+fn names(conn: &Connection, id: i64) -> (String, String) {
+    let information = SqliteRepository::new(conn).get(id).unwrap().unwrap();
+    (information.plaintiff, information.defendant)
+}
+
+#[test]
+fn the_last_party_of_a_role_is_kept() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+
+    assert_eq!(
+        party::remove(&conn, party_id(&conn, id, "Nguyễn Văn A")).unwrap_err(),
+        "Cannot remove the last party of this role"
+    );
+    assert_eq!(
+        party::update(
+            &conn,
+            party_id(&conn, id, "Trần Thị B"),
+            &details("plaintiff", "Trần Thị B")
+        )
+        .unwrap_err(),
+        "Cannot change the role of the last party of this role"
+    );
+    assert_eq!(
+        party::add(&conn, id, &details("plaintiff", "  ")).unwrap_err(),
+        "Full name is required"
+    );
+    assert_eq!(party::list(&conn, id).unwrap().len(), 2);
+
+    // Once another party has the role, either can go.
+    party::add(&conn, id, &details("plaintiff", "Lê Văn C")).unwrap();
+    party::remove(&conn, party_id(&conn, id, "Nguyễn Văn A")).unwrap();
+    assert_eq!(
+        party::remove(&conn, party_id(&conn, id, "Lê Văn C")).unwrap_err(),
+        "Cannot remove the last party of this role"
+    );
+    assert_eq!(party::list(&conn, id).unwrap().len(), 2);
+}
+
+#[test]
+fn report_names_follow_its_parties() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+
+    party::add(&conn, id, &details("defendant", "Phạm Văn D")).unwrap();
+    party::add(&conn, id, &details("plaintiff", "Lê Văn C")).unwrap();
+    assert_eq!(
+        names(&conn, id),
+        (
+            "Nguyễn Văn A, Lê Văn C".to_string(),
+            "Trần Thị B, Phạm Văn D".to_string()
+        )
+    );
+
+    party::update(
+        &conn,
+        party_id(&conn, id, "Phạm Văn D"),
+        &details("defendant", " Phạm Văn Dũng "),
+    )
+    .unwrap();
+    assert_eq!(names(&conn, id).1, "Trần Thị B, Phạm Văn Dũng");
+
+    party::remove(&conn, party_id(&conn, id, "Nguyễn Văn A")).unwrap();
+    assert_eq!(names(&conn, id).0, "Lê Văn C");
+
+    // Replaced wholesale, as changesets do.
+    party::replace_all(
+        &conn,
+        id,
+        &[
+            details("plaintiff", "Hoàng Văn E"),
+            details("defendant", "Đặng Thị F"),
+            details("defendant", "Vũ Văn G"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        names(&conn, id),
+        (
+            "Hoàng Văn E".to_string(),
+            "Đặng Thị F, Vũ Văn G".to_string()
+        )
+    );
+}
+
+#[test]
+fn unknown_roles_are_refused() {
+    assert!(serde_json::from_value::<PartyDetails>(
+        json!({ "role": "witness", "full_name": "Nguyễn Văn A" })
+    )
+    .is_err());
+
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    // Written by a newer version knowing more roles.
+    conn.execute(
+        "
+        INSERT INTO party (information_id, role, full_name, normalized_name, created_at)
+        VALUES (:id, 'witness', 'Lê Văn C', 'le van c', 0)
+        ",
+        named_params! { ":id": id },
+    )
+    .unwrap();
+    assert_eq!(party::list(&conn, id).unwrap_err(), "Fail to read parties");
+    assert_eq!(
+        party::search(&conn, "Lê Văn C").unwrap_err(),
+        "Fail to search parties"
+    );
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::{admin, create, day, report};
+use docman_core::{
+    db,
+    party::{self, PartyDetails},
+    repository::{InformationRepository, SqliteRepository},
+    Information, InformationPatch,
+};
+use rusqlite::named_params;
+
+fn patch(json: &str) -> InformationPatch {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn patch_changes_only_the_given_fields() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(
+        &conn,
+        Information {
+            description: Some("Mất xe máy".into()),
+            law: Some("Điều 173".into()),
+            ..report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B")
+        },
+    );
+    let admin = admin();
+    let repo = SqliteRepository::new(&conn);
+
+    // `law` is cleared, `description` is left out and kept.
+    repo.patch(&admin, id, patch(r#"{ "law": null }"#)).unwrap();
+    let patched = repo.get(id).unwrap().unwrap();
+    assert_eq!(patched.law, None);
+    assert_eq!(patched.description.as_deref(), Some("Mất xe máy"));
+    assert!(patched.updated_at.is_some());
+
+    // Invalid patches change nothing.
+    let invalid = InformationPatch {
+        inv_designation_no: Some(Some("12/QĐ".into())),
+        ..Default::default()
+    };
+    assert!(repo.patch(&admin, id, invalid).is_err());
+    assert_eq!(repo.get(id).unwrap().unwrap(), patched);
+}
+
+#[test]
+fn patch_tells_null_from_absent() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(
+        &conn,
+        Information {
+            description: Some("Mất xe máy".into()),
+            ..report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B")
+        },
+    );
+    let repo = SqliteRepository::new(&conn);
+
+    repo.patch(&admin(), id, patch("{}")).unwrap();
+    let unchanged = repo.get(id).unwrap().unwrap();
+    assert_eq!(unchanged.description.as_deref(), Some("Mất xe máy"));
+
+    repo.patch(&admin(), id, patch(r#"{ "description": null }"#))
+        .unwrap();
+    assert_eq!(repo.get(id).unwrap().unwrap().description, None);
+
+    // Required fields cannot be cleared.
+    assert!(serde_json::from_str::<InformationPatch>(r#"{ "plaintiff": null }"#).is_err());
+    assert!(serde_json::from_str::<InformationPatch>(r#"{ "accepted_at": null }"#).is_err());
+}
+
+#[test]
+fn patch_only_checks_the_rules_of_the_given_fields() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    // Saved before designation numbers were required.
+    conn.execute(
+        "UPDATE information SET inv_investigator = 'Điều tra viên E' WHERE id = :id",
+        named_params! { ":id": id },
+    )
+    .unwrap();
+    let repo = SqliteRepository::new(&conn);
+
+    repo.patch(&admin(), id, patch(r#"{ "law": "Điều 173" }"#))
+        .unwrap();
+    assert_eq!(
+        repo.get(id).unwrap().unwrap().law.as_deref(),
+        Some("Điều 173")
+    );
+    assert!(repo
+        .patch(
+            &admin(),
+            id,
+            patch(r#"{ "inv_investigator": "Điều tra viên F" }"#)
+        )
+        .is_err());
+}
+
+#[test]
+fn patched_names_rename_the_only_party() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let repo = SqliteRepository::new(&conn);
+
+    repo.patch(&admin(), id, patch(r#"{ "plaintiff": "Nguyễn Văn An" }"#))
+        .unwrap();
+    assert_eq!(repo.get(id).unwrap().unwrap().plaintiff, "Nguyễn Văn An");
+    let names: Vec<String> = party::details_of(&conn, id)
+        .unwrap()
+        .iter()
+        .map(|party| serde_json::to_value(party).unwrap()["full_name"].to_string())
+        .collect();
+    assert_eq!(names.len(), 2);
+    assert!(names.contains(&r#""Nguyễn Văn An""#.to_string()));
+
+    let second: PartyDetails =
+        serde_json::from_str(r#"{ "role": "plaintiff", "full_name": "Lê Văn C" }"#).unwrap();
+    party::add(&conn, id, &second).unwrap();
+    assert!(repo
+        .patch(&admin(), id, patch(r#"{ "plaintiff": "Nguyễn Văn Anh" }"#))
+        .is_err());
+    assert_eq!(
+        repo.get(id).unwrap().unwrap().plaintiff,
+        "Nguyễn Văn An, Lê Văn C"
+    );
+}
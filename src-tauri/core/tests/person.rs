@@ -0,0 +1,135 @@
+mod common;
+
+use common::{admin, create, day, report};
+use docman_core::{
+    db,
+    party::{self, PartyDetails},
+    person,
+    repository::{InformationRepository, SqliteRepository},
+};
+use rusqlite::{named_params, Connection};
+use serde_json::{json, Value};
+
+fn details(role: &str, full_name: &str, id_number: Option<&str>) -> PartyDetails {
+    serde_json::from_value(json!({ "role": role, "full_name": full_name, "id_number": id_number }))
+        .unwrap()
+}
+
+/// Names of the parties found by `person::find_reports`.
+fn found(conn: &Connection, name: &str, id_number: Option<&str>) -> Vec<String> {
+    person::find_reports(conn, name, id_number, None)
+        .unwrap()
+        .iter()
+        .map(|report| {
+            serde_json::to_value(report).unwrap()["full_name"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect()
+}
+
+fn party_id(conn: &Connection, information_id: i64, full_name: &str) -> i64 {
+    party::list(conn, information_id)
+        .unwrap()
+        .iter()
+        .map(|party| serde_json::to_value(party).unwrap())
+        .find(|party| party["full_name"] == full_name)
+        .unwrap()["id"]
+        .as_i64()
+        .unwrap()
+}
+
+#[test]
+fn names_are_compared_without_diacritics_case_or_extra_spaces() {
+    assert_eq!(person::normalize_name("  Nguyễn  Văn AN "), "nguyen van an");
+    assert_eq!(person::normalize_name("Đặng Thị Đào"), "dang thi dao");
+    assert_eq!(person::normalize_id_number(" 012 345 678 "), "012345678");
+
+    let conn = db::open_in_memory().unwrap();
+    let id = create(
+        &conn,
+        report(day(2023, 3, 1), "Nguyễn Văn An", "Đặng Thị Đào"),
+    );
+    create(&conn, report(day(2023, 3, 2), "Nguyễn Văn Ân", "Lê Văn C"));
+
+    assert_eq!(found(&conn, "NGUYEN van  an", None).len(), 2);
+    assert_eq!(found(&conn, "dang thi dao", None), ["Đặng Thị Đào"]);
+    assert!(found(&conn, "Nguyễn Văn", None).is_empty());
+    assert_eq!(
+        person::find_reports(&conn, "Nguyễn Văn An", None, Some(id))
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn id_numbers_are_compared_without_spaces() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    party::add(
+        &conn,
+        id,
+        &details("defendant", "Phạm Văn D", Some("012 345 678")),
+    )
+    .unwrap();
+
+    for typed in ["012345678", " 012 345678 "] {
+        assert_eq!(found(&conn, "Người khác", Some(typed)), ["Phạm Văn D"]);
+    }
+    assert!(found(&conn, "Người khác", Some("   ")).is_empty());
+
+    let searched = |term: &str| -> Vec<Value> {
+        party::search(&conn, term)
+            .unwrap()
+            .iter()
+            .map(|found| serde_json::to_value(found).unwrap()["full_name"].clone())
+            .collect()
+    };
+    assert_eq!(searched("345 678"), [json!("Phạm Văn D")]);
+    assert_eq!(searched("Phạm"), [json!("Phạm Văn D")]);
+}
+
+#[test]
+fn renamed_parties_are_found_by_their_new_name() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let repo = SqliteRepository::new(&conn);
+
+    // Renamed in the report form.
+    let mut edited = repo.get(id).unwrap().unwrap();
+    edited.plaintiff = "Nguyễn Văn Ánh".to_string();
+    repo.update(&admin(), edited).unwrap();
+    assert!(found(&conn, "Nguyễn Văn A", None).is_empty());
+    assert_eq!(found(&conn, "nguyen van anh", None), ["Nguyễn Văn Ánh"]);
+
+    // Renamed in the parties list.
+    party::update(
+        &conn,
+        party_id(&conn, id, "Trần Thị B"),
+        &details("defendant", "Trần Thị Bích", None),
+    )
+    .unwrap();
+    assert!(found(&conn, "Trần Thị B", None).is_empty());
+    assert_eq!(found(&conn, "tran thi bich", None), ["Trần Thị Bích"]);
+}
+
+#[test]
+fn parties_of_upgraded_databases_are_indexed() {
+    let conn = Connection::open_in_memory().unwrap();
+    // Before parties were kept, let alone indexed.
+    db::migrate_to(&conn, 3).unwrap();
+    conn.execute(
+        "
+        INSERT INTO information (acceptance_no, accepted_at, plaintiff, defendant, created_at)
+        VALUES ('1/TB-2023', :accepted_at, ' Nguyễn Văn An ', 'Trần Thị Bình', 0)
+        ",
+        named_params! { ":accepted_at": day(2023, 3, 1) },
+    )
+    .unwrap();
+    db::migrate(&conn).unwrap();
+
+    assert_eq!(found(&conn, "nguyen van an", None), ["Nguyễn Văn An"]);
+    assert_eq!(found(&conn, "TRAN THI BINH", None), ["Trần Thị Bình"]);
+}
@@ -0,0 +1,149 @@
+use std::{
+    env, fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{Arc, Barrier},
+    thread,
+    time::Duration,
+};
+
+mod common;
+
+use common::{create, day, report};
+use docman_core::{attachment, pool::Pool, vault};
+use rusqlite::{named_params, Connection};
+
+const KEY: &str = "pool test key";
+
+fn database(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("docman-pool-{}-{}.db", std::process::id(), name));
+    remove(&path);
+    path
+}
+
+fn remove(path: &Path) {
+    let _ = fs::remove_dir_all(attachment::store_path(path));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
+
+fn count(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row("SELECT count(*) FROM information", [], |row| row.get(0))
+}
+
+/// Inserts a report straight away, in whatever transaction `conn` has open.
+fn insert(conn: &Connection, acceptance_no: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "
+        INSERT INTO information (acceptance_no, accepted_at, plaintiff, defendant, created_at, uid)
+        VALUES (:acceptance_no, 0, 'Lê Văn C', 'Phạm Văn D', 0, lower(hex(randomblob(16))))
+        ",
+        named_params! { ":acceptance_no": acceptance_no },
+    )
+}
+
+fn read_count(pool: &Pool) -> i64 {
+    pool.with_reader(|conn| count(conn).map_err(|err| err.to_string()))
+        .unwrap()
+}
+
+fn add_report(pool: &Pool) {
+    pool.with_writer(|conn| -> Result<(), String> {
+        create(conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[test]
+fn databases_are_opened_in_wal_mode() {
+    let path = database("wal");
+    let pool = vault::open_pool(&path, KEY).unwrap();
+
+    let mode: String = pool
+        .with_reader(|conn| {
+            conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))
+                .map_err(|err| err.to_string())
+        })
+        .unwrap();
+    assert_eq!(mode, "wal");
+
+    drop(pool);
+    remove(&path);
+}
+
+#[test]
+fn reads_go_on_while_a_write_is_in_progress() {
+    let path = database("concurrent");
+    let pool = vault::open_pool(&path, KEY).unwrap();
+    add_report(&pool);
+
+    pool.with_writer(|conn| -> Result<(), String> {
+        let tx = conn.transaction().unwrap();
+        insert(&tx, "2/TB-2023").unwrap();
+        // More readers than the pool opens, all while the writer is held.
+        let barrier = Arc::new(Barrier::new(8));
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let pool = pool.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    pool.with_reader(|conn| {
+                        thread::sleep(Duration::from_millis(20));
+                        count(conn).map_err(|err| err.to_string())
+                    })
+                })
+            })
+            .collect();
+        for reader in readers {
+            // Only what was committed.
+            assert_eq!(reader.join().unwrap(), Ok(1));
+        }
+        tx.commit().unwrap();
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(read_count(&pool), 2);
+
+    drop(pool);
+    remove(&path);
+}
+
+#[test]
+fn failed_writes_leave_the_pool_usable() {
+    let path = database("failure");
+    let pool = vault::open_pool(&path, KEY).unwrap();
+    add_report(&pool);
+
+    // An error rolls back the transaction it left open.
+    let failed = pool.with_writer(|conn| -> Result<(), String> {
+        let tx = conn.transaction().map_err(|err| err.to_string())?;
+        insert(&tx, "2/TB-2023").map_err(|err| err.to_string())?;
+        tx.execute("INSERT INTO missing_table VALUES (1)", [])
+            .map_err(|err| err.to_string())?;
+        tx.commit().map_err(|err| err.to_string())
+    });
+    assert!(failed.is_err());
+    assert_eq!(read_count(&pool), 1);
+
+    // So does a panic, which poisons the lock of the writer.
+    let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+        pool.with_writer(|conn| -> Result<(), String> {
+            let tx = conn.transaction().unwrap();
+            insert(&tx, "3/TB-2023").unwrap();
+            panic!("command failed");
+        })
+    }));
+    assert!(panicked.is_err());
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        pool.with_reader(|_| -> Result<(), String> { panic!("command failed") })
+    }));
+
+    add_report(&pool);
+    assert_eq!(read_count(&pool), 2);
+
+    drop(pool);
+    remove(&path);
+}
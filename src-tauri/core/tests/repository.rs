@@ -0,0 +1,417 @@
+mod common;
+
+use chrono_tz::Asia::Ho_Chi_Minh;
+use common::{admin, create, day, report};
+use docman_core::{
+    dates, db,
+    numbering::NumberingScheme,
+    party::{self, PartyDetails},
+    repository::{InformationRepository, ListFilter, SqliteRepository},
+    settings::{self, Settings},
+    Information, InformationPageQueryOption,
+};
+use rusqlite::{named_params, Connection};
+
+fn list(
+    conn: &Connection,
+    filter: ListFilter,
+    search: Option<&str>,
+    offset: i64,
+    limit: i64,
+) -> (Vec<Information>, Option<i64>) {
+    let query = InformationPageQueryOption {
+        offset,
+        limit,
+        search: search.map(String::from),
+        ..Default::default()
+    };
+    SqliteRepository::new(conn).list(filter, &query).unwrap()
+}
+
+fn ids(informations: &[Information]) -> Vec<i64> {
+    let mut ids: Vec<i64> = informations.iter().map(|info| info.id).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn create_generates_acceptance_numbers() {
+    let conn = db::open_in_memory().unwrap();
+    let repo = SqliteRepository::new(&conn);
+    let first = repo
+        .create(report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"))
+        .unwrap();
+    let second = repo
+        .create(report(day(2023, 3, 2), "Lê Văn C", "Phạm Văn D"))
+        .unwrap();
+    assert_eq!(first.acceptance_no, "1/TB-2023");
+    assert_eq!(second.acceptance_no, "2/TB-2023");
+
+    let stored = repo.get(first.id).unwrap().unwrap();
+    assert_eq!(stored.acceptance_no, "1/TB-2023");
+    assert_eq!(stored.plaintiff, "Nguyễn Văn A");
+}
+
+#[test]
+fn typed_numbers_keep_the_sequence_gapless() {
+    let conn = db::open_in_memory().unwrap();
+    let repo = SqliteRepository::new(&conn);
+    create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let typed = |acceptance_no: &str| {
+        repo.create(Information {
+            acceptance_no: acceptance_no.to_string(),
+            ..report(day(2023, 3, 2), "Lê Văn C", "Phạm Văn D")
+        })
+    };
+
+    // 3/TB-2023 would leave 2/TB-2023 unused.
+    assert!(typed("3/TB-2023").is_err());
+    assert!(typed("02/TB-2023").is_ok());
+    assert!(typed("2/TB-2023").is_ok());
+    assert!(typed("Số cũ 15").is_ok());
+    let next = repo
+        .create(report(day(2023, 3, 3), "Lê Văn C", "Phạm Văn D"))
+        .unwrap();
+    assert_eq!(next.acceptance_no, "3/TB-2023");
+
+    let scheme: NumberingScheme =
+        serde_json::from_str(r#"{ "pattern": "{seq:11}/TB-{yyyy}", "unit": "" }"#).unwrap();
+    assert!(scheme.validate().is_err());
+}
+
+#[test]
+fn numbering_goes_on_after_numbers_typed_before_the_sequence() {
+    let conn = db::open_in_memory().unwrap();
+    // Reports of an upgraded database, numbered by hand with no sequence.
+    for acceptance_no in &["1/TB-2023", "2/TB-2023", "3/TB-2023", "5/TB-2023"] {
+        conn.execute(
+            "
+            INSERT INTO information (acceptance_no, accepted_at, plaintiff, defendant, created_at, uid)
+            VALUES (:acceptance_no, :accepted_at, 'Nguyễn Văn A', 'Trần Thị B', 0, lower(hex(randomblob(16))))
+            ",
+            named_params! { ":acceptance_no": acceptance_no, ":accepted_at": day(2023, 3, 1) },
+        )
+        .unwrap();
+    }
+    let repo = SqliteRepository::new(&conn);
+    let typed = |acceptance_no: &str| {
+        repo.create(Information {
+            acceptance_no: acceptance_no.to_string(),
+            ..report(day(2023, 3, 2), "Lê Văn C", "Phạm Văn D")
+        })
+    };
+
+    assert!(typed("7/TB-2023").is_err());
+    assert!(typed("4/TB-2023").is_ok());
+    // 5/TB-2023 is taken too, so the sequence goes on after it.
+    let next = repo
+        .create(report(day(2023, 3, 3), "Lê Văn C", "Phạm Văn D"))
+        .unwrap();
+    assert_eq!(next.acceptance_no, "6/TB-2023");
+    assert!(typed("7/TB-2023").is_ok());
+
+    // Renumbering a report follows the same rule.
+    let admin = admin();
+    let mut renumbered = repo.get(next.id).unwrap().unwrap();
+    renumbered.acceptance_no = "9/TB-2023".into();
+    assert!(repo.update(&admin, renumbered.clone()).is_err());
+    renumbered.acceptance_no = "8/TB-2023".into();
+    repo.update(&admin, renumbered).unwrap();
+}
+
+#[test]
+fn list_searches_fields_and_parties() {
+    let conn = db::open_in_memory().unwrap();
+    let a = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let b = create(&conn, report(day(2023, 3, 2), "Lê Văn C", "Phạm Văn D"));
+    conn.execute(
+        "UPDATE party SET id_number = '001099012345' WHERE information_id = :id",
+        named_params! { ":id": b },
+    )
+    .unwrap();
+
+    let (found, total) = list(&conn, ListFilter::All, Some("Trần"), 0, 10);
+    assert_eq!(ids(&found), vec![a]);
+    assert_eq!(total, Some(1));
+
+    let (found, _) = list(&conn, ListFilter::All, Some("2/TB"), 0, 10);
+    assert_eq!(ids(&found), vec![b]);
+
+    let (found, _) = list(&conn, ListFilter::All, Some("0010990"), 0, 10);
+    assert_eq!(ids(&found), vec![b]);
+
+    let (found, total) = list(&conn, ListFilter::All, Some("nobody"), 0, 10);
+    assert!(found.is_empty());
+    assert_eq!(total, None);
+}
+
+#[test]
+fn list_pages_through_every_report() {
+    let conn = db::open_in_memory().unwrap();
+    let mut created: Vec<i64> = (1..=7)
+        .map(|n| create(&conn, report(day(2023, 3, n), "Nguyễn Văn A", "Trần Thị B")))
+        .collect();
+    created.sort_unstable();
+
+    let mut seen = Vec::new();
+    for offset in [0, 3, 6] {
+        let (page, total) = list(&conn, ListFilter::All, None, offset, 3);
+        assert_eq!(total, Some(7));
+        assert_eq!(page.len(), if offset == 6 { 1 } else { 3 });
+        seen.extend(page);
+    }
+    assert_eq!(ids(&seen), created);
+
+    let (page, total) = list(&conn, ListFilter::All, None, 7, 3);
+    assert!(page.is_empty());
+    assert_eq!(total, None);
+}
+
+#[test]
+fn unassigned_filter_skips_designated_reports() {
+    let conn = db::open_in_memory().unwrap();
+    let unassigned = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    create(
+        &conn,
+        Information {
+            inv_investigator: Some("Điều tra viên E".into()),
+            inv_designation_no: Some("12/QĐ".into()),
+            inv_designated_at: Some(day(2023, 3, 3)),
+            ..report(day(2023, 3, 2), "Lê Văn C", "Phạm Văn D")
+        },
+    );
+    create(
+        &conn,
+        Information {
+            pro_procurator: Some("Kiểm sát viên F".into()),
+            pro_designation_no: Some("7/QĐ".into()),
+            pro_designated_at: Some(day(2023, 3, 4)),
+            ..report(day(2023, 3, 3), "Lê Văn C", "Trần Thị B")
+        },
+    );
+
+    let (found, total) = list(&conn, ListFilter::Unassigned, None, 0, 10);
+    assert_eq!(ids(&found), vec![unassigned]);
+    assert_eq!(total, Some(1));
+
+    // The filter also applies to searches.
+    let (found, _) = list(&conn, ListFilter::Unassigned, Some("Trần"), 0, 10);
+    assert_eq!(ids(&found), vec![unassigned]);
+
+    let (found, _) = list(&conn, ListFilter::All, None, 0, 10);
+    assert_eq!(found.len(), 3);
+}
+
+#[test]
+fn overdue_filter_follows_the_deadline_settings() {
+    let conn = db::open_in_memory().unwrap();
+    let today = dates::today(&Ho_Chi_Minh);
+    let days_ago =
+        |days: i64| dates::midnight(today - chrono::Duration::days(days), &Ho_Chi_Minh).unwrap();
+    let late = create(&conn, report(days_ago(30), "Nguyễn Văn A", "Trần Thị B"));
+    let recent = create(&conn, report(days_ago(10), "Lê Văn C", "Phạm Văn D"));
+    let extended = create(
+        &conn,
+        Information {
+            inv_investigator: Some("Điều tra viên E".into()),
+            inv_designation_no: Some("12/QĐ".into()),
+            inv_designated_at: Some(days_ago(29)),
+            inv_extended_at: Some(days_ago(5)),
+            ..report(days_ago(30), "Lê Văn C", "Trần Thị B")
+        },
+    );
+    let query = InformationPageQueryOption {
+        overdue: true,
+        ..Default::default()
+    };
+    let repo = SqliteRepository::new(&conn);
+
+    let (found, _) = repo.list(ListFilter::All, &query).unwrap();
+    assert_eq!(ids(&found), vec![late]);
+
+    let mut settings = Settings::load(&conn).unwrap();
+    let mut json = serde_json::to_value(&settings).unwrap();
+    json["deadlines"] = serde_json::json!({ "handling_days": 5, "extended_handling_days": 20 });
+    settings = serde_json::from_value(json).unwrap();
+    settings::update(&conn, &settings).unwrap();
+    let (found, _) = repo.list(ListFilter::All, &query).unwrap();
+    assert_eq!(ids(&found), vec![late, recent, extended]);
+}
+
+#[test]
+fn export_lists_reports_in_range_with_every_party() {
+    let conn = db::open_in_memory().unwrap();
+    let before = create(
+        &conn,
+        report(day(2023, 2, 28), "Nguyễn Văn A", "Trần Thị B"),
+    );
+    let late = create(&conn, report(day(2023, 3, 5), "Lê Văn C", "Phạm Văn D"));
+    let early = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    conn.execute(
+        "
+        INSERT INTO party (information_id, role, full_name, normalized_name, created_at)
+        VALUES (:id, 'defendant', 'Hoàng Văn G', 'hoang van g', 0)
+        ",
+        named_params! { ":id": early },
+    )
+    .unwrap();
+
+    let repo = SqliteRepository::new(&conn);
+    let (from, to) = (day(2023, 3, 1), day(2023, 3, 31));
+    assert_eq!(repo.count_accepted(from, to).unwrap(), 2);
+
+    let rows = repo
+        .export_chunk(from, to, (i64::MIN, i64::MIN), 10)
+        .unwrap();
+    assert_eq!(
+        rows.iter().map(|row| row.id).collect::<Vec<_>>(),
+        vec![early, late]
+    );
+    assert!(rows.iter().all(|row| row.id != before));
+    assert_eq!(rows[0].plaintiff, "Nguyễn Văn A");
+    assert_eq!(rows[0].defendant, "Trần Thị B\nHoàng Văn G");
+    assert_eq!(rows[1].defendant, "Phạm Văn D");
+}
+
+#[test]
+fn export_pages_by_acceptance_date_and_id() {
+    let conn = db::open_in_memory().unwrap();
+    let created: Vec<i64> = [3, 1, 2, 1]
+        .iter()
+        .map(|&n| create(&conn, report(day(2023, 3, n), "Nguyễn Văn A", "Trần Thị B")))
+        .collect();
+
+    let repo = SqliteRepository::new(&conn);
+    let (from, to) = (day(2023, 3, 1), day(2023, 3, 31));
+    let mut after = (i64::MIN, i64::MIN);
+    let mut exported = Vec::new();
+    loop {
+        let chunk = repo.export_chunk(from, to, after, 3).unwrap();
+        match chunk.last() {
+            Some(last) => after = (last.accepted_at, last.id),
+            None => break,
+        }
+        exported.extend(chunk.iter().map(|row| row.id));
+    }
+    assert_eq!(
+        exported,
+        vec![created[1], created[3], created[2], created[0]]
+    );
+}
+
+#[test]
+fn delete_removes_reports_and_their_parties() {
+    let conn = db::open_in_memory().unwrap();
+    let kept = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let deleted = create(&conn, report(day(2023, 3, 2), "Lê Văn C", "Phạm Văn D"));
+
+    let repo = SqliteRepository::new(&conn);
+    repo.delete(&[deleted]).unwrap();
+    assert!(repo.get(deleted).unwrap().is_none());
+    let (found, _) = list(&conn, ListFilter::All, None, 0, 10);
+    assert_eq!(ids(&found), vec![kept]);
+
+    let parties: i64 = conn
+        .query_row(
+            "SELECT count(*) FROM party WHERE information_id = :id",
+            named_params! { ":id": deleted },
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(parties, 0);
+}
+
+#[test]
+fn update_renames_the_only_party_of_an_edited_name() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let admin = admin();
+    let repo = SqliteRepository::new(&conn);
+
+    let mut edited = repo.get(id).unwrap().unwrap();
+    edited.plaintiff = "Nguyễn Văn An".to_string();
+    repo.update(&admin, edited).unwrap();
+    let updated = repo.get(id).unwrap().unwrap();
+    assert_eq!(updated.plaintiff, "Nguyễn Văn An");
+    let parties = party::details_of(&conn, id).unwrap();
+    assert_eq!(parties.len(), 2);
+
+    // With several defendants the name can only change in the parties list.
+    let second: PartyDetails =
+        serde_json::from_str(r#"{ "role": "defendant", "full_name": "Phạm Văn D" }"#).unwrap();
+    party::add(&conn, id, &second).unwrap();
+    let mut edited = repo.get(id).unwrap().unwrap();
+    assert_eq!(edited.defendant, "Trần Thị B, Phạm Văn D");
+    edited.defendant = "Trần Thị C".to_string();
+    assert!(repo.update(&admin, edited).is_err());
+    assert_eq!(
+        repo.get(id).unwrap().unwrap().defendant,
+        "Trần Thị B, Phạm Văn D"
+    );
+}
+
+#[test]
+fn party_roles_only_change_while_another_party_keeps_them() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let details = |role: &str, full_name: &str| -> PartyDetails {
+        serde_json::from_value(serde_json::json!({ "role": role, "full_name": full_name })).unwrap()
+    };
+    party::add(&conn, id, &details("defendant", "Phạm Văn D")).unwrap();
+    let id_of = |full_name: &str| {
+        party::list(&conn, id)
+            .unwrap()
+            .iter()
+            .map(|party| serde_json::to_value(party).unwrap())
+            .find(|party| party["full_name"] == full_name)
+            .unwrap()["id"]
+            .as_i64()
+            .unwrap()
+    };
+    let repo = SqliteRepository::new(&conn);
+
+    assert_eq!(
+        party::update(
+            &conn,
+            id_of("Nguyễn Văn A"),
+            &details("defendant", "Nguyễn Văn A")
+        )
+        .unwrap_err(),
+        "Cannot change the role of the last party of this role"
+    );
+    let information = repo.get(id).unwrap().unwrap();
+    assert_eq!(information.plaintiff, "Nguyễn Văn A");
+    assert_eq!(information.defendant, "Trần Thị B, Phạm Văn D");
+
+    party::update(
+        &conn,
+        id_of("Trần Thị B"),
+        &details("plaintiff", "Trần Thị B"),
+    )
+    .unwrap();
+    let information = repo.get(id).unwrap().unwrap();
+    assert_eq!(information.plaintiff, "Nguyễn Văn A, Trần Thị B");
+    assert_eq!(information.defendant, "Phạm Văn D");
+}
+
+#[test]
+fn update_only_checks_the_rules_of_changed_fields() {
+    let conn = db::open_in_memory().unwrap();
+    let id = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    // Saved before designations had to name the investigator.
+    conn.execute(
+        "UPDATE information SET inv_designation_no = '12/QĐ' WHERE id = :id",
+        named_params! { ":id": id },
+    )
+    .unwrap();
+    let admin = admin();
+    let repo = SqliteRepository::new(&conn);
+
+    let mut edited = repo.get(id).unwrap().unwrap();
+    edited.description = Some("Mất xe máy".to_string());
+    repo.update(&admin, edited).unwrap();
+
+    let mut edited = repo.get(id).unwrap().unwrap();
+    edited.inv_designation_no = Some("13/QĐ".to_string());
+    assert!(repo.update(&admin, edited).is_err());
+}
@@ -0,0 +1,283 @@
+use std::{env, fs, path::PathBuf};
+
+use chrono::TimeZone;
+use chrono_tz::Asia::Ho_Chi_Minh;
+use docman_core::{
+    attachment,
+    auth::{self, NewUser, Role},
+    error::CommandError,
+    pool::Pool,
+    remote::RemoteRepository,
+    repository::{InformationRepository, ListFilter},
+    server::Server,
+    vault, Information, InformationPageQueryOption,
+};
+
+const KEY: &str = "server test key";
+const PASSWORD: &str = "correct horse";
+
+/// A server on a free loopback port over a database of its own, removed on
+/// drop.
+struct TestServer {
+    server: Option<Server>,
+    pool: Pool,
+    path: PathBuf,
+}
+
+impl TestServer {
+    fn start(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("docman-{}-{}.db", name, std::process::id()));
+        let pool = vault::open_pool(&path, KEY).unwrap();
+        pool.with_writer(|conn| {
+            for (username, role) in [("admin", Role::Admin), ("clerk", Role::Clerk)] {
+                auth::create_user(
+                    conn,
+                    &NewUser {
+                        username: username.to_string(),
+                        full_name: username.to_string(),
+                        password: PASSWORD.to_string(),
+                        role,
+                    },
+                )?;
+            }
+            Ok::<_, String>(())
+        })
+        .unwrap();
+        let server = Server::start(pool.clone(), "127.0.0.1:0").unwrap();
+        TestServer {
+            server: Some(server),
+            pool,
+            path,
+        }
+    }
+
+    fn address(&self) -> String {
+        self.server.as_ref().unwrap().addr().to_string()
+    }
+
+    fn connect(&self, username: &str) -> RemoteRepository {
+        RemoteRepository::connect(&self.address(), username, PASSWORD)
+            .unwrap()
+            .0
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.server.take();
+        let _ = fs::remove_dir_all(attachment::store_path(&self.path));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = fs::remove_file(format!("{}{}", self.path.display(), suffix));
+        }
+    }
+}
+
+fn report(plaintiff: &str, defendant: &str) -> Information {
+    Information {
+        id: 0,
+        acceptance_no: String::new(),
+        accepted_at: Ho_Chi_Minh
+            .with_ymd_and_hms(2023, 3, 1, 0, 0, 0)
+            .unwrap()
+            .timestamp_millis(),
+        plaintiff: plaintiff.to_string(),
+        defendant: defendant.to_string(),
+        description: None,
+        law: None,
+        inv_investigator: None,
+        inv_designation_no: None,
+        inv_designated_at: None,
+        inv_status: None,
+        inv_handling_no: None,
+        inv_handled_at: None,
+        inv_transferred_at: None,
+        inv_extended_at: None,
+        inv_recovered_at: None,
+        inv_canceled_at: None,
+        pro_procurator: None,
+        pro_designation_no: None,
+        pro_designated_at: None,
+        pro_additional_evidence_requirement: None,
+        pro_non_prosecution_decision: None,
+        pro_cessation_decision: None,
+        created_at: None,
+        deleted_at: None,
+        updated_at: None,
+    }
+}
+
+#[test]
+fn remote_repository_reads_and_writes_through_the_server() {
+    let server = TestServer::start("server-round-trip");
+    let (remote, user) = RemoteRepository::connect(&server.address(), "admin", PASSWORD).unwrap();
+    assert_eq!(user.role, Role::Admin);
+
+    let created = remote
+        .create(report("Nguyễn Văn An", "Trần Thị Bình"))
+        .unwrap();
+    remote
+        .create(report("Lê Văn Cường", "Phạm Văn Dũng"))
+        .unwrap();
+    assert!(!created.acceptance_no.is_empty());
+
+    let (informations, total) = remote
+        .list(ListFilter::All, &InformationPageQueryOption::default())
+        .unwrap();
+    assert_eq!(informations.len(), 2);
+    assert_eq!(total, Some(2));
+
+    let mut information = remote.get(created.id).unwrap().unwrap();
+    information.description = Some("Mất xe máy".to_string());
+    remote.update(&user, information).unwrap();
+    let updated = remote.get(created.id).unwrap().unwrap();
+    assert_eq!(updated.description.as_deref(), Some("Mất xe máy"));
+
+    remote.delete(&[created.id]).unwrap();
+    assert!(remote.get(created.id).unwrap().is_none());
+    assert_eq!(remote.stats().unwrap().total, 1);
+}
+
+#[test]
+fn server_checks_credentials_and_roles() {
+    let server = TestServer::start("server-auth");
+    let login = RemoteRepository::connect(&server.address(), "admin", "wrong password");
+    assert!(login.is_err());
+
+    let clerk = server.connect("clerk");
+    let created = clerk
+        .create(report("Nguyễn Văn An", "Trần Thị Bình"))
+        .unwrap();
+    assert_eq!(
+        clerk.delete(&[created.id]).unwrap_err(),
+        "You don't have permission to do this"
+    );
+    // Exporting is for managers, over the server as well.
+    assert_eq!(
+        clerk.count_accepted(0, i64::MAX).unwrap_err(),
+        "You don't have permission to do this"
+    );
+    assert_eq!(
+        clerk.export_chunk(0, i64::MAX, (0, 0), 10).unwrap_err(),
+        "You don't have permission to do this"
+    );
+    let admin = server.connect("admin");
+    assert_eq!(admin.count_accepted(0, i64::MAX).unwrap(), 1);
+
+    clerk.logout().unwrap();
+    assert_eq!(clerk.get(created.id).unwrap_err(), "Please log in");
+}
+
+#[test]
+fn sessions_follow_changes_to_their_account() {
+    let server = TestServer::start("server-accounts");
+    let (clerk, user) = RemoteRepository::connect(&server.address(), "clerk", PASSWORD).unwrap();
+    assert_eq!(
+        clerk.count_accepted(0, i64::MAX).unwrap_err(),
+        "You don't have permission to do this"
+    );
+
+    server
+        .pool
+        .with_writer(|conn| auth::update_user(conn, user.id, "clerk", Role::Supervisor))
+        .unwrap();
+    assert_eq!(clerk.count_accepted(0, i64::MAX).unwrap(), 0);
+
+    server
+        .pool
+        .with_writer(|conn| auth::delete_user(conn, user.id))
+        .unwrap();
+    assert_eq!(clerk.stats().unwrap_err(), "Please log in");
+}
+
+#[test]
+fn invalid_reports_keep_their_field_errors() {
+    let server = TestServer::start("server-validation");
+    let remote = server.connect("clerk");
+    match remote.create(report("", "Trần Thị Bình")) {
+        Err(CommandError::Invalid { fields }) => {
+            assert_eq!(fields.len(), 1);
+            assert_eq!(fields[0].field(), "plaintiff");
+        }
+        other => panic!("expected field errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn repeated_failed_logins_have_to_wait() {
+    let server = TestServer::start("server-login-delay");
+    for _ in 0..3 {
+        let login = RemoteRepository::connect(&server.address(), "admin", "wrong password");
+        assert_eq!(login.err().as_deref(), Some("Wrong username or password"));
+    }
+    // Even the right password is refused until the delay has passed.
+    let login = RemoteRepository::connect(&server.address(), "admin", PASSWORD);
+    assert!(login.err().unwrap().starts_with("Too many failed logins"));
+}
+
+#[test]
+fn tags_are_managed_through_the_server() {
+    let server = TestServer::start("server-tags");
+    let admin = server.connect("admin");
+    let clerk = server.connect("clerk");
+    let id = clerk
+        .create(report("Nguyễn Văn An", "Trần Thị Bình"))
+        .unwrap()
+        .id;
+
+    assert_eq!(
+        clerk.create_tag("Khẩn").unwrap_err(),
+        "You don't have permission to do this"
+    );
+    let urgent = admin.create_tag("Khẩn").unwrap();
+    clerk.tag(&[id], &[urgent.id]).unwrap();
+    assert_eq!(clerk.tags_of(id).unwrap()[0].name, "Khẩn");
+    assert_eq!(clerk.list_tags().unwrap()[0].count, 1);
+
+    let query = InformationPageQueryOption {
+        tags: vec![urgent.id],
+        ..Default::default()
+    };
+    let (found, _) = clerk.list(ListFilter::All, &query).unwrap();
+    assert_eq!(found.len(), 1);
+
+    admin.rename_tag(urgent.id, "Rất khẩn").unwrap();
+    clerk.untag(&[id], &[urgent.id]).unwrap();
+    assert!(clerk.tags_of(id).unwrap().is_empty());
+    admin.delete_tag(urgent.id).unwrap();
+    assert!(admin.list_tags().unwrap().is_empty());
+}
+
+#[test]
+fn notes_are_written_by_the_user_logged_in_on_the_server() {
+    let server = TestServer::start("server-notes");
+    let (admin, user) = RemoteRepository::connect(&server.address(), "admin", PASSWORD).unwrap();
+    let clerk = server.connect("clerk");
+    let id = admin
+        .create(report("Nguyễn Văn An", "Trần Thị Bình"))
+        .unwrap()
+        .id;
+    let someone_else = auth::User {
+        id: user.id + 100,
+        ..user.clone()
+    };
+
+    let written = admin
+        .add_note(&someone_else, id, "Đã mời bị hại lên làm việc", None)
+        .unwrap();
+    assert_eq!(written.author_id, user.id);
+    assert_eq!(
+        clerk.add_note(&user, id, "Ghi chú", None).unwrap_err(),
+        "You don't have permission to do this"
+    );
+    assert_eq!(
+        clerk.edit_note(&user, written.id, "Sửa").unwrap_err(),
+        "You can only edit your own notes"
+    );
+    admin
+        .edit_note(&someone_else, written.id, "Đã lấy lời khai bị hại")
+        .unwrap();
+    assert_eq!(
+        clerk.list_notes(id).unwrap()[0].text,
+        "Đã lấy lời khai bị hại"
+    );
+}
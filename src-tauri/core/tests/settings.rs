@@ -0,0 +1,53 @@
+use docman_core::{
+    db,
+    settings::{self, Settings},
+};
+use serde_json::json;
+
+fn with_deadlines(
+    settings: &Settings,
+    handling_days: i64,
+    extended_handling_days: i64,
+) -> Settings {
+    let mut json = serde_json::to_value(settings).unwrap();
+    json["deadlines"] = json!({
+        "handling_days": handling_days,
+        "extended_handling_days": extended_handling_days,
+    });
+    serde_json::from_value(json).unwrap()
+}
+
+#[test]
+fn deadlines_are_between_a_day_and_ten_years() {
+    let conn = db::open_in_memory().unwrap();
+    let settings = Settings::load(&conn).unwrap();
+
+    for (handling_days, extended_handling_days, error) in [
+        (0, 60, "Handling time must be at least one day"),
+        (
+            30,
+            20,
+            "Extended handling time must not be shorter than the handling time",
+        ),
+        (20, 3651, "Handling time must be at most 3650 days"),
+        (3651, 3651, "Handling time must be at most 3650 days"),
+        (20, i64::MAX, "Handling time must be at most 3650 days"),
+    ] {
+        assert_eq!(
+            settings::update(
+                &conn,
+                &with_deadlines(&settings, handling_days, extended_handling_days)
+            )
+            .unwrap_err(),
+            error
+        );
+    }
+    let stored = serde_json::to_value(Settings::load(&conn).unwrap()).unwrap();
+    assert_eq!(stored["deadlines"]["extended_handling_days"], 60);
+
+    let saved = settings::update(&conn, &with_deadlines(&settings, 1, 3650)).unwrap();
+    assert_eq!(
+        serde_json::to_value(saved).unwrap()["deadlines"],
+        json!({ "handling_days": 1, "extended_handling_days": 3650 })
+    );
+}
@@ -0,0 +1,271 @@
+use std::{
+    env, fs,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+mod common;
+
+use common::{admin, create};
+use docman_core::{
+    db,
+    event::{self, EventKind, NewEvent},
+    repository::{InformationRepository, SqliteRepository},
+    sync::{self, ConflictKind},
+    Information,
+};
+use rusqlite::{named_params, Connection};
+
+fn report(acceptance_no: &str, plaintiff: &str, defendant: &str) -> Information {
+    Information {
+        acceptance_no: acceptance_no.to_string(),
+        ..common::report(common::day(2023, 3, 1), plaintiff, defendant)
+    }
+}
+
+fn describe(conn: &Connection, id: i64, description: &str) {
+    let repository = SqliteRepository::new(conn);
+    let mut information = repository.get(id).unwrap().unwrap();
+    information.description = Some(description.to_string());
+    repository.update(&admin(), information).unwrap();
+}
+
+fn find(conn: &Connection, acceptance_no: &str) -> Option<Information> {
+    conn.query_row(
+        "SELECT id FROM information WHERE acceptance_no = :acceptance_no",
+        named_params! { ":acceptance_no": acceptance_no },
+        |row| row.get(0),
+    )
+    .ok()
+    .and_then(|id| SqliteRepository::new(conn).get(id).unwrap())
+}
+
+/// Timestamps have millisecond precision; changesets end just before the
+/// current one.
+fn tick() {
+    thread::sleep(Duration::from_millis(5));
+}
+
+/// Carries the changes of `from` made since its previous export to `to`.
+fn carry(from: &Connection, to: &Connection) -> sync::ImportReport {
+    static FILES: AtomicUsize = AtomicUsize::new(0);
+    tick();
+    let path = env::temp_dir().join(format!(
+        "docman-changeset-{}-{}.json",
+        std::process::id(),
+        FILES.fetch_add(1, Ordering::SeqCst)
+    ));
+    sync::export_to(from, &path, None).unwrap();
+    let report = sync::import_from(to, &path);
+    fs::remove_file(&path).unwrap();
+    report.unwrap()
+}
+
+#[test]
+fn changes_travel_between_installations() {
+    let unit = db::open_in_memory().unwrap();
+    let office = db::open_in_memory().unwrap();
+    let kept = create(&unit, report("1/DV", "Nguyễn Văn An", "Trần Thị Bình"));
+    let removed = create(&unit, report("2/DV", "Lê Văn Cường", "Phạm Văn Dũng"));
+
+    let report = carry(&unit, &office);
+    assert_eq!((report.created, report.updated, report.deleted), (2, 0, 0));
+    assert_eq!(find(&office, "2/DV").unwrap().plaintiff, "Lê Văn Cường");
+
+    tick();
+    describe(&unit, kept, "Mất xe máy");
+    SqliteRepository::new(&unit).delete(&[removed]).unwrap();
+    let report = carry(&unit, &office);
+    assert_eq!((report.created, report.updated, report.deleted), (0, 1, 1));
+    assert!(report.conflicts.is_empty());
+    assert_eq!(
+        find(&office, "1/DV").unwrap().description.as_deref(),
+        Some("Mất xe máy")
+    );
+    assert!(find(&office, "2/DV").is_none());
+
+    // Nothing changed since the last export.
+    let report = carry(&unit, &office);
+    assert_eq!((report.created, report.updated, report.deleted), (0, 0, 0));
+}
+
+#[test]
+fn events_travel_with_their_report() {
+    let unit = db::open_in_memory().unwrap();
+    let office = db::open_in_memory().unwrap();
+    let day = |day| common::day(2023, 4, day);
+    let id = create(
+        &unit,
+        Information {
+            inv_investigator: Some("Điều tra viên E".into()),
+            inv_designation_no: Some("12/QĐ".into()),
+            inv_designated_at: Some(day(1)),
+            ..report("1/DV", "Nguyễn Văn An", "Trần Thị Bình")
+        },
+    );
+    let extension = |occurred_at| NewEvent {
+        kind: EventKind::Extended,
+        occurred_at,
+        document_no: Some("20/QĐ-GH".into()),
+        note: Some("Lần đầu".into()),
+    };
+    let first = event::add(&unit, &admin(), id, extension(day(5))).unwrap();
+    carry(&unit, &office);
+    let office_id = find(&office, "1/DV").unwrap().id;
+    let events = event::list(&office, office_id).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].document_no.as_deref(), Some("20/QĐ-GH"));
+    assert_eq!(events[0].note.as_deref(), Some("Lần đầu"));
+
+    // A corrected event replaces the one imported before.
+    tick();
+    event::edit(&unit, &admin(), first.id, extension(day(3))).unwrap();
+    event::add(&unit, &admin(), id, extension(day(4))).unwrap();
+    carry(&unit, &office);
+    let dates: Vec<i64> = event::list(&office, office_id)
+        .unwrap()
+        .iter()
+        .map(|event| event.occurred_at)
+        .collect();
+    assert_eq!(dates.len(), 2);
+    assert!(dates.contains(&day(3)) && dates.contains(&day(4)));
+    assert_eq!(find(&office, "1/DV").unwrap().inv_extended_at, Some(day(4)));
+}
+
+#[test]
+fn changes_made_on_both_sides_are_reported() {
+    let unit = db::open_in_memory().unwrap();
+    let office = db::open_in_memory().unwrap();
+    let edited = create(&unit, report("1/DV", "Nguyễn Văn An", "Trần Thị Bình"));
+    let deleted = create(&unit, report("2/DV", "Lê Văn Cường", "Phạm Văn Dũng"));
+    carry(&unit, &office);
+
+    tick();
+    describe(&unit, edited, "Changed at the unit");
+    SqliteRepository::new(&unit).delete(&[deleted]).unwrap();
+    let office_edited = find(&office, "1/DV").unwrap().id;
+    let office_deleted = find(&office, "2/DV").unwrap().id;
+    tick();
+    describe(&office, office_edited, "Changed at the office");
+    describe(&office, office_deleted, "Still in progress");
+
+    let report = carry(&unit, &office);
+    assert_eq!((report.updated, report.deleted), (0, 0));
+    let kinds: Vec<ConflictKind> = report
+        .conflicts
+        .iter()
+        .map(|conflict| conflict.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        [ConflictKind::BothChanged, ConflictKind::DeletedThere]
+    );
+    let conflict = &report.conflicts[0];
+    assert_eq!(
+        conflict.remote.as_ref().unwrap().description.as_deref(),
+        Some("Changed at the unit")
+    );
+    assert_eq!(
+        find(&office, "1/DV").unwrap().description.as_deref(),
+        Some("Changed at the office")
+    );
+    assert!(find(&office, "2/DV").is_some());
+}
+
+#[test]
+fn taken_acceptance_numbers_and_own_changesets_are_not_applied() {
+    let unit = db::open_in_memory().unwrap();
+    let office = db::open_in_memory().unwrap();
+    create(&unit, report("1/DV", "Nguyễn Văn An", "Trần Thị Bình"));
+    create(&office, report("1/DV", "Lê Văn Cường", "Phạm Văn Dũng"));
+
+    let report = carry(&unit, &office);
+    assert_eq!(report.created, 0);
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(report.conflicts[0].kind, ConflictKind::AcceptanceNoTaken);
+    assert_eq!(find(&office, "1/DV").unwrap().plaintiff, "Lê Văn Cường");
+
+    let own = sync::collect(&office, 0).unwrap();
+    assert!(sync::apply(&office, &own).is_err());
+}
+
+const DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// Changesets from a source whose clock runs a day ahead of this one.
+fn shifted(conn: &Connection, since: i64) -> sync::Changeset {
+    let mut changeset = sync::collect(conn, since).unwrap();
+    changeset.until += DAY;
+    for record in &mut changeset.records {
+        record.modified_at += DAY;
+        record.information.created_at = record.information.created_at.map(|at| at + DAY);
+        record.information.updated_at = record.information.updated_at.map(|at| at + DAY);
+    }
+    changeset
+}
+
+#[test]
+fn changes_made_here_are_told_by_the_local_clock() {
+    let unit = db::open_in_memory().unwrap();
+    let office = db::open_in_memory().unwrap();
+    let id = create(&unit, report("1/DV", "Nguyễn Văn An", "Trần Thị Bình"));
+    tick();
+    let first = shifted(&unit, 0);
+    sync::apply(&office, &first).unwrap();
+
+    tick();
+    describe(
+        &office,
+        find(&office, "1/DV").unwrap().id,
+        "Changed at the office",
+    );
+    tick();
+    describe(&unit, id, "Changed at the unit");
+    tick();
+    let report = sync::apply(&office, &shifted(&unit, first.until - DAY)).unwrap();
+
+    assert_eq!(report.updated, 0);
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(report.conflicts[0].kind, ConflictKind::BothChanged);
+    assert_eq!(
+        find(&office, "1/DV").unwrap().description.as_deref(),
+        Some("Changed at the office")
+    );
+}
+
+#[test]
+fn imported_reports_keep_the_source_timestamps() {
+    let unit = db::open_in_memory().unwrap();
+    let office = db::open_in_memory().unwrap();
+    let branch = db::open_in_memory().unwrap();
+    let id = create(&unit, report("1/DV", "Nguyễn Văn An", "Trần Thị Bình"));
+    tick();
+    let first = shifted(&unit, 0);
+    sync::apply(&office, &first).unwrap();
+    sync::apply(&branch, &first).unwrap();
+
+    let imported = find(&office, "1/DV").unwrap();
+    assert_eq!(imported.created_at, first.records[0].information.created_at);
+    assert_eq!(imported.updated_at, first.records[0].information.updated_at);
+
+    // Carried on as imported, and seen as the same change elsewhere.
+    tick();
+    let carried = sync::collect(&office, 0).unwrap();
+    assert_eq!(carried.records.len(), 1);
+    assert_eq!(carried.records[0].modified_at, first.records[0].modified_at);
+    let report = sync::apply(&branch, &carried).unwrap();
+    assert_eq!(report.unchanged, 1);
+    assert!(report.conflicts.is_empty());
+
+    // Only once, though its timestamps are ahead of this clock.
+    let since = db::now();
+    tick();
+    assert!(sync::collect(&office, since).unwrap().records.is_empty());
+
+    // Not changed here since, so a later change of the source applies.
+    describe(&unit, id, "Changed at the unit");
+    tick();
+    let report = sync::apply(&office, &shifted(&unit, first.until - DAY)).unwrap();
+    assert_eq!(report.updated, 1);
+    assert!(report.conflicts.is_empty());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{create, day, report};
+use docman_core::{
+    db,
+    repository::{InformationRepository, ListFilter, SqliteRepository},
+    tag, InformationPageQueryOption,
+};
+use rusqlite::Connection;
+
+/// Ids of the reports carrying every tag of `tags`.
+fn tagged(conn: &Connection, tags: Vec<i64>) -> Vec<i64> {
+    let query = InformationPageQueryOption {
+        tags,
+        ..Default::default()
+    };
+    let (found, _) = SqliteRepository::new(conn)
+        .list(ListFilter::All, &query)
+        .unwrap();
+    let mut ids: Vec<i64> = found.iter().map(|info| info.id).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn list_filters_on_every_given_tag() {
+    let conn = db::open_in_memory().unwrap();
+    let both = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let urgent_only = create(&conn, report(day(2023, 3, 2), "Lê Văn C", "Phạm Văn D"));
+    create(&conn, report(day(2023, 3, 3), "Lê Văn C", "Trần Thị B"));
+    let urgent = tag::create(&conn, "Khẩn").unwrap();
+    let juvenile = tag::create(&conn, " Người chưa thành niên ").unwrap();
+    assert!(tag::create(&conn, "Khẩn").is_err());
+
+    tag::add(&conn, &[both, urgent_only], &[urgent.id]).unwrap();
+    tag::add(&conn, &[both], &[urgent.id, juvenile.id]).unwrap();
+
+    assert_eq!(tagged(&conn, vec![urgent.id]), vec![both, urgent_only]);
+    assert_eq!(tagged(&conn, vec![urgent.id, juvenile.id]), vec![both]);
+
+    tag::remove(&conn, &[both], &[juvenile.id]).unwrap();
+    assert!(tagged(&conn, vec![juvenile.id]).is_empty());
+    let names = tag::names_for(&conn, &[both, urgent_only]).unwrap();
+    assert_eq!(names[&both], "Khẩn");
+
+    // Deleting a report or a tag takes it off.
+    SqliteRepository::new(&conn).delete(&[urgent_only]).unwrap();
+    let tags = tag::list(&conn).unwrap();
+    assert_eq!(tags[0].name, "Khẩn");
+    assert_eq!(tags[0].count, 1);
+    tag::delete(&conn, urgent.id).unwrap();
+    assert!(tag::tags_of(&conn, both).unwrap().is_empty());
+}
+
+#[test]
+fn tags_given_twice_in_the_filter_count_once() {
+    let conn = db::open_in_memory().unwrap();
+    let both = create(&conn, report(day(2023, 3, 1), "Nguyễn Văn A", "Trần Thị B"));
+    let urgent_only = create(&conn, report(day(2023, 3, 2), "Lê Văn C", "Phạm Văn D"));
+    let urgent = tag::create(&conn, "Khẩn").unwrap();
+    let juvenile = tag::create(&conn, "Người chưa thành niên").unwrap();
+    tag::add(&conn, &[both, urgent_only], &[urgent.id, urgent.id]).unwrap();
+    tag::add(&conn, &[both], &[juvenile.id]).unwrap();
+
+    assert_eq!(
+        tagged(&conn, vec![urgent.id, urgent.id]),
+        vec![both, urgent_only]
+    );
+    assert_eq!(
+        tagged(&conn, vec![juvenile.id, urgent.id, juvenile.id]),
+        vec![both]
+    );
+    assert_eq!(tag::list(&conn).unwrap()[0].count, 2);
+}
@@ -0,0 +1,100 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+mod common;
+
+use common::{create, day, report};
+use docman_core::{
+    attachment,
+    pool::Pool,
+    vault::{self, Vault},
+};
+
+const PASSWORD: &str = "correct horse";
+const NEW_PASSWORD: &str = "battery staple";
+
+fn database(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("docman-vault-{}-{}.db", std::process::id(), name));
+    remove(&path);
+    path
+}
+
+fn remove(path: &Path) {
+    let _ = fs::remove_dir_all(attachment::store_path(path));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
+
+fn add_report(pool: &Pool, plaintiff: &str) {
+    pool.with_writer(|conn| -> Result<(), String> {
+        create(conn, report(day(2023, 3, 1), plaintiff, "Trần Thị B"));
+        Ok(())
+    })
+    .unwrap();
+}
+
+fn count(pool: &Pool) -> i64 {
+    pool.with_reader(|conn| {
+        conn.query_row("SELECT count(*) FROM information", [], |row| row.get(0))
+            .map_err(|err| err.to_string())
+    })
+    .unwrap()
+}
+
+#[test]
+fn databases_only_open_with_their_key() {
+    let path = database("open");
+    let pool = vault::open_pool(&path, PASSWORD).unwrap();
+    add_report(&pool, "Nguyễn Văn A");
+    drop(pool);
+
+    assert_eq!(
+        vault::open_pool(&path, NEW_PASSWORD).unwrap_err(),
+        "Wrong password"
+    );
+    assert_eq!(count(&vault::open_pool(&path, PASSWORD).unwrap()), 1);
+
+    remove(&path);
+}
+
+#[test]
+fn changing_the_password_rekeys_what_is_still_in_the_wal() {
+    let path = database("rekey");
+    let vault = Vault::new(path.clone());
+    let pool = vault.setup_password(PASSWORD).unwrap();
+    add_report(&pool, "Nguyễn Văn A");
+    add_report(&pool, "Lê Văn C");
+    // A reader opened under the old key, given back to the pool.
+    assert_eq!(count(&pool), 2);
+    assert!(
+        fs::metadata(format!("{}-wal", path.display()))
+            .unwrap()
+            .len()
+            > 0
+    );
+
+    assert_eq!(
+        vault
+            .change_password(&pool, NEW_PASSWORD, PASSWORD)
+            .unwrap_err(),
+        "Wrong password"
+    );
+    vault
+        .change_password(&pool, PASSWORD, NEW_PASSWORD)
+        .unwrap();
+    assert_eq!(vault.key().unwrap(), NEW_PASSWORD);
+    add_report(&pool, "Phạm Văn D");
+    assert_eq!(count(&pool), 3);
+    drop(pool);
+
+    assert_eq!(
+        vault::open_pool(&path, PASSWORD).unwrap_err(),
+        "Wrong password"
+    );
+    assert_eq!(count(&vault::open_pool(&path, NEW_PASSWORD).unwrap()), 3);
+
+    remove(&path);
+}
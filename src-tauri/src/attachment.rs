@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use docman_core::attachment::{self, Attachment, AttachmentMetadata};
+use tauri::Manager;
+
+use crate::{auth::Session, pool::Pool, remote::Remote};
+
+#[tauri::command]
+pub async fn list_attachments(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+) -> Result<Vec<Attachment>, String> {
+    remote.ensure_local()?;
+    session.current()?;
+    pool.read(move |conn| attachment::list(conn, information_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn add_attachment(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+    source_path: String,
+    metadata: AttachmentMetadata,
+) -> Result<Attachment, String> {
+    remote.ensure_local()?;
+    let user = session.current()?;
+    let source = PathBuf::from(source_path);
+    let file_name = source
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or("Invalid file name")?
+        .to_string();
+    attachment::check_file_name(&file_name)?;
+    let checked = user.clone();
+    let key = pool
+        .read(move |conn| {
+            attachment::check_information(conn, &checked, information_id)?;
+            attachment::store_key(conn)
+        })
+        .await?;
+    // Copied without holding a connection, scans can be large.
+    let stored =
+        tauri::async_runtime::spawn_blocking(move || attachment::store_file(&key, &source))
+            .await
+            .map_err(|_| "Fail to store file")??;
+
+    pool.write(move |conn| {
+        attachment::add(conn, &user, information_id, &file_name, stored, &metadata)
+    })
+    .await
+}
+
+/// Removes an attachment, see `attachment::remove` for who may.
+#[tauri::command]
+pub async fn remove_attachment(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    id: i64,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    let user = session.current()?;
+    pool.write(move |conn| attachment::remove(conn, &user, id))
+        .await
+}
+
+/// Opens a decrypted and verified copy of the attachment with the system's
+/// default application. The copies are removed on logout and when the app
+/// exits.
+#[tauri::command]
+pub async fn open_attachment(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    id: i64,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    session.current()?;
+    let (key, found) = pool
+        .read(move |conn| {
+            let key = attachment::store_key(conn)?;
+            attachment::get_attachment(conn, id).map(|found| (key, found))
+        })
+        .await?;
+    let copy =
+        tauri::async_runtime::spawn_blocking(move || attachment::temporary_copy(&key, &found))
+            .await
+            .map_err(|_| "Fail to copy file")??;
+    tauri::api::shell::open(&app.shell_scope(), copy.display().to_string(), None)
+        .map_err(|_| "Cannot open attachment".into())
+}
@@ -0,0 +1,179 @@
+use std::sync::Mutex;
+
+use docman_core::{
+    attachment,
+    auth::{self, NewUser, User},
+};
+
+use crate::{pool::Pool, remote::Remote};
+
+pub use docman_core::auth::{Role, MANAGERS, REGISTRARS};
+
+/// The user logged in on this app instance.
+#[derive(Default)]
+pub struct Session(Mutex<Option<User>>);
+
+impl Session {
+    pub fn current(&self) -> Result<User, String> {
+        self.0
+            .lock()
+            .map_err(|_| "Fail to read session")?
+            .clone()
+            .ok_or_else(|| "Please log in".into())
+    }
+
+    /// Returns the current user if their role is one of `roles`.
+    pub fn require(&self, roles: &[Role]) -> Result<User, String> {
+        let user = self.current()?;
+        if !roles.contains(&user.role) {
+            return Err("You don't have permission to do this".into());
+        }
+        Ok(user)
+    }
+
+    pub fn set(&self, user: Option<User>) -> Result<(), String> {
+        *self.0.lock().map_err(|_| "Fail to update session")? = user;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub async fn has_users(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+) -> Result<bool, String> {
+    remote.ensure_local()?;
+    pool.read(|conn| Ok(auth::user_count(conn)? > 0)).await
+}
+
+/// Creates the first account, always an admin, and logs it in. Only allowed
+/// while there are no users at all.
+#[tauri::command]
+pub async fn create_initial_admin(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    username: String,
+    full_name: String,
+    password: String,
+) -> Result<User, String> {
+    remote.ensure_local()?;
+    let user = pool
+        .write(move |conn| auth::create_initial_admin(conn, username, full_name, password))
+        .await?;
+    session.set(Some(user.clone()))?;
+    Ok(user)
+}
+
+#[tauri::command]
+pub async fn login(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    username: String,
+    password: String,
+) -> Result<User, String> {
+    remote.ensure_local()?;
+    let user = pool
+        .read(move |conn| auth::authenticate(conn, &username, &password))
+        .await?;
+    session.set(Some(user.clone()))?;
+    Ok(user)
+}
+
+/// Also removes the decrypted copies of the attachments opened during the
+/// session.
+#[tauri::command]
+pub fn logout(session: tauri::State<Session>) -> Result<(), String> {
+    attachment::remove_temporary_copies();
+    session.set(None)
+}
+
+#[tauri::command]
+pub fn get_current_user(session: tauri::State<Session>) -> Option<User> {
+    session.current().ok()
+}
+
+#[tauri::command]
+pub async fn change_own_password(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    old_password: String,
+    new_password: String,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    let user = session.current()?;
+    pool.write(move |conn| auth::change_password(conn, user.id, &old_password, &new_password))
+        .await
+}
+
+#[tauri::command]
+pub async fn list_users(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+) -> Result<Vec<User>, String> {
+    remote.ensure_local()?;
+    session.require(&[Role::Admin])?;
+    pool.read(auth::list_users).await
+}
+
+#[tauri::command]
+pub async fn create_user(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    user: NewUser,
+) -> Result<User, String> {
+    remote.ensure_local()?;
+    session.require(&[Role::Admin])?;
+    pool.write(move |conn| auth::create_user(conn, &user)).await
+}
+
+#[tauri::command]
+pub async fn update_user(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    id: i64,
+    full_name: String,
+    role: Role,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    let admin = session.require(&[Role::Admin])?;
+    if admin.id == id && role != Role::Admin {
+        return Err("You cannot remove your own administrator role".into());
+    }
+    pool.write(move |conn| auth::update_user(conn, id, &full_name, role))
+        .await
+}
+
+#[tauri::command]
+pub async fn reset_user_password(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    id: i64,
+    password: String,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    session.require(&[Role::Admin])?;
+    pool.write(move |conn| auth::reset_password(conn, id, &password))
+        .await
+}
+
+#[tauri::command]
+pub async fn delete_user(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    id: i64,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    let admin = session.require(&[Role::Admin])?;
+    if admin.id == id {
+        return Err("You cannot delete your own account".into());
+    }
+    pool.write(move |conn| auth::delete_user(conn, id)).await
+}
@@ -0,0 +1,101 @@
+use std::{path::Path, sync::Mutex, thread, time::Duration};
+
+use docman_core::{
+    backup::{self, BackupInfo},
+    vault::Vault,
+};
+use tauri::Manager;
+
+use crate::{auth, pool::Pool, remote::Remote};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Emitted to every window with the error when the daily backup fails.
+pub const FAILED_EVENT: &str = "backup-failed";
+
+/// Error of the last daily backup, until one succeeds, for the windows that
+/// were not listening when it was emitted.
+#[derive(Default)]
+pub struct BackupFailure(Mutex<Option<String>>);
+
+/// Starts the background thread taking the daily backup, checking hourly so an
+/// app left open overnight still gets one.
+pub fn spawn_daily_backup(app: tauri::AppHandle) {
+    thread::spawn(move || loop {
+        let failure = app
+            .state::<Vault>()
+            .key()
+            .and_then(|key| backup::run_daily_backup(&app.state::<Pool>(), &key))
+            .err();
+        if let Some(err) = &failure {
+            let _ = app.emit_all(FAILED_EVENT, err);
+        }
+        if let Ok(mut last) = app.state::<BackupFailure>().0.lock() {
+            *last = failure;
+        }
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub async fn backup_database(
+    vault: tauri::State<'_, Vault>,
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, auth::Session>,
+    path: String,
+) -> Result<String, String> {
+    remote.ensure_local()?;
+    session.require(auth::MANAGERS)?;
+    let key = vault.key()?;
+    pool.read(move |conn| {
+        backup::backup_to(conn, Path::new(&path), &key).map_err(|_| "Fail to back up database")?;
+        Ok(path)
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn list_backups(
+    vault: tauri::State<Vault>,
+    remote: tauri::State<Remote>,
+    session: tauri::State<auth::Session>,
+) -> Result<Vec<BackupInfo>, String> {
+    remote.ensure_local()?;
+    session.require(auth::MANAGERS)?;
+    backup::list_backups(vault.path())
+}
+
+/// Why the last daily backup failed, if it did.
+#[tauri::command]
+pub fn get_backup_failure(
+    failure: tauri::State<BackupFailure>,
+    remote: tauri::State<Remote>,
+    session: tauri::State<auth::Session>,
+) -> Result<Option<String>, String> {
+    remote.ensure_local()?;
+    session.require(auth::MANAGERS)?;
+    Ok(failure
+        .0
+        .lock()
+        .map_err(|_| "Fail to read backup status")?
+        .clone())
+}
+
+/// Replaces the live database with the backup at `path`, see
+/// `backup::restore`.
+#[tauri::command]
+pub async fn restore_backup(
+    vault: tauri::State<'_, Vault>,
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, auth::Session>,
+    path: String,
+    password: Option<String>,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    session.require(&[auth::Role::Admin])?;
+    let key = vault.key()?;
+    pool.write(move |conn| backup::restore(conn, Path::new(&path), password.as_deref(), &key))
+        .await
+}
@@ -0,0 +1,63 @@
+use docman_core::duplicate::{self, DuplicateCandidate, InformationLink, LinkKind};
+
+use crate::{
+    auth::{self, Session},
+    pool::Pool,
+    remote::Remote,
+};
+
+#[tauri::command]
+pub async fn find_duplicates(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+) -> Result<Vec<DuplicateCandidate>, String> {
+    remote.ensure_local()?;
+    session.current()?;
+    pool.read(move |conn| {
+        duplicate::find_candidates(conn, information_id)
+            .map_err(|_| "Fail to search duplicates".into())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_information_links(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+) -> Result<Vec<InformationLink>, String> {
+    remote.ensure_local()?;
+    session.current()?;
+    pool.read(move |conn| duplicate::list_links(conn, information_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn link_information(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+    linked_id: i64,
+    kind: LinkKind,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    let user = session.require(auth::REGISTRARS)?;
+    pool.write(move |conn| duplicate::link(conn, &user, information_id, linked_id, kind))
+        .await
+}
+
+#[tauri::command]
+pub async fn unlink_information(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    id: i64,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    session.require(auth::REGISTRARS)?;
+    pool.write(move |conn| duplicate::unlink(conn, id)).await
+}
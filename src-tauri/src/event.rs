@@ -0,0 +1,61 @@
+use docman_core::event::{self, InformationEvent, NewEvent};
+
+use crate::{auth::Session, pool::Pool, remote::Remote};
+
+#[tauri::command]
+pub async fn list_events(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+) -> Result<Vec<InformationEvent>, String> {
+    remote.ensure_local()?;
+    session.current()?;
+    pool.read(move |conn| event::list(conn, information_id))
+        .await
+}
+
+/// Records an extension, transfer, recovery or cancellation of an
+/// investigation; see `event::add`.
+#[tauri::command]
+pub async fn add_event(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+    event: NewEvent,
+) -> Result<InformationEvent, String> {
+    remote.ensure_local()?;
+    let user = session.current()?;
+    pool.write(move |conn| event::add(conn, &user, information_id, event))
+        .await
+}
+
+/// Corrects an event; only the user who recorded it or a supervisor may.
+#[tauri::command]
+pub async fn edit_event(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    id: i64,
+    event: NewEvent,
+) -> Result<InformationEvent, String> {
+    remote.ensure_local()?;
+    let user = session.current()?;
+    pool.write(move |conn| event::edit(conn, &user, id, event))
+        .await
+}
+
+/// Removes an event recorded by mistake; only the user who recorded it or a
+/// supervisor may.
+#[tauri::command]
+pub async fn remove_event(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    id: i64,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    let user = session.current()?;
+    pool.write(move |conn| event::remove(conn, &user, id)).await
+}
@@ -0,0 +1,63 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use docman_core::export::{self, ExportProgress, ExportSetting};
+use tauri::Manager;
+
+use crate::{
+    auth,
+    remote::{self, Remote},
+};
+
+const PROGRESS_EVENT: &str = "export-progress";
+
+/// Shared between `export_excel` and `cancel_export`; only one export runs at
+/// a time.
+#[derive(Default)]
+pub struct ExportState {
+    running: AtomicBool,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Writes the report on a blocking worker so other commands keep running,
+/// emitting `export-progress` events after every chunk. In client mode the
+/// server writes it and the single event comes once it is downloaded.
+#[tauri::command]
+pub async fn export_excel(
+    window: tauri::Window,
+    export_state: tauri::State<'_, ExportState>,
+    session: tauri::State<'_, auth::Session>,
+    setting: ExportSetting,
+) -> Result<String, String> {
+    session.require(auth::MANAGERS)?;
+    if export_state.running.swap(true, Ordering::SeqCst) {
+        return Err("Another export is in progress".into());
+    }
+    export_state.cancelled.store(false, Ordering::SeqCst);
+
+    let cancelled = export_state.cancelled.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let app = window.app_handle();
+        if let Some(remote) = app.state::<Remote>().current()? {
+            remote.export(&setting)?;
+            let _ = window.emit(PROGRESS_EVENT, ExportProgress { done: 1, total: 1 });
+            return Ok(setting.path().to_string());
+        }
+        let pool = remote::local_pool(&app)?;
+        export::write_workbook(&pool, &setting, &cancelled, |progress| {
+            let _ = window.emit(PROGRESS_EVENT, progress);
+        })
+        .map(|_| setting.path().to_string())
+    })
+    .await;
+    export_state.running.store(false, Ordering::SeqCst);
+
+    result.map_err(|_| String::from("Export was interrupted"))?
+}
+
+#[tauri::command]
+pub fn cancel_export(export_state: tauri::State<ExportState>) {
+    export_state.cancelled.store(true, Ordering::SeqCst);
+}
@@ -0,0 +1,234 @@
+use docman_core::{
+    assignment::{self, Assignment, AssignmentRecord},
+    error::CommandError,
+    repository::ListFilter,
+    Information, InformationCreated, InformationPageQueryOption, InformationPatch,
+};
+use tauri::Manager;
+
+mod attachment;
+mod auth;
+mod backup;
+mod duplicate;
+mod event;
+mod export;
+mod merge;
+mod note;
+mod numbering;
+mod party;
+mod person;
+mod pool;
+mod remote;
+mod server;
+mod settings;
+mod sync;
+mod tag;
+mod vault;
+
+#[tauri::command]
+async fn get_new_information_list(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, auth::Session>,
+    query_opt: InformationPageQueryOption,
+) -> Result<(Vec<Information>, Option<i64>), String> {
+    session.current()?;
+    remote::read(&app, move |repository| {
+        repository.list(ListFilter::Unassigned, &query_opt)
+    })
+    .await
+}
+
+#[tauri::command]
+async fn get_information_list(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, auth::Session>,
+    query_opt: InformationPageQueryOption,
+) -> Result<(Vec<Information>, Option<i64>), String> {
+    session.current()?;
+    remote::read(&app, move |repository| {
+        repository.list(ListFilter::All, &query_opt)
+    })
+    .await
+}
+
+#[tauri::command]
+async fn create_information(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, auth::Session>,
+    information: Information,
+) -> Result<InformationCreated, CommandError> {
+    session.require(auth::REGISTRARS)?;
+    remote::write(&app, move |repository| repository.create(information)).await
+}
+
+#[tauri::command]
+async fn update_information(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, auth::Session>,
+    information: Information,
+) -> Result<(), CommandError> {
+    let user = session.current()?;
+    remote::write(&app, move |repository| {
+        repository.update(&user, information)
+    })
+    .await
+}
+
+/// Changes only the fields given in `patch`; see `InformationPatch`.
+#[tauri::command]
+async fn patch_information(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, auth::Session>,
+    id: i64,
+    patch: InformationPatch,
+) -> Result<(), CommandError> {
+    let user = session.current()?;
+    remote::write(&app, move |repository| repository.patch(&user, id, patch)).await
+}
+
+/// Designates the same investigator or procurator for many reports, such as
+/// those of `get_new_information_list`, without sending their other fields.
+#[tauri::command]
+async fn assign_information(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, auth::Session>,
+    ids: Vec<i64>,
+    assignment: Assignment,
+) -> Result<(), CommandError> {
+    let user = session.require(auth::MANAGERS)?;
+    remote::write(&app, move |repository| {
+        repository.assign(&user, &ids, &assignment)
+    })
+    .await
+}
+
+#[tauri::command]
+async fn get_assignment_history(
+    pool: tauri::State<'_, pool::Pool>,
+    remote: tauri::State<'_, remote::Remote>,
+    session: tauri::State<'_, auth::Session>,
+    information_id: i64,
+) -> Result<Vec<AssignmentRecord>, String> {
+    remote.ensure_local()?;
+    session.current()?;
+    pool.read(move |conn| assignment::history(conn, information_id))
+        .await
+}
+
+#[tauri::command]
+async fn delete_information(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, auth::Session>,
+    ids: Vec<String>,
+) -> Result<(), String> {
+    session.require(auth::MANAGERS)?;
+    let ids = ids
+        .iter()
+        .map(|id| id.parse::<i64>())
+        .collect::<std::result::Result<Vec<i64>, _>>()
+        .map_err(|_| "Invalid information id")?;
+    remote::write(&app, move |repository| repository.delete(&ids)).await
+}
+
+/// Starts the app.
+pub fn run() {
+    let db_path = docman_core::vault::default_path().expect("Cannot get data dir");
+    // Attachments used to be kept in one folder for every database.
+    let _ = docman_core::attachment::adopt_shared_store(&db_path);
+    let vault = docman_core::vault::Vault::new(db_path);
+    // A connection to a server doesn't outlive the app, even one that crashed.
+    let _ = vault.set_connected_server(None);
+
+    // The connection pool is only opened and managed once the database is
+    // unlocked, see `vault::unlock_database`.
+    tauri::Builder::default()
+        .manage(vault)
+        .manage(export::ExportState::default())
+        .manage(backup::BackupFailure::default())
+        .manage(auth::Session::default())
+        .manage(remote::Remote::default())
+        .manage(server::ServerState::default())
+        .invoke_handler(tauri::generate_handler![
+            vault::get_database_status,
+            vault::setup_password,
+            vault::unlock_database,
+            vault::change_password,
+            auth::has_users,
+            auth::create_initial_admin,
+            auth::login,
+            auth::logout,
+            auth::get_current_user,
+            auth::change_own_password,
+            auth::list_users,
+            auth::create_user,
+            auth::update_user,
+            auth::reset_user_password,
+            auth::delete_user,
+            create_information,
+            get_information_list,
+            get_new_information_list,
+            update_information,
+            patch_information,
+            assign_information,
+            get_assignment_history,
+            delete_information,
+            export::export_excel,
+            export::cancel_export,
+            backup::backup_database,
+            backup::list_backups,
+            backup::get_backup_failure,
+            backup::restore_backup,
+            attachment::list_attachments,
+            attachment::add_attachment,
+            attachment::remove_attachment,
+            attachment::open_attachment,
+            party::list_parties,
+            party::add_party,
+            party::update_party,
+            party::remove_party,
+            party::search_parties,
+            person::find_person_reports,
+            duplicate::find_duplicates,
+            duplicate::list_information_links,
+            duplicate::link_information,
+            duplicate::unlink_information,
+            numbering::preview_acceptance_no,
+            settings::get_settings,
+            settings::update_settings,
+            server::start_server,
+            server::stop_server,
+            server::get_server_address,
+            remote::connect_server,
+            remote::disconnect_server,
+            remote::get_server_connection,
+            sync::export_changeset,
+            sync::import_changeset,
+            merge::preview_merge,
+            merge::merge_database,
+            tag::list_tags,
+            tag::create_tag,
+            tag::rename_tag,
+            tag::delete_tag,
+            tag::list_information_tags,
+            tag::tag_information,
+            tag::untag_information,
+            note::list_notes,
+            note::add_note,
+            note::edit_note,
+            event::list_events,
+            event::add_event,
+            event::edit_event,
+            event::remove_event
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                docman_core::attachment::remove_temporary_copies();
+                // The connection ends with the app.
+                let _ = app
+                    .state::<docman_core::vault::Vault>()
+                    .set_connected_server(None);
+            }
+        });
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use docman_core::{
+    merge::{self, MergeReport},
+    vault::Vault,
+};
+
+use crate::{auth, pool::Pool, remote::Remote};
+
+/// Compares the database at `path` with this one, see `merge_database`.
+/// `password` is the other database's, if it differs from this one's.
+#[tauri::command]
+pub async fn preview_merge(
+    vault: tauri::State<'_, Vault>,
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, auth::Session>,
+    path: String,
+    password: Option<String>,
+) -> Result<MergeReport, String> {
+    remote.ensure_local()?;
+    session.require(&[auth::Role::Admin])?;
+    let key = vault.key()?;
+    pool.write(move |conn| merge::preview(conn, Path::new(&path), password.as_deref(), &key))
+        .await
+}
+
+/// Adds the reports of the database at `path` that this one lacks. Reports
+/// with the same acceptance number but different content are returned, not
+/// merged.
+#[tauri::command]
+pub async fn merge_database(
+    vault: tauri::State<'_, Vault>,
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, auth::Session>,
+    path: String,
+    password: Option<String>,
+) -> Result<MergeReport, String> {
+    remote.ensure_local()?;
+    session.require(&[auth::Role::Admin])?;
+    let key = vault.key()?;
+    pool.write(move |conn| merge::merge(conn, Path::new(&path), password.as_deref(), &key))
+        .await
+}
@@ -0,0 +1,47 @@
+use docman_core::note::Note;
+
+use crate::{auth::Session, remote};
+
+#[tauri::command]
+pub async fn list_notes(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+) -> Result<Vec<Note>, String> {
+    session.current()?;
+    remote::read(&app, move |repository| {
+        repository.list_notes(information_id)
+    })
+    .await
+}
+
+/// Adds a note to a report, optionally about one of its attachments.
+#[tauri::command]
+pub async fn add_note(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+    text: String,
+    attachment_id: Option<i64>,
+) -> Result<Note, String> {
+    let user = session.current()?;
+    remote::write(&app, move |repository| {
+        repository.add_note(&user, information_id, &text, attachment_id)
+    })
+    .await
+}
+
+/// Corrects the text of a note written by the current user.
+#[tauri::command]
+pub async fn edit_note(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+    id: i64,
+    text: String,
+) -> Result<Note, String> {
+    let user = session.current()?;
+    remote::write(&app, move |repository| {
+        repository.edit_note(&user, id, &text)
+    })
+    .await
+}
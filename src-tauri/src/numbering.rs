@@ -0,0 +1,18 @@
+use docman_core::numbering;
+
+use crate::{auth::Session, pool::Pool, remote::Remote};
+
+/// Number the next report accepted at `accepted_at` would get, without
+/// allocating it.
+#[tauri::command]
+pub async fn preview_acceptance_no(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    accepted_at: i64,
+) -> Result<String, String> {
+    remote.ensure_local()?;
+    session.current()?;
+    pool.read(move |conn| numbering::preview(conn, accepted_at))
+        .await
+}
@@ -0,0 +1,74 @@
+use docman_core::party::{self, Party, PartyDetails, PartyMatch};
+
+use crate::{
+    auth::{self, Session},
+    pool::Pool,
+    remote::Remote,
+};
+
+#[tauri::command]
+pub async fn list_parties(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+) -> Result<Vec<Party>, String> {
+    remote.ensure_local()?;
+    session.current()?;
+    pool.read(move |conn| party::list(conn, information_id))
+        .await
+}
+
+#[tauri::command]
+pub async fn add_party(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+    party: PartyDetails,
+) -> Result<Party, String> {
+    remote.ensure_local()?;
+    session.require(auth::REGISTRARS)?;
+    pool.write(move |conn| party::add(conn, information_id, &party))
+        .await
+}
+
+#[tauri::command]
+pub async fn update_party(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    id: i64,
+    party: PartyDetails,
+) -> Result<Party, String> {
+    remote.ensure_local()?;
+    session.require(auth::REGISTRARS)?;
+    pool.write(move |conn| party::update(conn, id, &party))
+        .await
+}
+
+/// Removes a party, keeping at least one of each role, see `party::remove`.
+#[tauri::command]
+pub async fn remove_party(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    id: i64,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    session.require(auth::REGISTRARS)?;
+    pool.write(move |conn| party::remove(conn, id)).await
+}
+
+/// Finds parties by name or ID number across all reports.
+#[tauri::command]
+pub async fn search_parties(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    term: String,
+) -> Result<Vec<PartyMatch>, String> {
+    remote.ensure_local()?;
+    session.current()?;
+    pool.read(move |conn| party::search(conn, &term)).await
+}
@@ -0,0 +1,25 @@
+use docman_core::person::{self, PersonReport};
+
+use crate::{auth::Session, pool::Pool, remote::Remote};
+
+/// Lists every report in which a person with this name, or this ID number,
+/// appears as plaintiff or defendant.
+#[tauri::command]
+pub async fn find_person_reports(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    name: String,
+    id_number: Option<String>,
+) -> Result<Vec<PersonReport>, String> {
+    remote.ensure_local()?;
+    session.current()?;
+    if person::normalize_name(&name).is_empty() && id_number.is_none() {
+        return Ok(Vec::new());
+    }
+    pool.read(move |conn| {
+        person::find_reports(conn, &name, id_number.as_deref(), None)
+            .map_err(|_| "Fail to search persons".into())
+    })
+    .await
+}
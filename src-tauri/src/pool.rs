@@ -0,0 +1,50 @@
+use std::ops::Deref;
+
+use rusqlite::Connection;
+
+/// The core pool as managed by Tauri, with async versions of its methods for
+/// commands.
+pub struct Pool(docman_core::pool::Pool);
+
+impl From<docman_core::pool::Pool> for Pool {
+    fn from(pool: docman_core::pool::Pool) -> Self {
+        Pool(pool)
+    }
+}
+
+impl Deref for Pool {
+    type Target = docman_core::pool::Pool;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Pool {
+    /// Runs `f` on a read-only connection on the blocking thread pool, so that
+    /// commands don't hold up the async runtime while querying.
+    pub async fn read<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        T: Send + 'static,
+        E: From<String> + Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, E> + Send + 'static,
+    {
+        let pool = self.0.clone();
+        tauri::async_runtime::spawn_blocking(move || pool.with_reader(f))
+            .await
+            .map_err(|_| E::from("Database task was interrupted".to_string()))?
+    }
+
+    /// Runs `f` on the writer on the blocking thread pool.
+    pub async fn write<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        T: Send + 'static,
+        E: From<String> + Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, E> + Send + 'static,
+    {
+        let pool = self.0.clone();
+        tauri::async_runtime::spawn_blocking(move || pool.with_writer(f))
+            .await
+            .map_err(|_| E::from("Database task was interrupted".to_string()))?
+    }
+}
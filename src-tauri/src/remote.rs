@@ -0,0 +1,203 @@
+use std::{mem, sync::Mutex};
+
+use docman_core::{
+    auth::User,
+    remote::RemoteRepository,
+    repository::{InformationRepository, SqliteRepository},
+    vault::Vault,
+};
+use tauri::Manager;
+
+use crate::{auth::Session, pool::Pool};
+
+/// The server this app works against in client mode, instead of its own
+/// database.
+#[derive(Default)]
+pub struct Remote(Mutex<Option<RemoteRepository>>);
+
+impl Remote {
+    pub fn current(&self) -> Result<Option<RemoteRepository>, String> {
+        Ok(self
+            .0
+            .lock()
+            .map_err(|_| "Fail to read server connection")?
+            .clone())
+    }
+
+    /// Fails in client mode, for the commands that only work on the local
+    /// database: the ids they get come from the server's reports, and changes
+    /// to the local one would go unseen while the app shows the server's.
+    pub fn ensure_local(&self) -> Result<(), String> {
+        match self.current()? {
+            Some(_) => Err("Not available while connected to a server".into()),
+            None => Ok(()),
+        }
+    }
+
+    fn replace(
+        &self,
+        remote: Option<RemoteRepository>,
+    ) -> Result<Option<RemoteRepository>, String> {
+        let mut current = self
+            .0
+            .lock()
+            .map_err(|_| "Fail to update server connection")?;
+        Ok(mem::replace(&mut current, remote))
+    }
+}
+
+/// Requests to the server block, so they run on the blocking thread pool like
+/// the database ones.
+pub async fn blocking<T, E, F>(f: F) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<String> + Send + 'static,
+    F: FnOnce() -> Result<T, E> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|_| E::from("Server request was interrupted".to_string()))?
+}
+
+/// The pool of the local database, once unlocked. Commands available in client
+/// mode take it from here rather than as a `State` argument, which would fail
+/// before they run.
+pub fn local_pool(app: &tauri::AppHandle) -> Result<tauri::State<'_, Pool>, String> {
+    app.try_state::<Pool>()
+        .ok_or_else(|| "Database is locked".into())
+}
+
+/// Runs `f` on the reports of the server in client mode, or on a reader of the
+/// local database otherwise.
+pub async fn read<T, E, F>(app: &tauri::AppHandle, f: F) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<String> + Send + 'static,
+    F: FnOnce(&dyn InformationRepository) -> Result<T, E> + Send + 'static,
+{
+    match app.state::<Remote>().current()? {
+        Some(remote) => blocking(move || f(&remote)).await,
+        None => {
+            local_pool(app)?
+                .read(move |conn| f(&SqliteRepository::new(conn)))
+                .await
+        }
+    }
+}
+
+/// Same as `read`, on the writer of the local database.
+pub async fn write<T, E, F>(app: &tauri::AppHandle, f: F) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<String> + Send + 'static,
+    F: FnOnce(&dyn InformationRepository) -> Result<T, E> + Send + 'static,
+{
+    match app.state::<Remote>().current()? {
+        Some(remote) => blocking(move || f(&remote)).await,
+        None => {
+            local_pool(app)?
+                .write(move |conn| f(&SqliteRepository::new(conn)))
+                .await
+        }
+    }
+}
+
+/// Switches to client mode: logs in to the server at `address` as `username`,
+/// whose account is the server's, and works on its reports until
+/// `disconnect_server`.
+#[tauri::command]
+pub async fn connect_server(
+    vault: tauri::State<'_, Vault>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    address: String,
+    username: String,
+    password: String,
+) -> Result<User, String> {
+    if remote.current()?.is_some() {
+        return Err("Already connected to a server".into());
+    }
+    let (repository, user) =
+        blocking(move || RemoteRepository::connect(&address, &username, &password)).await?;
+    vault.set_connected_server(Some(repository.base_url()))?;
+    remote.replace(Some(repository))?;
+    session.set(Some(user.clone()))?;
+    Ok(user)
+}
+
+/// Leaves client mode, logging out of the server and of the app.
+#[tauri::command]
+pub async fn disconnect_server(
+    vault: tauri::State<'_, Vault>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+) -> Result<(), String> {
+    if let Some(repository) = remote.replace(None)? {
+        session.set(None)?;
+        vault.set_connected_server(None)?;
+        // The session ends on the server when it stops anyway.
+        let _ = blocking(move || repository.logout()).await;
+    }
+    Ok(())
+}
+
+/// URL of the server in client mode.
+#[tauri::command]
+pub fn get_server_connection(remote: tauri::State<Remote>) -> Result<Option<String>, String> {
+    Ok(remote
+        .current()?
+        .map(|repository| repository.base_url().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use docman_core::{
+        attachment,
+        auth::{self, NewUser, Role},
+        server::Server,
+        vault,
+    };
+
+    use super::*;
+
+    const PASSWORD: &str = "correct horse";
+
+    #[test]
+    fn local_only_commands_are_refused_while_connected() {
+        let path = env::temp_dir().join(format!("docman-client-mode-{}.db", std::process::id()));
+        let pool = vault::open_pool(&path, "client mode test key").unwrap();
+        pool.with_writer(|conn| {
+            auth::create_user(
+                conn,
+                &NewUser {
+                    username: "admin".to_string(),
+                    full_name: "Admin".to_string(),
+                    password: PASSWORD.to_string(),
+                    role: Role::Admin,
+                },
+            )
+        })
+        .unwrap();
+        let server = Server::start(pool, "127.0.0.1:0").unwrap();
+        let (repository, _) =
+            RemoteRepository::connect(&server.addr().to_string(), "admin", PASSWORD).unwrap();
+
+        let remote = Remote::default();
+        assert!(remote.ensure_local().is_ok());
+        remote.replace(Some(repository)).unwrap();
+        assert_eq!(
+            remote.ensure_local().unwrap_err(),
+            "Not available while connected to a server"
+        );
+        remote.replace(None).unwrap();
+        assert!(remote.ensure_local().is_ok());
+
+        drop(server);
+        let _ = fs::remove_dir_all(attachment::store_path(&path));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::Mutex;
+
+use docman_core::server::{self, Server};
+
+use crate::{auth, pool::Pool, remote::Remote};
+
+/// The server sharing this app's database with the LAN, while it runs.
+#[derive(Default)]
+pub struct ServerState(Mutex<Option<Server>>);
+
+/// Shares the unlocked database on every interface, `port` or 8750 by
+/// default, and returns the address listened on. The other PCs connect to
+/// this PC's network address on that port. The traffic is not encrypted, so
+/// the server should only run on a trusted office network.
+#[tauri::command]
+pub fn start_server(
+    state: tauri::State<ServerState>,
+    pool: tauri::State<Pool>,
+    remote: tauri::State<Remote>,
+    session: tauri::State<auth::Session>,
+    port: Option<u16>,
+) -> Result<String, String> {
+    remote.ensure_local()?;
+    session.require(&[auth::Role::Admin])?;
+    let mut running = state.0.lock().map_err(|_| "Fail to read server state")?;
+    if running.is_some() {
+        return Err("Server is already running".into());
+    }
+    let addr = format!("0.0.0.0:{}", port.unwrap_or(server::DEFAULT_PORT));
+    let server = Server::start(docman_core::pool::Pool::clone(&pool), &addr)?;
+    let addr = server.addr().to_string();
+    *running = Some(server);
+    Ok(addr)
+}
+
+/// Stops the server once the requests in progress are answered. Apps
+/// connected to it have to connect again after it restarts.
+#[tauri::command]
+pub fn stop_server(
+    state: tauri::State<ServerState>,
+    session: tauri::State<auth::Session>,
+) -> Result<(), String> {
+    session.require(&[auth::Role::Admin])?;
+    let server = state
+        .0
+        .lock()
+        .map_err(|_| "Fail to read server state")?
+        .take();
+    drop(server);
+    Ok(())
+}
+
+/// Address the server listens on, if it runs.
+#[tauri::command]
+pub fn get_server_address(state: tauri::State<ServerState>) -> Result<Option<String>, String> {
+    Ok(state
+        .0
+        .lock()
+        .map_err(|_| "Fail to read server state")?
+        .as_ref()
+        .map(|server| server.addr().to_string()))
+}
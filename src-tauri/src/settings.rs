@@ -0,0 +1,46 @@
+use docman_core::settings::{self, Settings};
+use tauri::Manager;
+
+use crate::{
+    auth::{self, Session},
+    pool::Pool,
+    remote::{self, blocking, Remote},
+};
+
+/// Emitted to every window with the new `Settings` after they are saved.
+pub const CHANGED_EVENT: &str = "settings-changed";
+
+/// The server's settings in client mode, so dates and the agency name match
+/// its reports.
+#[tauri::command]
+pub async fn get_settings(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+) -> Result<Settings, String> {
+    session.current()?;
+    if let Some(remote) = app.state::<Remote>().current()? {
+        return blocking(move || remote.settings()).await;
+    }
+    remote::local_pool(&app)?
+        .read(|conn| Settings::load(conn).map_err(|_| String::from("Fail to read settings")))
+        .await
+}
+
+/// Saves every setting at once and tells the windows about it. Not available
+/// in client mode, where the app shows the server's settings.
+#[tauri::command]
+pub async fn update_settings(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, Session>,
+    settings: Settings,
+) -> Result<Settings, String> {
+    remote.ensure_local()?;
+    session.require(&[auth::Role::Admin])?;
+    let saved = pool
+        .write(move |conn| settings::update(conn, &settings))
+        .await?;
+    let _ = app.emit_all(CHANGED_EVENT, &saved);
+    Ok(saved)
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use docman_core::sync::{self, ExportSummary, ImportReport};
+
+use crate::{auth, pool::Pool, remote::Remote};
+
+/// Writes the reports changed since `since`, or since the previous export, to
+/// a changeset file for another installation.
+#[tauri::command]
+pub async fn export_changeset(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, auth::Session>,
+    path: String,
+    since: Option<i64>,
+) -> Result<ExportSummary, String> {
+    remote.ensure_local()?;
+    session.require(auth::MANAGERS)?;
+    pool.write(move |conn| sync::export_to(conn, Path::new(&path), since))
+        .await
+}
+
+/// Applies a changeset from another installation, returning the conflicts to
+/// resolve by hand.
+#[tauri::command]
+pub async fn import_changeset(
+    pool: tauri::State<'_, Pool>,
+    remote: tauri::State<'_, Remote>,
+    session: tauri::State<'_, auth::Session>,
+    path: String,
+) -> Result<ImportReport, String> {
+    remote.ensure_local()?;
+    session.require(auth::MANAGERS)?;
+    pool.write(move |conn| sync::import_from(conn, Path::new(&path)))
+        .await
+}
@@ -0,0 +1,81 @@
+use docman_core::tag::Tag;
+
+use crate::{
+    auth::{self, Session},
+    remote,
+};
+
+#[tauri::command]
+pub async fn list_tags(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+) -> Result<Vec<Tag>, String> {
+    session.current()?;
+    remote::read(&app, |repository| repository.list_tags()).await
+}
+
+#[tauri::command]
+pub async fn create_tag(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+    name: String,
+) -> Result<Tag, String> {
+    session.require(auth::MANAGERS)?;
+    remote::write(&app, move |repository| repository.create_tag(&name)).await
+}
+
+#[tauri::command]
+pub async fn rename_tag(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+    id: i64,
+    name: String,
+) -> Result<Tag, String> {
+    session.require(auth::MANAGERS)?;
+    remote::write(&app, move |repository| repository.rename_tag(id, &name)).await
+}
+
+/// Deletes a tag, taking it off every report.
+#[tauri::command]
+pub async fn delete_tag(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+    id: i64,
+) -> Result<(), String> {
+    session.require(auth::MANAGERS)?;
+    remote::write(&app, move |repository| repository.delete_tag(id)).await
+}
+
+#[tauri::command]
+pub async fn list_information_tags(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+    information_id: i64,
+) -> Result<Vec<Tag>, String> {
+    session.current()?;
+    remote::read(&app, move |repository| repository.tags_of(information_id)).await
+}
+
+/// Puts the tags on every report of `ids`.
+#[tauri::command]
+pub async fn tag_information(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+    ids: Vec<i64>,
+    tag_ids: Vec<i64>,
+) -> Result<(), String> {
+    session.require(auth::REGISTRARS)?;
+    remote::write(&app, move |repository| repository.tag(&ids, &tag_ids)).await
+}
+
+/// Takes the tags off every report of `ids`.
+#[tauri::command]
+pub async fn untag_information(
+    app: tauri::AppHandle,
+    session: tauri::State<'_, Session>,
+    ids: Vec<i64>,
+    tag_ids: Vec<i64>,
+) -> Result<(), String> {
+    session.require(auth::REGISTRARS)?;
+    remote::write(&app, move |repository| repository.untag(&ids, &tag_ids)).await
+}
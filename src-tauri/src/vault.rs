@@ -0,0 +1,64 @@
+use docman_core::{
+    pool,
+    vault::{DatabaseStatus, Vault},
+};
+use tauri::Manager;
+
+use crate::{auth, backup, pool::Pool, remote::Remote};
+
+/// Hands the pool of the unlocked database to Tauri so the other commands can
+/// use it.
+fn manage(app: &tauri::AppHandle, pool: pool::Pool) {
+    app.manage(Pool::from(pool));
+    backup::spawn_daily_backup(app.clone());
+}
+
+#[tauri::command]
+pub fn get_database_status(vault: tauri::State<Vault>) -> Result<DatabaseStatus, String> {
+    vault.status()
+}
+
+/// First run: creates the encrypted database, or encrypts the existing
+/// plaintext one (and the backups taken before encryption) with `password`.
+#[tauri::command]
+pub fn setup_password(
+    app: tauri::AppHandle,
+    vault: tauri::State<Vault>,
+    password: String,
+) -> Result<(), String> {
+    let pool = vault.setup_password(&password)?;
+    manage(&app, pool);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unlock_database(
+    app: tauri::AppHandle,
+    vault: tauri::State<Vault>,
+    password: String,
+) -> Result<(), String> {
+    match vault.status()? {
+        DatabaseStatus::Locked => {
+            let pool = vault.unlock(&password)?;
+            manage(&app, pool);
+            Ok(())
+        }
+        DatabaseStatus::Unlocked => Ok(()),
+        _ => Err("Password is not set".into()),
+    }
+}
+
+/// Re-keys the database. Backups keep the password they were taken with.
+#[tauri::command]
+pub fn change_password(
+    vault: tauri::State<Vault>,
+    pool: tauri::State<Pool>,
+    remote: tauri::State<Remote>,
+    session: tauri::State<auth::Session>,
+    old_password: String,
+    new_password: String,
+) -> Result<(), String> {
+    remote.ensure_local()?;
+    session.require(&[auth::Role::Admin])?;
+    vault.change_password(&pool, &old_password, &new_password)
+}
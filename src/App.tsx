@@ -6,17 +6,42 @@ import { LocalizationProvider } from "@mui/x-date-pickers";
 import { AdapterMoment } from "@mui/x-date-pickers/AdapterMoment";
 import { SnackbarProvider } from "notistack";
 import { RouterProvider } from "react-router-dom";
+import { useEffect, useState } from "react";
+import { DatabaseStatus, getDatabaseStatus } from "@/services/database";
+import UnlockView from "@/views/unlock";
+import LoginView from "@/views/login";
+import { User } from "@/services/auth";
 
 const mdTheme = createTheme();
 
 const MAX_NOTIFICATION_STACK = 3;
 
 function App() {
+  const [status, setStatus] = useState<DatabaseStatus | null>(null);
+  const [user, setUser] = useState<User | null>(null);
+
+  useEffect(() => {
+    getDatabaseStatus().then(setStatus);
+  }, []);
+
   return (
     <LocalizationProvider dateAdapter={AdapterMoment}>
       <ThemeProvider theme={mdTheme}>
         <SnackbarProvider maxSnack={MAX_NOTIFICATION_STACK}>
-          <RouterProvider router={router} />
+          {status === DatabaseStatus.Unlocked ? (
+            user ? (
+              <RouterProvider router={router} />
+            ) : (
+              <LoginView onLoggedIn={setUser} />
+            )
+          ) : (
+            status && (
+              <UnlockView
+                status={status}
+                onUnlocked={() => setStatus(DatabaseStatus.Unlocked)}
+              />
+            )
+          )}
         </SnackbarProvider>
       </ThemeProvider>
     </LocalizationProvider>
@@ -36,6 +36,14 @@ export function useAppToast() {
         action,
       });
     },
+    showWarningToast: (config: ToastConfig) => {
+      const { title, action } = config;
+      enqueueSnackbar(title, {
+        ...toastDefaultConfig,
+        variant: "warning",
+        action,
+      });
+    },
     showInfoToast: (config: ToastConfig) => {
       const { title, action } = config;
       enqueueSnackbar(title, {
@@ -16,6 +16,8 @@ import MenuIcon from "@mui/icons-material/Menu";
 import ChevronLeftIcon from "@mui/icons-material/ChevronLeft";
 import NotificationsIcon from "@mui/icons-material/Notifications";
 import { RouteItems } from "@/routes";
+import { useAppToast } from "@/hook/toast";
+import { getBackupFailure, onBackupFailed } from "@/services/backup";
 
 const drawerWidth: number = 240;
 
@@ -76,6 +78,20 @@ const mdTheme = createTheme();
 
 function Layout({ children, name }: LayoutProps) {
   const [open, setOpen] = React.useState(true);
+  const { showFailToast } = useAppToast();
+
+  React.useEffect(() => {
+    const showFailure = (error: string) =>
+      showFailToast({ title: `Daily backup failed: ${error}` });
+    // A failure from before the window listened; only managers may read it.
+    getBackupFailure()
+      .then((error) => error && showFailure(error))
+      .catch(() => {});
+    const unlisten = onBackupFailed(showFailure);
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
   const toggleDrawer = () => {
     setOpen(!open);
   };
@@ -0,0 +1,36 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export enum AssignmentKind {
+  Investigator = "investigator",
+  Procurator = "procurator",
+}
+
+export type Assignment = {
+  kind: AssignmentKind;
+  assignee: string;
+  designation_no: string;
+  designated_at: number;
+};
+
+export type AssignmentRecord = Assignment & {
+  id: number;
+  information_id: number;
+  assigned_by: number | null;
+  assigned_at: number;
+};
+
+// Designates the same investigator or procurator for every report of `ids`.
+// Nothing is assigned if one of them can't be; the error names its acceptance
+// number.
+export async function assignInformation(
+  ids: number[],
+  assignment: Assignment
+): Promise<void> {
+  await invoke("assign_information", { ids, assignment });
+}
+
+export async function getAssignmentHistory(
+  informationId: number
+): Promise<AssignmentRecord[]> {
+  return invoke("get_assignment_history", { informationId });
+}
@@ -0,0 +1,47 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export enum DocumentType {
+  Report = "report",
+  DesignationDecision = "designation_decision",
+  ProcuracyRequest = "procuracy_request",
+  Other = "other",
+}
+
+export type Attachment = {
+  id: number;
+  information_id: number;
+  hash: string;
+  file_name: string;
+  size: number;
+  doc_type: DocumentType;
+  doc_date: number | null;
+  description: string | null;
+  created_by: number | null;
+  created_at: number;
+};
+
+export type AttachmentMetadata = {
+  doc_type: DocumentType;
+  doc_date: number | null;
+  description: string | null;
+};
+
+export async function listAttachments(informationId: number): Promise<Attachment[]> {
+  return invoke("list_attachments", { informationId });
+}
+
+export async function addAttachment(
+  informationId: number,
+  sourcePath: string,
+  metadata: AttachmentMetadata
+): Promise<Attachment> {
+  return invoke("add_attachment", { informationId, sourcePath, metadata });
+}
+
+export async function removeAttachment(id: number): Promise<void> {
+  await invoke("remove_attachment", { id });
+}
+
+export async function openAttachment(id: number): Promise<void> {
+  await invoke("open_attachment", { id });
+}
@@ -0,0 +1,82 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export enum Role {
+  Clerk = "clerk",
+  Investigator = "investigator",
+  Procurator = "procurator",
+  Supervisor = "supervisor",
+  Admin = "admin",
+}
+
+export type User = {
+  id: number;
+  username: string;
+  full_name: string;
+  role: Role;
+  created_at: number;
+};
+
+export type NewUser = {
+  username: string;
+  full_name: string;
+  password: string;
+  role: Role;
+};
+
+export async function hasUsers(): Promise<boolean> {
+  return invoke("has_users");
+}
+
+export async function createInitialAdmin(
+  username: string,
+  fullName: string,
+  password: string
+): Promise<User> {
+  return invoke("create_initial_admin", { username, fullName, password });
+}
+
+export async function login(username: string, password: string): Promise<User> {
+  return invoke("login", { username, password });
+}
+
+export async function logout(): Promise<void> {
+  await invoke("logout");
+}
+
+export async function getCurrentUser(): Promise<User | null> {
+  return invoke("get_current_user");
+}
+
+export async function changeOwnPassword(
+  oldPassword: string,
+  newPassword: string
+): Promise<void> {
+  await invoke("change_own_password", { oldPassword, newPassword });
+}
+
+export async function listUsers(): Promise<User[]> {
+  return invoke("list_users");
+}
+
+export async function createUser(user: NewUser): Promise<User> {
+  return invoke("create_user", { user });
+}
+
+export async function updateUser(
+  id: number,
+  fullName: string,
+  role: Role
+): Promise<void> {
+  await invoke("update_user", { id, fullName, role });
+}
+
+export async function resetUserPassword(
+  id: number,
+  password: string
+): Promise<void> {
+  await invoke("reset_user_password", { id, password });
+}
+
+export async function deleteUser(id: number): Promise<void> {
+  await invoke("delete_user", { id });
+}
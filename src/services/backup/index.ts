@@ -0,0 +1,14 @@
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/tauri";
+
+// Why the last daily backup failed, if it did. Managers only.
+export async function getBackupFailure(): Promise<string | null> {
+  return invoke("get_backup_failure");
+}
+
+// Called with the error whenever the daily backup fails.
+export function onBackupFailed(
+  handler: (error: string) => void
+): Promise<UnlistenFn> {
+  return listen<string>("backup-failed", (event) => handler(event.payload));
+}
@@ -4,8 +4,35 @@ import {
   ProcuracyInformation,
 } from "@/models/information";
 import { invoke } from "@tauri-apps/api/tauri";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import { KnownPerson } from "@/services/person";
+import { DuplicateCandidate } from "@/services/duplicate";
 
-export async function addNewCriminalInformation(information: Information) {
+export type InformationCreated = {
+  id: number;
+  acceptance_no: string;
+  warnings: KnownPerson[];
+  duplicates: DuplicateCandidate[];
+};
+
+export type FieldError = {
+  field: string;
+  message: string;
+};
+
+// Rejected input of `create_information`/`update_information`; other errors
+// are plain strings.
+export type ValidationError = {
+  fields: FieldError[];
+};
+
+export function isValidationError(err: unknown): err is ValidationError {
+  return typeof err === "object" && err !== null && "fields" in err;
+}
+
+export async function addNewCriminalInformation(
+  information: Information
+): Promise<InformationCreated> {
   return invoke("create_information", {
     information: {
       id: 0, // Seed ID
@@ -46,6 +73,10 @@ export async function addNewCriminalInformation(information: Information) {
   });
 }
 
+export async function previewAcceptanceNo(acceptedAt: Date): Promise<string> {
+  return invoke("preview_acceptance_no", { acceptedAt: acceptedAt.getTime() });
+}
+
 export async function updateInformation(information: Information) {
   return invoke("update_information", {
     information: {
@@ -97,6 +128,10 @@ export type QueryOption = {
   limit: number;
   order: Order;
   search: string | null;
+  // Ids of tags the listed reports must all carry.
+  tags?: number[];
+  // Only reports past their handling deadline (settings.deadlines).
+  overdue?: boolean;
 };
 
 export async function getInformationList(
@@ -204,15 +239,70 @@ export type SummarySetting = {
   from: Date;
   to: Date;
   path: string;
+  // Adds a column with the notes of each report.
+  notes?: boolean;
 };
 
 export async function exportExcel(setting: SummarySetting): Promise<string> {
-  const { from, to, path } = setting;
+  const { from, to, path, notes } = setting;
   return invoke("export_excel", {
     setting: {
       from: from.getTime(),
       to: to.getTime(),
       path,
+      notes: notes ?? false,
     },
   });
 }
+
+export type ExportProgress = {
+  done: number;
+  total: number;
+};
+
+export async function cancelExport(): Promise<void> {
+  await invoke("cancel_export");
+}
+
+export async function onExportProgress(
+  handler: (progress: ExportProgress) => void
+): Promise<UnlistenFn> {
+  return listen<ExportProgress>("export-progress", (event) =>
+    handler(event.payload)
+  );
+}
+
+// Fields of a report to change with `patchInformation`, named as stored. Left
+// out fields are unchanged; `null` clears a field that may be empty. A new
+// plaintiff or defendant renames the only party of that role.
+export type InformationPatch = {
+  acceptance_no?: string;
+  accepted_at?: number;
+  plaintiff?: string;
+  defendant?: string;
+  description?: string | null;
+  law?: string | null;
+  inv_investigator?: string | null;
+  inv_designation_no?: string | null;
+  inv_designated_at?: number | null;
+  inv_status?: number | null;
+  inv_handling_no?: string | null;
+  inv_handled_at?: number | null;
+  inv_transferred_at?: number | null;
+  inv_extended_at?: number | null;
+  inv_recovered_at?: number | null;
+  inv_canceled_at?: number | null;
+  pro_procurator?: string | null;
+  pro_designation_no?: string | null;
+  pro_designated_at?: number | null;
+  pro_additional_evidence_requirement?: string | null;
+  pro_non_prosecution_decision?: string | null;
+  pro_cessation_decision?: string | null;
+};
+
+export async function patchInformation(
+  id: number,
+  patch: InformationPatch
+): Promise<void> {
+  await invoke("patch_information", { id, patch });
+}
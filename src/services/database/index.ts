@@ -0,0 +1,27 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export enum DatabaseStatus {
+  Uninitialized = "Uninitialized",
+  Plaintext = "Plaintext",
+  Locked = "Locked",
+  Unlocked = "Unlocked",
+}
+
+export async function getDatabaseStatus(): Promise<DatabaseStatus> {
+  return invoke("get_database_status");
+}
+
+export async function setupPassword(password: string): Promise<void> {
+  await invoke("setup_password", { password });
+}
+
+export async function unlockDatabase(password: string): Promise<void> {
+  await invoke("unlock_database", { password });
+}
+
+export async function changePassword(
+  oldPassword: string,
+  newPassword: string
+): Promise<void> {
+  await invoke("change_password", { oldPassword, newPassword });
+}
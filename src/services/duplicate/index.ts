@@ -0,0 +1,49 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export type DuplicateCandidate = {
+  information_id: number;
+  acceptance_no: string;
+  accepted_at: number;
+  plaintiff: string;
+  defendant: string;
+  score: number;
+};
+
+export enum LinkKind {
+  Duplicate = "duplicate",
+  Related = "related",
+}
+
+export type InformationLink = {
+  id: number;
+  information_id: number;
+  acceptance_no: string;
+  accepted_at: number;
+  kind: LinkKind;
+  created_by: number | null;
+  created_at: number;
+};
+
+export async function findDuplicates(
+  informationId: number
+): Promise<DuplicateCandidate[]> {
+  return invoke("find_duplicates", { informationId });
+}
+
+export async function listInformationLinks(
+  informationId: number
+): Promise<InformationLink[]> {
+  return invoke("list_information_links", { informationId });
+}
+
+export async function linkInformation(
+  informationId: number,
+  linkedId: number,
+  kind: LinkKind
+): Promise<void> {
+  await invoke("link_information", { informationId, linkedId, kind });
+}
+
+export async function unlinkInformation(id: number): Promise<void> {
+  await invoke("unlink_information", { id });
+}
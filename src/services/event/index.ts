@@ -0,0 +1,49 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+// The report's `inv_*_at` date of each kind is the one of its latest event.
+export enum EventKind {
+  Extended = "extended",
+  Transferred = "transferred",
+  Recovered = "recovered",
+  Canceled = "canceled",
+}
+
+export type NewEvent = {
+  kind: EventKind;
+  occurred_at: number;
+  document_no: string | null;
+  note: string | null;
+};
+
+export type InformationEvent = NewEvent & {
+  id: number;
+  information_id: number;
+  created_by: number | null;
+  created_at: number;
+};
+
+export async function listEvents(
+  informationId: number
+): Promise<InformationEvent[]> {
+  return invoke("list_events", { informationId });
+}
+
+export async function addEvent(
+  informationId: number,
+  event: NewEvent
+): Promise<InformationEvent> {
+  return invoke("add_event", { informationId, event });
+}
+
+// Only the user who recorded an event, or a supervisor, may correct or
+// remove it.
+export async function editEvent(
+  id: number,
+  event: NewEvent
+): Promise<InformationEvent> {
+  return invoke("edit_event", { id, event });
+}
+
+export async function removeEvent(id: number): Promise<void> {
+  await invoke("remove_event", { id });
+}
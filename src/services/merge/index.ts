@@ -0,0 +1,33 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+import { ChangedInformation } from "../sync";
+
+export type MergeCollision = {
+  acceptance_no: string;
+  local: ChangedInformation;
+  other: ChangedInformation;
+};
+
+export type MergeReport = {
+  new: ChangedInformation[];
+  identical: number;
+  collisions: MergeCollision[];
+};
+
+// Compares another unit's database with this one without changing anything.
+// `password` is only needed if the other database has a different one.
+export async function previewMerge(
+  path: string,
+  password?: string
+): Promise<MergeReport> {
+  return invoke("preview_merge", { path, password });
+}
+
+// Adds the reports the other database has and this one lacks; reports with
+// the same acceptance number but different content are returned, not merged.
+export async function mergeDatabase(
+  path: string,
+  password?: string
+): Promise<MergeReport> {
+  return invoke("merge_database", { path, password });
+}
@@ -0,0 +1,31 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export type Note = {
+  id: number;
+  information_id: number;
+  author_id: number;
+  author_name: string | null;
+  text: string;
+  attachment_id: number | null;
+  attachment_name: string | null;
+  created_at: number;
+  // Set once the author has edited the note.
+  updated_at: number | null;
+};
+
+export async function listNotes(informationId: number): Promise<Note[]> {
+  return invoke("list_notes", { informationId });
+}
+
+export async function addNote(
+  informationId: number,
+  text: string,
+  attachmentId?: number
+): Promise<Note> {
+  return invoke("add_note", { informationId, text, attachmentId });
+}
+
+// Only the author of a note may edit it.
+export async function editNote(id: number, text: string): Promise<Note> {
+  return invoke("edit_note", { id, text });
+}
@@ -0,0 +1,44 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export enum PartyRole {
+  Plaintiff = "plaintiff",
+  Defendant = "defendant",
+}
+
+export type PartyDetails = {
+  role: PartyRole;
+  full_name: string;
+  birth_year: number | null;
+  id_number: string | null;
+  address: string | null;
+};
+
+export type Party = PartyDetails & {
+  id: number;
+  information_id: number;
+};
+
+export type PartyMatch = Party & {
+  acceptance_no: string;
+  accepted_at: number;
+};
+
+export async function listParties(informationId: number): Promise<Party[]> {
+  return invoke("list_parties", { informationId });
+}
+
+export async function addParty(informationId: number, party: PartyDetails): Promise<Party> {
+  return invoke("add_party", { informationId, party });
+}
+
+export async function updateParty(id: number, party: PartyDetails): Promise<Party> {
+  return invoke("update_party", { id, party });
+}
+
+export async function removeParty(id: number): Promise<void> {
+  await invoke("remove_party", { id });
+}
+
+export async function searchParties(term: string): Promise<PartyMatch[]> {
+  return invoke("search_parties", { term });
+}
@@ -0,0 +1,25 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export type PersonReport = {
+  information_id: number;
+  acceptance_no: string;
+  accepted_at: number;
+  role: string;
+  full_name: string;
+  id_number: string | null;
+  inv_status: number | null;
+  inv_investigator: string | null;
+  pro_procurator: string | null;
+};
+
+export type KnownPerson = {
+  full_name: string;
+  reports: PersonReport[];
+};
+
+export async function findPersonReports(
+  name: string,
+  idNumber?: string
+): Promise<PersonReport[]> {
+  return invoke("find_person_reports", { name, idNumber });
+}
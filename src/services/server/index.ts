@@ -0,0 +1,35 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+import { User } from "../auth";
+
+// Shares the unlocked database with the LAN; resolves to the address listened
+// on. Other PCs connect to this PC's network address on that port.
+export async function startServer(port?: number): Promise<string> {
+  return invoke("start_server", { port });
+}
+
+export async function stopServer(): Promise<void> {
+  await invoke("stop_server");
+}
+
+export async function getServerAddress(): Promise<string | null> {
+  return invoke("get_server_address");
+}
+
+// Client mode: reports are read and saved on the server at `address`
+// ("host:port") with an account of the server, until disconnected.
+export async function connectServer(
+  address: string,
+  username: string,
+  password: string
+): Promise<User> {
+  return invoke("connect_server", { address, username, password });
+}
+
+export async function disconnectServer(): Promise<void> {
+  await invoke("disconnect_server");
+}
+
+export async function getServerConnection(): Promise<string | null> {
+  return invoke("get_server_connection");
+}
@@ -0,0 +1,45 @@
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/tauri";
+
+export type DateSettings = {
+  timezone: string;
+  format: string;
+};
+
+export type NumberingScheme = {
+  pattern: string;
+  unit: string;
+};
+
+export type DeadlineSettings = {
+  handling_days: number;
+  extended_handling_days: number;
+};
+
+export type Settings = {
+  agency_name: string;
+  export_dir: string | null;
+  dates: DateSettings;
+  numbering: NumberingScheme;
+  deadlines: DeadlineSettings;
+};
+
+export async function getSettings(): Promise<Settings> {
+  return invoke("get_settings");
+}
+
+export async function updateSettings(settings: Settings): Promise<Settings> {
+  return invoke("update_settings", { settings });
+}
+
+// Called with the new settings whenever any window saves them.
+export function onSettingsChanged(
+  handler: (settings: Settings) => void
+): Promise<UnlistenFn> {
+  return listen<Settings>("settings-changed", (event) => handler(event.payload));
+}
+
+// Date formats are stored with Excel's lowercase tokens (dd/mm/yyyy).
+export function toMomentFormat(format: string): string {
+  return format.toUpperCase();
+}
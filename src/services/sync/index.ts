@@ -0,0 +1,57 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export type ExportSummary = {
+  records: number;
+  deletions: number;
+  since: number;
+  until: number;
+};
+
+export enum ConflictKind {
+  BothChanged = "both_changed",
+  DeletedThere = "deleted_there",
+  DeletedHere = "deleted_here",
+  AcceptanceNoTaken = "acceptance_no_taken",
+}
+
+// A report as stored by the backend, in either installation.
+export type ChangedInformation = {
+  id: number;
+  acceptance_no: string;
+  accepted_at: number;
+  plaintiff: string;
+  defendant: string;
+  description: string | null;
+  updated_at: number | null;
+  [column: string]: unknown;
+};
+
+export type Conflict = {
+  uid: string;
+  kind: ConflictKind;
+  local: ChangedInformation | null;
+  local_modified_at: number | null;
+  remote: ChangedInformation | null;
+  remote_modified_at: number;
+};
+
+export type ImportReport = {
+  created: number;
+  updated: number;
+  deleted: number;
+  unchanged: number;
+  conflicts: Conflict[];
+};
+
+// Writes the reports changed since `since` (milliseconds), or since the
+// previous export, to a changeset file for another installation.
+export async function exportChangeset(
+  path: string,
+  since?: number
+): Promise<ExportSummary> {
+  return invoke("export_changeset", { path, since });
+}
+
+export async function importChangeset(path: string): Promise<ImportReport> {
+  return invoke("import_changeset", { path });
+}
@@ -0,0 +1,47 @@
+import { invoke } from "@tauri-apps/api/tauri";
+
+export type Tag = {
+  id: number;
+  name: string;
+  // Number of reports carrying the tag.
+  count: number;
+  created_at: number;
+};
+
+export async function listTags(): Promise<Tag[]> {
+  return invoke("list_tags");
+}
+
+export async function createTag(name: string): Promise<Tag> {
+  return invoke("create_tag", { name });
+}
+
+export async function renameTag(id: number, name: string): Promise<Tag> {
+  return invoke("rename_tag", { id, name });
+}
+
+// Deletes a tag, taking it off every report.
+export async function deleteTag(id: number): Promise<void> {
+  await invoke("delete_tag", { id });
+}
+
+export async function listInformationTags(
+  informationId: number
+): Promise<Tag[]> {
+  return invoke("list_information_tags", { informationId });
+}
+
+// Puts every tag of `tagIds` on every report of `ids`.
+export async function tagInformation(
+  ids: number[],
+  tagIds: number[]
+): Promise<void> {
+  await invoke("tag_information", { ids, tagIds });
+}
+
+export async function untagInformation(
+  ids: number[],
+  tagIds: number[]
+): Promise<void> {
+  await invoke("untag_information", { ids, tagIds });
+}
@@ -1,4 +1,4 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 
 import {
   Information,
@@ -8,6 +8,8 @@ import {
 } from "@/models/information";
 import {
   addNewCriminalInformation,
+  isValidationError,
+  previewAcceptanceNo,
   updateInformation,
 } from "@/services/criminal-information";
 import { useAppToast } from "@/hook/toast";
@@ -34,6 +36,31 @@ type Props = {
   onClose: () => void;
 };
 
+const FIELD_LABELS: Record<string, string> = {
+  acceptance_no: "Số thụ lý",
+  accepted_at: "Ngày thụ lý",
+  plaintiff: "Nguyên đơn",
+  defendant: "Bị đơn",
+  law: "Điều luật",
+  description: "Nội dung",
+  inv_investigator: "Điều tra viên",
+  inv_designation_no: "Số PC",
+  inv_designated_at: "Ngày PC",
+  inv_status: "Trạng thái",
+  inv_handling_no: "Số xử lý",
+  inv_handled_at: "Ngày xử lý",
+  inv_transferred_at: "Ngày chuyển",
+  inv_extended_at: "Ngày gia hạn",
+  inv_recovered_at: "Ngày phục hồi",
+  inv_canceled_at: "Ngày hủy",
+  pro_procurator: "KSV thụ lý",
+  pro_designation_no: "Số QĐPC",
+  pro_designated_at: "Ngày QĐPC",
+  pro_additional_evidence_requirement: "Trao đổi/Yêu cầu BSCC",
+  pro_non_prosecution_decision: "Kết luận QĐKKT",
+  pro_cessation_decision: "Kết luận TĐC",
+};
+
 export function ViewInformationModal({ information, refresh, onClose }: Props) {
   const [haveInvestigation, setHaveInvestigation] = useState(
     information ? !!information.investigationInformation : true
@@ -106,7 +133,20 @@ export function ViewInformationModal({ information, refresh, onClose }: Props) {
     information?.procuracyInformation?.cessationDecision ?? ""
   );
   const [isError, setIsError] = useState(false);
-  const { showSuccessToast, showFailToast } = useAppToast();
+  // New reports left without a number get the next one of the numbering scheme.
+  const [nextAcceptanceNo, setNextAcceptanceNo] = useState("");
+
+  useEffect(() => {
+    if (information || !acceptedAt) {
+      setNextAcceptanceNo("");
+      return;
+    }
+    previewAcceptanceNo(acceptedAt)
+      .then(setNextAcceptanceNo)
+      .catch(() => setNextAcceptanceNo(""));
+  }, [information, acceptedAt]);
+  const { showSuccessToast, showFailToast, showWarningToast } =
+    useAppToast();
 
   const clear = () => {
     setHaveInvestigation(false);
@@ -156,10 +196,11 @@ export function ViewInformationModal({ information, refresh, onClose }: Props) {
               <Grid container spacing={4} columns={2} columnSpacing={5}>
                 <Grid item xs={1}>
                   <Box>
-                    <FieldLabel required content="Số thụ lý" />
+                    <FieldLabel required={!!information} content="Số thụ lý" />
                     <TextField
-                      required
-                      error={isError && acceptanceNo === ""}
+                      required={!!information}
+                      error={isError && !!information && acceptanceNo === ""}
+                      placeholder={nextAcceptanceNo}
                       value={acceptanceNo}
                       onChange={(event) => setAcceptanceNo(event.target.value)}
                     />
@@ -571,7 +612,7 @@ export function ViewInformationModal({ information, refresh, onClose }: Props) {
           color="primary"
           onClick={() => {
             if (
-              !acceptanceNo ||
+              (information && !acceptanceNo) ||
               !acceptedAt ||
               !plaintiff ||
               !defendant ||
@@ -624,7 +665,26 @@ export function ViewInformationModal({ information, refresh, onClose }: Props) {
                 investigationInfor,
                 prosecutionInfor
               );
-              promise = addNewCriminalInformation(newInfor);
+              promise = addNewCriminalInformation(newInfor).then(
+                ({ warnings, duplicates }) => {
+                  warnings.forEach((person) => {
+                    const reports = person.reports
+                      .map((report) => report.acceptance_no)
+                      .join(", ");
+                    showWarningToast({
+                      title: `${person.full_name} đã có trong tin báo: ${reports}`,
+                    });
+                  });
+                  if (duplicates.length > 0) {
+                    const reports = duplicates
+                      .map((candidate) => candidate.acceptance_no)
+                      .join(", ");
+                    showWarningToast({
+                      title: `Có thể trùng với tin báo: ${reports}`,
+                    });
+                  }
+                }
+              );
             } else {
               const updatingInfor = new Information(
                 information.id,
@@ -648,6 +708,14 @@ export function ViewInformationModal({ information, refresh, onClose }: Props) {
               })
               .catch((err) => {
                 console.error(err);
+                if (isValidationError(err)) {
+                  err.fields.forEach(({ field, message }) => {
+                    showFailToast({
+                      title: `${FIELD_LABELS[field] ?? field}: ${message}`,
+                    });
+                  });
+                  return;
+                }
                 showFailToast({ title: `${actionLabel} thất bại` });
               });
           }}
@@ -0,0 +1,80 @@
+import { Box, Button, Paper, TextField, Typography } from "@mui/material";
+import { useEffect, useState } from "react";
+import { createInitialAdmin, hasUsers, login, User } from "@/services/auth";
+
+type LoginViewProps = {
+  onLoggedIn: (user: User) => void;
+};
+
+const LoginView = ({ onLoggedIn }: LoginViewProps) => {
+  const [isFirstRun, setIsFirstRun] = useState<boolean | null>(null);
+  const [username, setUsername] = useState("");
+  const [fullName, setFullName] = useState("");
+  const [password, setPassword] = useState("");
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    hasUsers().then((exists) => setIsFirstRun(!exists));
+  }, []);
+
+  const submit = () => {
+    (isFirstRun
+      ? createInitialAdmin(username, fullName, password)
+      : login(username, password)
+    )
+      .then(onLoggedIn)
+      .catch((err) => setError(String(err)));
+  };
+
+  if (isFirstRun === null) return null;
+
+  return (
+    <Box
+      height="100vh"
+      display="flex"
+      alignItems="center"
+      justifyContent="center"
+    >
+      <Paper sx={{ p: 4, width: 400 }}>
+        <Typography variant="h6" mb={2}>
+          {isFirstRun ? "Tạo tài khoản quản trị" : "Đăng nhập"}
+        </Typography>
+        <TextField
+          label="Tên đăng nhập"
+          fullWidth
+          autoFocus
+          value={username}
+          onChange={(e) => setUsername(e.target.value)}
+        />
+        {isFirstRun && (
+          <TextField
+            sx={{ mt: 2 }}
+            label="Họ và tên"
+            fullWidth
+            value={fullName}
+            onChange={(e) => setFullName(e.target.value)}
+          />
+        )}
+        <TextField
+          sx={{ mt: 2 }}
+          type="password"
+          label="Mật khẩu"
+          fullWidth
+          value={password}
+          onChange={(e) => setPassword(e.target.value)}
+          onKeyDown={(e) => e.key === "Enter" && submit()}
+        />
+        {error && (
+          <Typography color="error" mt={2}>
+            {error}
+          </Typography>
+        )}
+        <Button sx={{ mt: 3 }} variant="contained" fullWidth onClick={submit}>
+          {isFirstRun ? "Tạo tài khoản" : "Đăng nhập"}
+        </Button>
+      </Paper>
+    </Box>
+  );
+};
+
+export default LoginView;
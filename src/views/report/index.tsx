@@ -1,14 +1,20 @@
 import DatePicker from "@components/date-picker";
 import { FieldLabel } from "@components/text-field";
-import { Box, Button } from "@mui/material";
+import { Box, Button, LinearProgress } from "@mui/material";
 import Grid from "@mui/material/Grid";
 import Paper from "@mui/material/Paper";
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { Moment } from "moment";
 import moment from "moment";
-import { exportExcel } from "@/services/criminal-information";
+import {
+  cancelExport,
+  exportExcel,
+  ExportProgress,
+  onExportProgress,
+} from "@/services/criminal-information";
 import { save } from "@tauri-apps/api/dialog";
 import { homeDir, join } from "@tauri-apps/api/path";
+import { getSettings } from "@/services/settings";
 import { useAppToast } from "@/hook/toast";
 import { open } from "@tauri-apps/api/shell";
 
@@ -16,8 +22,16 @@ const ReportView = () => {
   const [from, setFrom] = useState<Date | null>(null);
   const [to, setTo] = useState<Date | null>(null);
   const [isError, setIsError] = useState(false);
+  const [progress, setProgress] = useState<ExportProgress | null>(null);
   const { showSuccessToast, showFailToast } = useAppToast();
 
+  useEffect(() => {
+    const unlisten = onExportProgress(setProgress);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   return (
     <Grid container spacing={3}>
       <Grid item xs={12}>
@@ -62,6 +76,7 @@ const ReportView = () => {
                 color="info"
                 variant="contained"
                 fullWidth
+                disabled={progress !== null}
                 onClick={() => {
                   if (!from || !to) {
                     setIsError(true);
@@ -70,7 +85,8 @@ const ReportView = () => {
                   const fileName = `${Date.now()}-tin-bao-${moment(from).format(
                     "DDMMYY"
                   )}-${moment(to).format("DDMMYY")}.xlsx`;
-                  homeDir()
+                  getSettings()
+                    .then((settings) => settings.export_dir ?? homeDir())
                     .then((baseDirPath) => join(baseDirPath, fileName))
                     .then((fullPath) => save({ defaultPath: fullPath }))
                     .then((path) => {
@@ -102,12 +118,37 @@ const ReportView = () => {
                     .catch((err) => {
                       console.error(err);
                       showFailToast({ title: "Tạo báo cáo thất bại" });
-                    });
+                    })
+                    .finally(() => setProgress(null));
                 }}
               >
                 Tạo báo cáo
               </Button>
             </Grid>
+            {progress && (
+              <Grid item xs={5}>
+                <LinearProgress
+                  variant="determinate"
+                  value={
+                    progress.total > 0
+                      ? (progress.done / progress.total) * 100
+                      : 0
+                  }
+                />
+                <Box mt={1} display="flex" justifyContent="space-between">
+                  <span>
+                    {progress.done}/{progress.total}
+                  </span>
+                  <Button
+                    color="error"
+                    size="small"
+                    onClick={() => cancelExport()}
+                  >
+                    Hủy
+                  </Button>
+                </Box>
+              </Grid>
+            )}
           </Grid>
         </Paper>
       </Grid>
@@ -0,0 +1,74 @@
+import { Box, Button, Paper, TextField, Typography } from "@mui/material";
+import { useState } from "react";
+import {
+  DatabaseStatus,
+  setupPassword,
+  unlockDatabase,
+} from "@/services/database";
+
+type UnlockViewProps = {
+  status: DatabaseStatus;
+  onUnlocked: () => void;
+};
+
+const UnlockView = ({ status, onUnlocked }: UnlockViewProps) => {
+  const [password, setPassword] = useState("");
+  const [confirmation, setConfirmation] = useState("");
+  const [error, setError] = useState<string | null>(null);
+  const isSetup = status !== DatabaseStatus.Locked;
+
+  const submit = () => {
+    if (isSetup && password !== confirmation) {
+      setError("Mật khẩu xác nhận không khớp");
+      return;
+    }
+    (isSetup ? setupPassword(password) : unlockDatabase(password))
+      .then(onUnlocked)
+      .catch((err) => setError(String(err)));
+  };
+
+  return (
+    <Box
+      height="100vh"
+      display="flex"
+      alignItems="center"
+      justifyContent="center"
+    >
+      <Paper sx={{ p: 4, width: 400 }}>
+        <Typography variant="h6" mb={2}>
+          {isSetup ? "Tạo mật khẩu cơ sở dữ liệu" : "Mở khóa cơ sở dữ liệu"}
+        </Typography>
+        <TextField
+          type="password"
+          label="Mật khẩu"
+          fullWidth
+          autoFocus
+          value={password}
+          onChange={(e) => setPassword(e.target.value)}
+          onKeyDown={(e) => e.key === "Enter" && !isSetup && submit()}
+        />
+        {isSetup && (
+          <TextField
+            sx={{ mt: 2 }}
+            type="password"
+            label="Xác nhận mật khẩu"
+            fullWidth
+            value={confirmation}
+            onChange={(e) => setConfirmation(e.target.value)}
+            onKeyDown={(e) => e.key === "Enter" && submit()}
+          />
+        )}
+        {error && (
+          <Typography color="error" mt={2}>
+            {error}
+          </Typography>
+        )}
+        <Button sx={{ mt: 3 }} variant="contained" fullWidth onClick={submit}>
+          {isSetup ? "Tạo mật khẩu" : "Mở khóa"}
+        </Button>
+      </Paper>
+    </Box>
+  );
+};
+
+export default UnlockView;